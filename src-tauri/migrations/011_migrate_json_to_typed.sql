@@ -157,7 +157,7 @@ AND r.id NOT IN (SELECT resource_id FROM resource_figures);
 INSERT OR IGNORE INTO resource_commands (
     resource_id,
     name,
-    file_type_id,
+    command_type_id,
     content,
     description,
     built_in
@@ -203,7 +203,7 @@ AND r.id NOT IN (SELECT resource_id FROM resource_packages);
 INSERT OR IGNORE INTO resource_preambles (
     resource_id,
     name,
-    file_type_id,
+    preamble_type_id,
     content,
     description,
     built_in
@@ -0,0 +1,20 @@
+-- Migration 016: Full-text search index over resource content
+-- Content is filled in by the application when resources are added, imported or changed
+
+CREATE VIRTUAL TABLE IF NOT EXISTS resources_fts USING fts5(
+    resource_id UNINDEXED,
+    title,
+    content,
+    path
+);
+
+-- Seed existing resources with title and path; DatabaseManager::new reads their
+-- content from disk once this migration has run
+INSERT INTO resources_fts (resource_id, title, content, path)
+SELECT id, COALESCE(title, ''), '', path FROM resources;
+
+-- Keep the index in sync when resources are removed
+CREATE TRIGGER IF NOT EXISTS resources_fts_delete AFTER DELETE ON resources
+BEGIN
+    DELETE FROM resources_fts WHERE resource_id = old.id;
+END;
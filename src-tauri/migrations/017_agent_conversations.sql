@@ -0,0 +1,23 @@
+-- Migration 017: Agent conversations
+-- Stores complete agent transcripts, including tool calls and tool outputs, so a
+-- session can be resumed with the same context the model saw
+
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    created_at TEXT DEFAULT (datetime('now')),
+    updated_at TEXT DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS messages (
+    conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    role TEXT NOT NULL,
+    content TEXT,
+    tool_calls TEXT, -- JSON array of ToolCall
+    tool_call_id TEXT,
+    usage TEXT, -- JSON TokenUsage
+    PRIMARY KEY (conversation_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_conversations_updated ON conversations(updated_at);
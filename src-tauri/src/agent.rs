@@ -1,12 +1,14 @@
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::Arc;
 use tauri::Emitter;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 use crate::ai::{self, ProviderConfig};
 use crate::database::DatabaseManager;
-use crate::tools::ToolRegistry;
+use crate::tools::{ToolApprovals, ToolRegistry, Workspace};
 use crate::vectors::VectorStoreState;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +17,23 @@ pub struct AgentMessage {
     pub content: Option<String>,
     pub tool_calls: Option<Vec<ToolCall>>,
     pub tool_call_id: Option<String>, // For role="tool"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>, // Reported by the provider for assistant replies
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +53,8 @@ pub struct AgentState {
     pub tools: Arc<ToolRegistry>,
     pub config: ProviderConfig,
     pub is_running: bool,
+    /// Cancelled by `stop_agent_cmd` to abort an in-flight completion
+    pub cancel_token: CancellationToken,
 }
 
 impl AgentState {
@@ -42,6 +63,9 @@ impl AgentState {
         db_manager: Arc<Mutex<Option<DatabaseManager>>>,
         vector_store: Arc<VectorStoreState>,
         app_handle: tauri::AppHandle,
+        require_approval: bool,
+        shell_escape: crate::compiler::ShellEscape,
+        workspace: Workspace,
     ) -> Self {
         AgentState {
             messages: Vec::new(),
@@ -50,9 +74,13 @@ impl AgentState {
                 db_manager,
                 vector_store,
                 config.clone(),
+                require_approval,
+                shell_escape,
+                workspace,
             )),
             config,
             is_running: false,
+            cancel_token: CancellationToken::new(),
         }
     }
 
@@ -61,6 +89,115 @@ impl AgentState {
     }
 }
 
+/// Tool-calling rounds before the agent stops and reports the limit
+const DEFAULT_MAX_STEPS: u32 = 10;
+
+/// Estimated prompt tokens above which the oldest messages are dropped
+const DEFAULT_CONTEXT_BUDGET: usize = 96_000;
+
+/// Trailing messages that are never trimmed, so the current task stays intact
+const KEEP_RECENT_MESSAGES: usize = 8;
+
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a helpful AI assistant integrated into a LaTeX editor.
+You are connected to a Database of User Resources (files).
+CRITICAL RULES:
+1. The local filesystem (via `ls` or `run_terminal`) is the APPLICATION SOURCE, NOT the user's data. DO NOT use `ls`, `dir` or `find` to look for user files.
+2. You MUST use `find_resource(name, optional_collection)` to locate user files. If the user specifies a base/collection, pass it as the second argument.
+3. You MUST use `search_files(query)` to find files containing specific text.
+4. When modifying EXISTING files, you MUST use `apply_patch` for targeted changes or `propose_edit` to replace the whole file.
+5. Use `write_file` ONLY for creating NEW files (get path from `find_resource` or user input).
+6. After editing a LaTeX document, use `compile` to check it and fix any errors it reports.";
+
+/// Per-run overrides for the agent loop; unset fields use the defaults above
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentOptions {
+    #[serde(default)]
+    pub max_steps: Option<u32>,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Ask the user before `run_terminal` executes anything
+    #[serde(default)]
+    pub require_approval: bool,
+    /// The user's shell-escape setting for `compile`, which then asks before each run
+    #[serde(default)]
+    pub shell_escape: bool,
+    /// Directory the file tools may touch, normally the open project
+    #[serde(default)]
+    pub workspace_root: Option<String>,
+    /// Active collection, whose directory is the workspace when no project is open
+    #[serde(default)]
+    pub collection: Option<String>,
+    /// Estimated token budget for the conversation sent to the model
+    #[serde(default)]
+    pub context_budget: Option<usize>,
+}
+
+/// Payload of `agent-context-trimmed`
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextTrimmed {
+    pub removed_messages: usize,
+    pub estimated_tokens: usize,
+}
+
+/// Rough token count of a message: about four characters per token
+fn estimate_tokens(msg: &AgentMessage) -> usize {
+    let mut chars = msg.role.len() + msg.content.as_deref().map_or(0, str::len);
+    for call in msg.tool_calls.iter().flatten() {
+        chars += call.id.len() + call.function.name.len() + call.function.arguments.len();
+    }
+    chars.div_ceil(4)
+}
+
+/// Drop the oldest messages until the estimate fits `budget`, keeping a leading system
+/// prompt and the last `KEEP_RECENT_MESSAGES`. Tool results are removed together with
+/// the assistant message that requested them, and a short note marks the gap.
+/// Returns how many messages were removed.
+fn trim_context(messages: &mut Vec<AgentMessage>, budget: usize) -> usize {
+    let mut total: usize = messages.iter().map(estimate_tokens).sum();
+    if total <= budget {
+        return 0;
+    }
+
+    let start = usize::from(messages.first().is_some_and(|m| m.role == "system"));
+    let limit = messages.len().saturating_sub(KEEP_RECENT_MESSAGES);
+    let mut cut = start;
+    while total > budget && cut < limit {
+        // A tool result without its tool call is rejected by the providers, so a message
+        // goes together with the tool results after it; if those reach into the recent
+        // tail, trimming stops before the tool call instead
+        let mut end = cut + 1;
+        while end < messages.len() && messages[end].role == "tool" {
+            end += 1;
+        }
+        if end > limit {
+            break;
+        }
+        total -= messages[cut..end]
+            .iter()
+            .map(estimate_tokens)
+            .sum::<usize>();
+        cut = end;
+    }
+
+    let removed = cut - start;
+    if removed > 0 {
+        messages.splice(
+            start..cut,
+            [AgentMessage {
+                role: "user".to_string(),
+                content: Some(format!(
+                    "[{} earlier messages were removed to fit the context window]",
+                    removed
+                )),
+                tool_calls: None,
+                tool_call_id: None,
+                usage: None,
+            }],
+        );
+    }
+    removed
+}
+
 // Global Agent Wrapper
 pub struct GlobalAgent(pub Arc<Mutex<Option<AgentState>>>);
 
@@ -70,6 +207,7 @@ pub struct GlobalAgent(pub Arc<Mutex<Option<AgentState>>>);
 pub async fn start_agent_cmd(
     chat_history: Vec<AgentMessage>,
     config: ProviderConfig,
+    options: Option<AgentOptions>,
     state: tauri::State<'_, GlobalAgent>,
     app_handle: tauri::AppHandle,
     // Extract State
@@ -82,43 +220,83 @@ pub async fn start_agent_cmd(
     );
     println!("[AGENT] Config: {:?}", config);
 
+    let options = options.unwrap_or_default();
+
     // 1. Initialize State
     let mut agent_guard = state.0.lock().await;
 
+    // A new run replaces any previous one
+    if let Some(previous) = agent_guard.as_ref() {
+        previous.cancel_token.cancel();
+    }
+
     // Inject dependencies
     let db_manager = app_state.db_manager.clone();
     // Clone the VectorStoreState struct (wrapper around Arc)
     let vector_store_state_inner = vector_state.0.clone(); // This is Arc<Mutex<VectorStore>>
     let vector_store_arc = Arc::new(VectorStoreState(vector_store_state_inner)); // Re-wrap in VectorStoreState struct
 
+    let workspace_root = match options.workspace_root.as_deref() {
+        Some(root) if !root.is_empty() => Some(std::path::PathBuf::from(root)),
+        _ => match options.collection.as_deref() {
+            Some(collection) if !collection.is_empty() => {
+                let db_guard = db_manager.lock().await;
+                let db = db_guard.as_ref().ok_or("Database not initialized")?;
+                db.collection_root(collection).await?
+            }
+            _ => None,
+        },
+    };
+    // Without a project or collection the agent can still chat; file tools refuse every path
+    let workspace = match workspace_root {
+        Some(root) => Workspace::new(&root)?,
+        None => Workspace::none(),
+    };
+
     // Create new agent with dependencies
-    let mut agent = AgentState::new(config, db_manager, vector_store_arc, app_handle.clone());
+    let mut agent = AgentState::new(
+        config,
+        db_manager,
+        vector_store_arc,
+        app_handle.clone(),
+        options.require_approval,
+        crate::compiler::ShellEscape::from_flags(options.shell_escape, false),
+        workspace,
+    );
     agent.is_running = true;
 
-    // System Prompt (could be passed in, simpler for now)
+    // System Prompt
+    let workspace_note = match agent.tools.workspace_root() {
+        Some(root) => format!(
+            "File tools are restricted to the workspace {}. Relative paths are resolved from there.",
+            root.display()
+        ),
+        None => "No project or collection is open, so file tools are unavailable.".to_string(),
+    };
+    let system_prompt = format!(
+        "{}\n\n{}",
+        options
+            .system_prompt
+            .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string()),
+        workspace_note
+    );
     agent.add_message(AgentMessage {
         role: "system".to_string(),
-        content: Some("You are a helpful AI assistant integrated into a LaTeX editor.
-You are connected to a Database of User Resources (files).
-CRITICAL RULES:
-1. The local filesystem (via `ls` or `run_terminal`) is the APPLICATION SOURCE, NOT the user's data. DO NOT use `ls`, `dir` or `find` to look for user files.
-2. You MUST use `find_resource(name, optional_collection)` to locate user files. If the user specifies a base/collection, pass it as the second argument.
-3. You MUST use `search_files(query)` to find files containing specific text.
-4. When modifying EXISTING files, you MUST use `propose_edit`.
-5. Use `write_file` ONLY for creating NEW files (get path from `find_resource` or user input).".to_string()),
+        content: Some(system_prompt),
         tool_calls: None,
         tool_call_id: None,
+        usage: None,
     });
 
     // Add User History
-    // Note: The history likely contains User/Assistant turns.
-    // Ideally, we should filter out Tool calls if they were not persisted properly, but frontend only tracks User/Assistant for now.
-    // If we want to support persistent Tool outputs, we need frontend to store them.
-    // For now, assume history is just text conversation.
-    for msg in chat_history {
+    // The chat panel only sends text turns; a conversation restored with
+    // `load_conversation_cmd` also carries its tool calls and outputs.
+    // System messages are dropped since the prompt above replaces them.
+    for msg in chat_history.into_iter().filter(|m| m.role != "system") {
         agent.add_message(msg);
     }
 
+    let cancel_token = agent.cancel_token.clone();
     *agent_guard = Some(agent);
     drop(agent_guard); // Release lock while thinking
 
@@ -136,16 +314,26 @@ CRITICAL RULES:
         let state_arc = state_clone;
         let app_handle = app_handle_clone; // Use the captured app_handle
 
-        let max_steps = 10;
+        let max_steps = options.max_steps.unwrap_or(DEFAULT_MAX_STEPS);
+        let context_budget = options.context_budget.unwrap_or(DEFAULT_CONTEXT_BUDGET);
         let mut current_step = 0;
+        let mut run_usage = TokenUsage::default();
 
         loop {
             // 1. Check if running and Loop Limit
             {
                 let guard = state_arc.lock().await;
                 if let Some(agent) = &*guard {
-                    if !agent.is_running || current_step >= max_steps {
-                        let _ = app_handle.emit("agent-finished", "Top limit reached or stopped");
+                    if !agent.is_running || cancel_token.is_cancelled() {
+                        let _ = app_handle.emit("agent-finished", "cancelled");
+                        break;
+                    }
+                    // Reported as an error so the UI can offer to continue
+                    if current_step >= max_steps {
+                        let _ = app_handle.emit(
+                            "agent-error",
+                            format!("Step limit reached ({} steps)", max_steps),
+                        );
                         break;
                     }
                 } else {
@@ -156,8 +344,19 @@ CRITICAL RULES:
 
             // A. Get Next Step
             let (messages, tools_registry, config) = {
-                let guard = state_arc.lock().await;
-                if let Some(agent) = guard.as_ref() {
+                let mut guard = state_arc.lock().await;
+                if let Some(agent) = guard.as_mut() {
+                    // Shrink long sessions before the provider rejects them
+                    let removed_messages = trim_context(&mut agent.messages, context_budget);
+                    if removed_messages > 0 {
+                        let _ = app_handle.emit(
+                            "agent-context-trimmed",
+                            ContextTrimmed {
+                                removed_messages,
+                                estimated_tokens: agent.messages.iter().map(estimate_tokens).sum(),
+                            },
+                        );
+                    }
                     (
                         agent.messages.clone(),
                         agent.tools.clone(), // Clone the Arc<ToolRegistry>
@@ -173,13 +372,24 @@ CRITICAL RULES:
 
             // B. Call AI
             let tool_defs = tools_registry.get_definitions();
-            let response_result = ai::chat(&messages, &tool_defs, &config)
-                .await
-                .map_err(|e| e.to_string());
+            // Dropping the request future aborts the HTTP call when the run is cancelled
+            let response_result = tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    let _ = app_handle.emit("agent-finished", "cancelled");
+                    break;
+                }
+                result = ai::chat_stream(&messages, &tool_defs, &config, |token| {
+                    let _ = app_handle.emit("agent-token", token);
+                }) => result.map_err(|e| e.to_string()),
+            };
 
             // C. Handle Response
             match response_result {
                 Ok(response_msg) => {
+                    if let Some(usage) = &response_msg.usage {
+                        run_usage.add(usage);
+                    }
+
                     // Add assistant message to state
                     {
                         let mut guard = state_arc.lock().await;
@@ -212,33 +422,17 @@ CRITICAL RULES:
                                 ),
                             );
 
-                            // Execute Tools
-                            for tool_call in calls {
-                                let tool_name = &tool_call.function.name;
-                                let args_str = &tool_call.function.arguments;
-                                let call_id = &tool_call.id;
-
-                                let output = {
-                                    let args_res: Result<Value, _> = serde_json::from_str(args_str);
-                                    match args_res {
-                                        Ok(args) => {
-                                            // Get Tool from the cloned registry
-                                            if let Some(tool) = tools_registry.get(tool_name) {
-                                                match tool.execute(args).await {
-                                                    Ok(out) => out,
-                                                    Err(e) => format!("Error: {}", e),
-                                                }
-                                            } else {
-                                                format!("Error: Tool '{}' not found", tool_name)
-                                            }
-                                        }
-                                        Err(e) => format!(
-                                            "Error parsing arguments for tool '{}': {}",
-                                            tool_name, e
-                                        ),
-                                    }
-                                };
+                            // Execute Tools. Stopping drops the calls still running, which
+                            // kills terminal commands and abandons pending approvals.
+                            let outputs = tokio::select! {
+                                _ = cancel_token.cancelled() => {
+                                    let _ = app_handle.emit("agent-finished", "cancelled");
+                                    break;
+                                }
+                                outputs = execute_tool_calls(&tools_registry, calls) => outputs,
+                            };
 
+                            for (tool_call, output) in calls.iter().zip(outputs) {
                                 // Add Tool Output Message
                                 {
                                     let mut guard = state_arc.lock().await;
@@ -247,7 +441,8 @@ CRITICAL RULES:
                                             role: "tool".to_string(),
                                             content: Some(output.clone()),
                                             tool_calls: None,
-                                            tool_call_id: Some(call_id.clone()),
+                                            tool_call_id: Some(tool_call.id.clone()),
+                                            usage: None,
                                         });
                                     }
                                 }
@@ -281,17 +476,68 @@ CRITICAL RULES:
                 }
             }
         }
+
+        // Totals for the whole run, however it ended
+        let _ = app_handle.emit("agent-usage", run_usage);
     });
 
     Ok(())
 }
 
+/// Run one tool call; failures are returned as text so the model can react to them
+async fn execute_tool_call(tools: &ToolRegistry, tool_call: &ToolCall) -> String {
+    let tool_name = &tool_call.function.name;
+    let args: Value = match serde_json::from_str(&tool_call.function.arguments) {
+        Ok(args) => args,
+        Err(e) => {
+            return format!("Error parsing arguments for tool '{}': {}", tool_name, e);
+        }
+    };
+
+    match tools.get(tool_name) {
+        Some(tool) => match tool.execute(args).await {
+            Ok(out) => out,
+            Err(e) => format!("Error: {}", e),
+        },
+        None => format!("Error: Tool '{}' not found", tool_name),
+    }
+}
+
+/// Run a turn's tool calls, returning their outputs in call order. Consecutive
+/// read-only calls run concurrently; any other call waits for the ones before it and
+/// finishes before later ones start, so reads still see earlier writes.
+async fn execute_tool_calls(tools: &ToolRegistry, calls: &[ToolCall]) -> Vec<String> {
+    let mut outputs = Vec::with_capacity(calls.len());
+    let mut batch = Vec::new();
+
+    for tool_call in calls {
+        if tools
+            .get(&tool_call.function.name)
+            .is_some_and(|tool| tool.parallelizable())
+        {
+            batch.push(execute_tool_call(tools, tool_call));
+            continue;
+        }
+        outputs.extend(join_all(batch.drain(..)).await);
+        outputs.push(execute_tool_call(tools, tool_call).await);
+    }
+    outputs.extend(join_all(batch).await);
+
+    outputs
+}
+
 #[tauri::command]
-pub async fn stop_agent_cmd(state: tauri::State<'_, GlobalAgent>) -> Result<(), String> {
+pub async fn stop_agent_cmd(
+    state: tauri::State<'_, GlobalAgent>,
+    approvals: tauri::State<'_, ToolApprovals>,
+) -> Result<(), String> {
     let mut agent_opt = state.0.lock().await;
     if let Some(agent) = agent_opt.as_mut() {
         agent.is_running = false;
+        agent.cancel_token.cancel();
     }
+    // Dropping the senders denies every request still waiting for the user
+    approvals.0.lock().map_err(|e| e.to_string())?.clear();
     Ok(())
 }
 
@@ -414,6 +660,7 @@ async fn run_agent_loop(state_arc: Arc<Mutex<Option<AgentState>>>, app_handle: t
                                         content: Some(output.clone()),
                                         tool_calls: None,
                                         tool_call_id: Some(call_id.clone()),
+                                        usage: None,
                                     });
                                 }
                             }
@@ -446,3 +693,65 @@ async fn run_agent_loop(state_arc: Arc<Mutex<Option<AgentState>>>, app_handle: t
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, chars: usize) -> AgentMessage {
+        AgentMessage {
+            role: role.to_string(),
+            content: Some("x".repeat(chars)),
+            tool_calls: None,
+            tool_call_id: None,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn trims_oldest_messages_with_their_tool_results() {
+        let mut messages = vec![
+            message("system", 40),
+            message("user", 400),
+            message("assistant", 400),
+            message("tool", 400),
+            message("tool", 400),
+            message("assistant", 400),
+        ];
+        messages.extend((0..KEEP_RECENT_MESSAGES).map(|_| message("user", 40)));
+
+        assert_eq!(trim_context(&mut messages, 200), 5);
+        assert_eq!(messages.len(), 2 + KEEP_RECENT_MESSAGES);
+        assert_eq!(messages[0].role, "system");
+        assert!(messages.iter().all(|m| m.role != "tool"));
+    }
+
+    #[test]
+    fn stops_before_a_tool_call_whose_results_reach_the_recent_tail() {
+        let mut messages = vec![
+            message("system", 40),
+            message("user", 400),
+            message("assistant", 400),
+            message("tool", 400),
+            message("tool", 400),
+            message("tool", 400),
+        ];
+        messages.extend((0..KEEP_RECENT_MESSAGES - 2).map(|_| message("user", 40)));
+        let tail = messages[messages.len() - KEEP_RECENT_MESSAGES..].to_vec();
+
+        assert_eq!(trim_context(&mut messages, 200), 1);
+        assert_eq!(messages[2].role, "assistant");
+        let kept = &messages[messages.len() - KEEP_RECENT_MESSAGES..];
+        assert!(kept
+            .iter()
+            .zip(&tail)
+            .all(|(a, b)| a.role == b.role && a.content == b.content));
+    }
+
+    #[test]
+    fn keeps_recent_messages_even_over_budget() {
+        let mut messages = vec![message("system", 40), message("user", 4000)];
+        assert_eq!(trim_context(&mut messages, 10), 0);
+        assert_eq!(messages.len(), 2);
+    }
+}
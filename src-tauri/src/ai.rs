@@ -1,4 +1,5 @@
 use crate::tools::ToolDefinition;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 
@@ -20,30 +21,8 @@ pub async fn chat(
         .build()
         .map_err(|e| Box::new(e) as Box<dyn Error>)?;
 
-    // Map AgentMessage to internal ChatMessage
-    let formatted_messages: Vec<ChatMessage> = messages
-        .iter()
-        .map(|m| ChatMessage {
-            role: m.role.clone(),
-            content: m.content.clone(),
-            tool_calls: m.tool_calls.clone(),
-            tool_call_id: m.tool_call_id.clone(),
-        })
-        .collect();
-
-    let tools_json: Vec<serde_json::Value> = tools
-        .iter()
-        .map(|t| {
-            serde_json::json!({
-                "type": "function",
-                "function": {
-                    "name": t.name,
-                    "description": t.description,
-                    "parameters": t.parameters
-                }
-            })
-        })
-        .collect();
+    let formatted_messages = to_chat_messages(messages);
+    let tools_json = openai_tools(tools);
 
     match config.provider.as_str() {
         "openai" => {
@@ -54,17 +33,22 @@ pub async fn chat(
                 "model": config.model.as_deref().unwrap_or("gpt-4o"),
                 "messages": formatted_messages,
             });
+            extend_payload(
+                &mut payload,
+                config.sampling(["temperature", "top_p", "max_tokens"]),
+            );
 
             if !tools_json.is_empty() {
                 payload["tools"] = serde_json::json!(tools_json);
             }
 
-            let response = client
-                .post("https://api.openai.com/v1/chat/completions")
-                .bearer_auth(api_key)
-                .json(&payload)
-                .send()
-                .await?;
+            let response = send_with_retry(
+                client
+                    .post(config.openai_endpoint("chat/completions"))
+                    .bearer_auth(api_key)
+                    .json(&payload),
+            )
+            .await?;
 
             if !response.status().is_success() {
                 let err_text = response.text().await?;
@@ -94,6 +78,8 @@ pub async fn chat(
                     .collect()
             });
 
+            let usage = openai_usage(&data["usage"]);
+
             println!(
                 "[AI] Response parsed successfully. Content present: {}, Tool calls: {}",
                 content.is_some(),
@@ -107,6 +93,7 @@ pub async fn chat(
                 content,
                 tool_calls,
                 tool_call_id: None,
+                usage,
             })
         }
         "ollama" => {
@@ -121,12 +108,16 @@ pub async fn chat(
                 "messages": formatted_messages,
                 "stream": false
             });
+            let options = config.sampling(["temperature", "top_p", "num_predict"]);
+            if !options.is_empty() {
+                payload["options"] = serde_json::Value::Object(options);
+            }
 
             if !tools_json.is_empty() {
                 payload["tools"] = serde_json::json!(tools_json);
             }
 
-            let response = client.post(&url).json(&payload).send().await?;
+            let response = send_with_retry(client.post(&url).json(&payload)).await?;
 
             if !response.status().is_success() {
                 let err_text = response.text().await?;
@@ -139,40 +130,17 @@ pub async fn chat(
 
             let content = msg["content"].as_str().map(|s| s.to_string());
 
-            // Fix arguments serialization if Ollama returns object
-            let tool_calls = if let Some(calls) = msg["tool_calls"].as_array() {
-                let mapped: Vec<crate::agent::ToolCall> = calls
-                    .iter()
-                    .map(|tc| {
-                        let args = &tc["function"]["arguments"];
-                        let args_str = if args.is_string() {
-                            args.as_str().unwrap().to_string()
-                        } else {
-                            args.to_string() // Serialize object to json string
-                        };
-
-                        crate::agent::ToolCall {
-                            id: format!("call_{}", uuid::Uuid::new_v4()),
-                            function: crate::agent::ToolCallFunction {
-                                name: tc["function"]["name"]
-                                    .as_str()
-                                    .unwrap_or_default()
-                                    .to_string(),
-                                arguments: args_str,
-                            },
-                        }
-                    })
-                    .collect();
-                Some(mapped)
-            } else {
-                None
-            };
+            let tool_calls = msg["tool_calls"]
+                .as_array()
+                .map(|calls| calls.iter().map(ollama_tool_call).collect());
+            let usage = ollama_usage(&data);
 
             Ok(crate::agent::AgentMessage {
                 role: "assistant".to_string(),
                 content,
                 tool_calls,
                 tool_call_id: None,
+                usage,
             })
         }
         "gemini" => {
@@ -207,6 +175,9 @@ pub async fn chat(
             // We'll filter system out and put it into system_instruction field.
             let mut gemini_contents: Vec<serde_json::Value> = Vec::new();
             let mut system_instruction: Option<serde_json::Value> = None;
+            // Tool messages only carry the call id; Gemini needs the function name
+            let mut call_names: std::collections::HashMap<String, String> =
+                std::collections::HashMap::new();
 
             for msg in messages {
                 match msg.role.as_str() {
@@ -230,6 +201,7 @@ pub async fn chat(
                         }
                         if let Some(tool_calls) = &msg.tool_calls {
                             for tc in tool_calls {
+                                call_names.insert(tc.id.clone(), tc.function.name.clone());
                                 parts.push(serde_json::json!({
                                      "functionCall": {
                                          "name": tc.function.name,
@@ -244,30 +216,27 @@ pub async fn chat(
                         }));
                     }
                     "tool" => {
-                        // Gemini expects tool response in "functionResponse" part
-                        // "role": "function"? No, role is "user" usually (from tool), or "function" role?
-                        // Gemini docs say: role="function" for function responses.
-                        // Wait, docs say: role "user" or "model".
-                        // Actually, for functionResponse, the role is 'function' in some docs, but official API user 'function' role.
-                        // Let's verify:
-                        // It seems we should use 'function' role.
-                        // Content parts: [{ "functionResponse": { "name": ..., "response": ... } }]
-
-                        let _call_id = msg.tool_call_id.clone().unwrap_or_default();
-                        // We need the function name to match the response.
-                        // AgentMessage structure stores call_id but not name in the response msg.
-                        // We might need to infer it or AgentState needs to track pending calls.
-                        // For now, I'll assume we can't perfectly map tool responses without name.
-                        // But wait! `tool_calls` in the previous assistant message has the name.
-
-                        // IMPERFECT SOLUTION: Just pass it as "user" text "Tool Output: ..."
-                        // This confuses Gemini less than a malformed functionResponse.
-                        // Let's try "user" role with "Observation: ..." pattern which works for RAG/ReAct.
+                        let call_id = msg.tool_call_id.as_deref().unwrap_or_default();
+                        let name = call_names.get(call_id).cloned().unwrap_or_default();
+                        let part = serde_json::json!({
+                            "functionResponse": {
+                                "name": name,
+                                "response": { "result": msg.content.as_deref().unwrap_or("") }
+                            }
+                        });
 
-                        gemini_contents.push(serde_json::json!({
-                            "role": "user",
-                            "parts": [{ "text": format!("Tool Output: {}", msg.content.as_deref().unwrap_or("")) }]
-                        }));
+                        // Responses to parallel calls share one "function" turn
+                        match gemini_contents.last_mut() {
+                            Some(last) if last["role"] == "function" => {
+                                if let Some(parts) = last["parts"].as_array_mut() {
+                                    parts.push(part);
+                                }
+                            }
+                            _ => gemini_contents.push(serde_json::json!({
+                                "role": "function",
+                                "parts": [part]
+                            })),
+                        }
                     }
                     _ => {}
                 }
@@ -276,6 +245,10 @@ pub async fn chat(
             let mut payload = serde_json::json!({
                 "contents": gemini_contents,
             });
+            let generation_config = config.sampling(["temperature", "topP", "maxOutputTokens"]);
+            if !generation_config.is_empty() {
+                payload["generationConfig"] = serde_json::Value::Object(generation_config);
+            }
 
             if !gemini_tools.is_empty() {
                 payload["tools"] = serde_json::json!(gemini_tools);
@@ -284,7 +257,7 @@ pub async fn chat(
                 payload["system_instruction"] = sys;
             }
 
-            let response = client.post(&url).json(&payload).send().await?;
+            let response = send_with_retry(client.post(&url).json(&payload)).await?;
 
             if !response.status().is_success() {
                 let err_text = response.text().await?;
@@ -295,6 +268,10 @@ pub async fn chat(
             let data: serde_json::Value = response.json().await?;
             // println!("[AI] Gemini Raw Response: {:?}", data);
 
+            let usage = token_usage(
+                data["usageMetadata"]["promptTokenCount"].as_u64(),
+                data["usageMetadata"]["candidatesTokenCount"].as_u64(),
+            );
             let candidate = &data["candidates"][0];
             let content_parts = candidate["content"]["parts"].as_array();
 
@@ -312,7 +289,8 @@ pub async fn chat(
                         let args_str = args.to_string();
 
                         tool_calls.push(crate::agent::ToolCall {
-                            id: "gemini_call".to_string(), // Gemini doesn't use IDs
+                            // Gemini doesn't use IDs; make them unique so responses map back by name
+                            id: format!("gemini_call_{}", uuid::Uuid::new_v4()),
                             function: crate::agent::ToolCallFunction {
                                 name,
                                 arguments: args_str,
@@ -338,6 +316,166 @@ pub async fn chat(
                 content,
                 tool_calls,
                 tool_call_id: None,
+                usage,
+            })
+        }
+        "claude" => {
+            let api_key = config
+                .api_key
+                .as_deref()
+                .ok_or("Anthropic API Key missing")?;
+            let model = config
+                .model
+                .as_deref()
+                .unwrap_or("claude-3-5-sonnet-latest");
+
+            println!("[AI] Sending request to Claude. Model: {}", model);
+
+            let claude_tools: Vec<serde_json::Value> = tools
+                .iter()
+                .map(|t| {
+                    serde_json::json!({
+                        "name": t.name,
+                        "description": t.description,
+                        "input_schema": t.parameters
+                    })
+                })
+                .collect();
+
+            // Claude takes the system prompt as a top-level field and expects
+            // tool results as "tool_result" blocks inside a user turn
+            let mut system_prompt: Option<String> = None;
+            let mut claude_messages: Vec<serde_json::Value> = Vec::new();
+
+            for msg in messages {
+                match msg.role.as_str() {
+                    "system" => {
+                        system_prompt = msg.content.clone();
+                    }
+                    "user" => {
+                        push_claude_block(
+                            &mut claude_messages,
+                            "user",
+                            serde_json::json!({
+                                "type": "text",
+                                "text": msg.content.as_deref().unwrap_or("")
+                            }),
+                        );
+                    }
+                    "assistant" => {
+                        if let Some(content) = msg.content.as_deref().filter(|c| !c.is_empty()) {
+                            push_claude_block(
+                                &mut claude_messages,
+                                "assistant",
+                                serde_json::json!({ "type": "text", "text": content }),
+                            );
+                        }
+                        for tc in msg.tool_calls.iter().flatten() {
+                            push_claude_block(
+                                &mut claude_messages,
+                                "assistant",
+                                serde_json::json!({
+                                    "type": "tool_use",
+                                    "id": tc.id,
+                                    "name": tc.function.name,
+                                    "input": serde_json::from_str::<serde_json::Value>(&tc.function.arguments)
+                                        .unwrap_or(serde_json::json!({}))
+                                }),
+                            );
+                        }
+                    }
+                    "tool" => {
+                        push_claude_block(
+                            &mut claude_messages,
+                            "user",
+                            serde_json::json!({
+                                "type": "tool_result",
+                                "tool_use_id": msg.tool_call_id.as_deref().unwrap_or_default(),
+                                "content": msg.content.as_deref().unwrap_or("")
+                            }),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+
+            let mut payload = serde_json::json!({
+                "model": model,
+                "max_tokens": 4096,
+                "messages": claude_messages,
+            });
+            extend_payload(
+                &mut payload,
+                config.sampling(["temperature", "top_p", "max_tokens"]),
+            );
+
+            if !claude_tools.is_empty() {
+                payload["tools"] = serde_json::json!(claude_tools);
+            }
+            if let Some(system) = system_prompt {
+                payload["system"] = serde_json::json!(system);
+            }
+
+            let response = send_with_retry(
+                client
+                    .post("https://api.anthropic.com/v1/messages")
+                    .header("x-api-key", api_key)
+                    .header("anthropic-version", "2023-06-01")
+                    .json(&payload),
+            )
+            .await?;
+
+            if !response.status().is_success() {
+                let err_text = response.text().await?;
+                println!("[AI] Claude Error Response: {}", err_text);
+                return Err(format!("Claude Chat Error: {}", err_text).into());
+            }
+
+            let data: serde_json::Value = response.json().await?;
+
+            let usage = token_usage(
+                data["usage"]["input_tokens"].as_u64(),
+                data["usage"]["output_tokens"].as_u64(),
+            );
+
+            let mut content_text = String::new();
+            let mut tool_calls = Vec::new();
+
+            for block in data["content"].as_array().into_iter().flatten() {
+                match block["type"].as_str() {
+                    Some("text") => {
+                        content_text.push_str(block["text"].as_str().unwrap_or_default());
+                    }
+                    Some("tool_use") => {
+                        tool_calls.push(crate::agent::ToolCall {
+                            id: block["id"].as_str().unwrap_or_default().to_string(),
+                            function: crate::agent::ToolCallFunction {
+                                name: block["name"].as_str().unwrap_or_default().to_string(),
+                                arguments: block["input"].to_string(),
+                            },
+                        });
+                    }
+                    _ => {}
+                }
+            }
+
+            let content = if content_text.is_empty() {
+                None
+            } else {
+                Some(content_text)
+            };
+            let tool_calls = if tool_calls.is_empty() {
+                None
+            } else {
+                Some(tool_calls)
+            };
+
+            Ok(crate::agent::AgentMessage {
+                role: "assistant".to_string(),
+                content,
+                tool_calls,
+                tool_call_id: None,
+                usage,
             })
         }
         _ => Err(format!(
@@ -348,6 +486,317 @@ pub async fn chat(
     }
 }
 
+// Map AgentMessage to internal ChatMessage
+fn to_chat_messages(messages: &[crate::agent::AgentMessage]) -> Vec<ChatMessage> {
+    messages
+        .iter()
+        .map(|m| ChatMessage {
+            role: m.role.clone(),
+            content: m.content.clone(),
+            tool_calls: m.tool_calls.clone(),
+            tool_call_id: m.tool_call_id.clone(),
+        })
+        .collect()
+}
+
+/// Tool definitions in the OpenAI function-calling format (also accepted by Ollama)
+fn openai_tools(tools: &[ToolDefinition]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|t| {
+            serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": t.name,
+                    "description": t.description,
+                    "parameters": t.parameters
+                }
+            })
+        })
+        .collect()
+}
+
+/// Ollama returns tool arguments as an object and without call ids
+fn ollama_tool_call(tc: &serde_json::Value) -> crate::agent::ToolCall {
+    let args = &tc["function"]["arguments"];
+    let args_str = match args.as_str() {
+        Some(s) => s.to_string(),
+        None => args.to_string(), // Serialize object to json string
+    };
+
+    crate::agent::ToolCall {
+        id: format!("call_{}", uuid::Uuid::new_v4()),
+        function: crate::agent::ToolCallFunction {
+            name: tc["function"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            arguments: args_str,
+        },
+    }
+}
+
+/// Like `chat`, but streams the reply for openai and ollama, calling `on_token` with each
+/// text delta. Tool calls are accumulated and only returned once the reply is complete.
+/// Other providers fall back to a single `chat` request.
+pub async fn chat_stream<F>(
+    messages: &[crate::agent::AgentMessage],
+    tools: &[ToolDefinition],
+    config: &ProviderConfig,
+    mut on_token: F,
+) -> Result<crate::agent::AgentMessage, Box<dyn Error>>
+where
+    F: FnMut(&str),
+{
+    if config.provider != "openai" && config.provider != "ollama" {
+        return chat(messages, tools, config).await;
+    }
+
+    // No overall timeout: long answers stream for longer than a single request should take
+    let client = reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| Box::new(e) as Box<dyn Error>)?;
+
+    let tools_json = openai_tools(tools);
+    let mut payload = serde_json::json!({
+        "messages": to_chat_messages(messages),
+        "stream": true,
+    });
+    if !tools_json.is_empty() {
+        payload["tools"] = serde_json::json!(tools_json);
+    }
+
+    let request = if config.provider == "openai" {
+        let api_key = config.api_key.as_deref().ok_or("OpenAI API Key missing")?;
+        payload["model"] = serde_json::json!(config.model.as_deref().unwrap_or("gpt-4o"));
+        // Ask for a final chunk carrying token usage
+        payload["stream_options"] = serde_json::json!({ "include_usage": true });
+        extend_payload(
+            &mut payload,
+            config.sampling(["temperature", "top_p", "max_tokens"]),
+        );
+        println!(
+            "[AI] Streaming request to OpenAI. Model: {:?}",
+            config.model
+        );
+        client
+            .post(config.openai_endpoint("chat/completions"))
+            .bearer_auth(api_key)
+    } else {
+        let base_url = config.url.as_deref().unwrap_or("http://localhost:11434");
+        payload["model"] = serde_json::json!(config.model.as_deref().unwrap_or("llama3"));
+        let options = config.sampling(["temperature", "top_p", "num_predict"]);
+        if !options.is_empty() {
+            payload["options"] = serde_json::Value::Object(options);
+        }
+        println!(
+            "[AI] Streaming request to Ollama. Model: {:?}",
+            config.model
+        );
+        client.post(format!("{}/api/chat", base_url.trim_end_matches('/')))
+    };
+
+    let response = send_with_retry(request.json(&payload)).await?;
+    if !response.status().is_success() {
+        let err_text = response.text().await?;
+        println!("[AI] Stream Error Response: {}", err_text);
+        return Err(format!("{} Chat Error: {}", config.provider, err_text).into());
+    }
+
+    let mut content = String::new();
+    let mut usage = None;
+    let mut tool_calls: Vec<crate::agent::ToolCall> = Vec::new();
+    // OpenAI sends tool calls in fragments keyed by index
+    let mut partial_calls: std::collections::BTreeMap<u64, crate::agent::ToolCall> =
+        std::collections::BTreeMap::new();
+
+    let mut buffer = String::new();
+    let mut stream = response.bytes_stream();
+    'stream: while let Some(chunk) = stream.next().await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk?));
+
+        // Both SSE (openai) and NDJSON (ollama) are line-delimited
+        while let Some(newline) = buffer.find('\n') {
+            let line: String = buffer.drain(..=newline).collect();
+            let line = line.trim();
+
+            let json_text = if config.provider == "openai" {
+                match line.strip_prefix("data:") {
+                    Some(data) if data.trim() == "[DONE]" => break 'stream,
+                    Some(data) => data.trim(),
+                    None => continue,
+                }
+            } else {
+                line
+            };
+            if json_text.is_empty() {
+                continue;
+            }
+            let data: serde_json::Value = serde_json::from_str(json_text)?;
+
+            if config.provider == "openai" {
+                if data["usage"].is_object() {
+                    usage = openai_usage(&data["usage"]);
+                }
+                let delta = &data["choices"][0]["delta"];
+                if let Some(text) = delta["content"].as_str() {
+                    content.push_str(text);
+                    on_token(text);
+                }
+                for tc in delta["tool_calls"].as_array().into_iter().flatten() {
+                    let call = partial_calls
+                        .entry(tc["index"].as_u64().unwrap_or(0))
+                        .or_insert_with(|| crate::agent::ToolCall {
+                            id: String::new(),
+                            function: crate::agent::ToolCallFunction {
+                                name: String::new(),
+                                arguments: String::new(),
+                            },
+                        });
+                    if let Some(id) = tc["id"].as_str() {
+                        call.id = id.to_string();
+                    }
+                    if let Some(name) = tc["function"]["name"].as_str() {
+                        call.function.name.push_str(name);
+                    }
+                    if let Some(args) = tc["function"]["arguments"].as_str() {
+                        call.function.arguments.push_str(args);
+                    }
+                }
+            } else {
+                let msg = &data["message"];
+                if let Some(text) = msg["content"].as_str().filter(|t| !t.is_empty()) {
+                    content.push_str(text);
+                    on_token(text);
+                }
+                for tc in msg["tool_calls"].as_array().into_iter().flatten() {
+                    tool_calls.push(ollama_tool_call(tc));
+                }
+                if data["done"].as_bool() == Some(true) {
+                    usage = ollama_usage(&data);
+                    break 'stream;
+                }
+            }
+        }
+    }
+    tool_calls.extend(partial_calls.into_values());
+
+    Ok(crate::agent::AgentMessage {
+        role: "assistant".to_string(),
+        content: if content.is_empty() {
+            None
+        } else {
+            Some(content)
+        },
+        tool_calls: if tool_calls.is_empty() {
+            None
+        } else {
+            Some(tool_calls)
+        },
+        tool_call_id: None,
+        usage,
+    })
+}
+
+fn token_usage(
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+) -> Option<crate::agent::TokenUsage> {
+    if prompt_tokens.is_none() && completion_tokens.is_none() {
+        return None;
+    }
+    let prompt_tokens = prompt_tokens.unwrap_or(0);
+    let completion_tokens = completion_tokens.unwrap_or(0);
+    Some(crate::agent::TokenUsage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    })
+}
+
+fn openai_usage(usage: &serde_json::Value) -> Option<crate::agent::TokenUsage> {
+    token_usage(
+        usage["prompt_tokens"].as_u64(),
+        usage["completion_tokens"].as_u64(),
+    )
+}
+
+/// Ollama has no usage object; its eval counts are the closest estimate
+fn ollama_usage(data: &serde_json::Value) -> Option<crate::agent::TokenUsage> {
+    token_usage(
+        data["prompt_eval_count"].as_u64(),
+        data["eval_count"].as_u64(),
+    )
+}
+
+/// Retries after the first attempt for rate limits, server errors and timeouts
+const MAX_RETRIES: u32 = 3;
+/// Longest `Retry-After` honored; a longer one would stall the agent turn
+const MAX_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Send a request, retrying transient failures (429/500/502/503 and network timeouts)
+/// with exponential backoff of 1s, 2s, 4s, or the server's `Retry-After` when given and
+/// no longer than `MAX_RETRY_AFTER`.
+/// Other errors, including auth failures, are returned immediately.
+async fn send_with_retry(
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let mut attempt = 0;
+    loop {
+        let Some(this_try) = request.try_clone() else {
+            // Streaming bodies can't be replayed
+            return request.send().await;
+        };
+
+        let delay = match this_try.send().await {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                if attempt >= MAX_RETRIES || !matches!(status, 429 | 500 | 502 | 503) {
+                    return Ok(response);
+                }
+                response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .map(std::time::Duration::from_secs)
+                    .filter(|delay| *delay <= MAX_RETRY_AFTER)
+            }
+            Err(e) => {
+                if attempt >= MAX_RETRIES || !(e.is_timeout() || e.is_connect()) {
+                    return Err(e);
+                }
+                None
+            }
+        };
+
+        let delay = delay.unwrap_or_else(|| std::time::Duration::from_secs(1 << attempt));
+        println!(
+            "[AI] Transient failure, retrying in {:?} (attempt {}/{})",
+            delay,
+            attempt + 1,
+            MAX_RETRIES
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Append a content block to the last Claude message when it has the same role,
+/// since the Messages API requires user and assistant turns to alternate
+fn push_claude_block(messages: &mut Vec<serde_json::Value>, role: &str, block: serde_json::Value) {
+    if let Some(last) = messages.last_mut() {
+        if last["role"] == role {
+            if let Some(content) = last["content"].as_array_mut() {
+                content.push(block);
+                return;
+            }
+        }
+    }
+    messages.push(serde_json::json!({ "role": role, "content": [block] }));
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub provider: String,
@@ -357,6 +806,131 @@ pub struct ProviderConfig {
     pub model: Option<String>,
     #[serde(default)]
     pub url: Option<String>,
+    /// Sampling settings; unset ones are omitted so the provider's defaults apply
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+}
+
+/// Default base of the "openai" provider
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+impl ProviderConfig {
+    /// `path` under `url` when set, so any OpenAI-compatible gateway (OpenRouter, Groq,
+    /// LM Studio, a corporate proxy) can stand in for the OpenAI API
+    fn openai_endpoint(&self, path: &str) -> String {
+        let base = self
+            .url
+            .as_deref()
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or(OPENAI_BASE_URL);
+        format!("{}/{}", base.trim_end_matches('/'), path)
+    }
+
+    /// The sampling settings that are set, keyed by the provider's names for
+    /// temperature, top_p and max_tokens (in that order)
+    fn sampling(&self, names: [&str; 3]) -> serde_json::Map<String, serde_json::Value> {
+        let [temperature, top_p, max_tokens] = names;
+        let mut params = serde_json::Map::new();
+        if let Some(t) = self.temperature {
+            params.insert(temperature.to_string(), serde_json::json!(t));
+        }
+        if let Some(p) = self.top_p {
+            params.insert(top_p.to_string(), serde_json::json!(p));
+        }
+        if let Some(n) = self.max_tokens {
+            params.insert(max_tokens.to_string(), serde_json::json!(n));
+        }
+        params
+    }
+}
+
+/// Copy `fields` into the top level of a JSON request body
+fn extend_payload(
+    payload: &mut serde_json::Value,
+    fields: serde_json::Map<String, serde_json::Value>,
+) {
+    if let Some(object) = payload.as_object_mut() {
+        object.extend(fields);
+    }
+}
+
+/// Embed several texts, returning vectors in input order. OpenAI takes the whole array in one
+/// request; other providers only accept a single input, so they are embedded one by one.
+pub async fn get_embeddings_batch(
+    texts: &[String],
+    config: &ProviderConfig,
+) -> Result<Vec<Vec<f32>>, String> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if config.provider != "openai" {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            embeddings.push(
+                get_embedding(text, config)
+                    .await
+                    .map_err(|e| e.to_string())?,
+            );
+        }
+        return Ok(embeddings);
+    }
+
+    let api_key = config.api_key.as_deref().ok_or("OpenAI API Key missing")?;
+    let client = reqwest::Client::new();
+    let response = send_with_retry(
+        client
+            .post(config.openai_endpoint("embeddings"))
+            .bearer_auth(api_key)
+            .json(&serde_json::json!({
+                "input": texts,
+                "model": "text-embedding-3-small"
+            })),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "OpenAI Error: {}",
+            response.text().await.map_err(|e| e.to_string())?
+        ));
+    }
+
+    let data: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    let mut items: Vec<&serde_json::Value> = data["data"]
+        .as_array()
+        .ok_or("Failed to parse embeddings")?
+        .iter()
+        .collect();
+    if items.len() != texts.len() {
+        return Err(format!(
+            "Expected {} embeddings, got {}",
+            texts.len(),
+            items.len()
+        ));
+    }
+    // Entries carry their input position; don't rely on response order
+    items.sort_by_key(|item| item["index"].as_u64().unwrap_or(0));
+
+    items
+        .into_iter()
+        .map(|item| {
+            item["embedding"]
+                .as_array()
+                .ok_or_else(|| "Failed to parse embedding".to_string())
+                .map(|values| {
+                    values
+                        .iter()
+                        .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+                        .collect()
+                })
+        })
+        .collect()
 }
 
 pub async fn get_embedding(
@@ -368,17 +942,18 @@ pub async fn get_embedding(
     match config.provider.as_str() {
         "openai" => {
             let api_key = config.api_key.as_deref().ok_or("OpenAI API Key missing")?;
-            let url = "https://api.openai.com/v1/embeddings";
-
-            let response = client
-                .post(url)
-                .bearer_auth(api_key)
-                .json(&serde_json::json!({
-                    "input": text,
-                    "model": "text-embedding-3-small"
-                }))
-                .send()
-                .await?;
+            let url = config.openai_endpoint("embeddings");
+
+            let response = send_with_retry(
+                client
+                    .post(url)
+                    .bearer_auth(api_key)
+                    .json(&serde_json::json!({
+                        "input": text,
+                        "model": "text-embedding-3-small"
+                    }))
+            )
+            .await?;
 
             if !response.status().is_success() {
                 return Err(format!("OpenAI Error: {}", response.text().await?).into());
@@ -401,15 +976,16 @@ pub async fn get_embedding(
                 api_key
             );
 
-            let response = client
-                .post(&url)
-                .json(&serde_json::json!({
-                    "content": {
-                        "parts": [{ "text": text }]
-                    }
-                }))
-                .send()
-                .await?;
+            let response = send_with_retry(
+                client
+                    .post(&url)
+                    .json(&serde_json::json!({
+                        "content": {
+                            "parts": [{ "text": text }]
+                        }
+                    }))
+            )
+            .await?;
 
             if !response.status().is_success() {
                 return Err(format!("Gemini Error: {}", response.text().await?).into());
@@ -430,14 +1006,15 @@ pub async fn get_embedding(
             let url = format!("{}/api/embeddings", base_url.trim_end_matches('/'));
             let model = config.model.as_deref().unwrap_or("nomic-embed-text");
 
-            let response = client
-                .post(&url)
-                .json(&serde_json::json!({
-                    "model": model,
-                    "prompt": text
-                }))
-                .send()
-                .await?;
+            let response = send_with_retry(
+                client
+                    .post(&url)
+                    .json(&serde_json::json!({
+                        "model": model,
+                        "prompt": text
+                    }))
+            )
+            .await?;
 
             if !response.status().is_success() {
                 return Err(format!("Ollama Error: {}", response.text().await?).into());
@@ -453,6 +1030,187 @@ pub async fn get_embedding(
 
             Ok(embedding)
         }
+        "claude" => Err("Claude does not provide an embeddings API; use OpenAI, Gemini or Ollama for embeddings".into()),
         _ => Err("Unknown provider".into()),
     }
 }
+
+/// Model ids the provider currently offers, sorted for display
+pub async fn list_models(config: &ProviderConfig) -> Result<Vec<String>, Box<dyn Error>> {
+    let client = reqwest::Client::new();
+    let Some((request, list_key, id_key)) = models_request(&client, config)? else {
+        return Ok(vec!["mock".to_string()]);
+    };
+
+    let response = send_with_retry(request).await?;
+    if !response.status().is_success() {
+        return Err(format!("{} Error: {}", config.provider, response.text().await?).into());
+    }
+
+    let data: serde_json::Value = response.json().await?;
+    parse_models(&data, list_key, id_key)
+}
+
+/// A model-list request plus the JSON keys of the list and of each model's id
+type ModelsRequest = (reqwest::RequestBuilder, &'static str, &'static str);
+
+/// Request listing the provider's models; `None` for the mock provider, which has
+/// nothing to query
+fn models_request(
+    client: &reqwest::Client,
+    config: &ProviderConfig,
+) -> Result<Option<ModelsRequest>, Box<dyn Error>> {
+    let request = match config.provider.as_str() {
+        "openai" => {
+            let api_key = config.api_key.as_deref().ok_or("OpenAI API Key missing")?;
+            let request = client
+                .get(config.openai_endpoint("models"))
+                .bearer_auth(api_key);
+            (request, "data", "id")
+        }
+        "gemini" => {
+            let api_key = config.api_key.as_deref().ok_or("Gemini API Key missing")?;
+            let request = client
+                .get("https://generativelanguage.googleapis.com/v1beta/models")
+                .query(&[("key", api_key), ("pageSize", "1000")]);
+            (request, "models", "name")
+        }
+        "claude" => {
+            let api_key = config.api_key.as_deref().ok_or("Claude API Key missing")?;
+            let request = client
+                .get("https://api.anthropic.com/v1/models")
+                .query(&[("limit", "1000")])
+                .header("x-api-key", api_key)
+                .header("anthropic-version", "2023-06-01");
+            (request, "data", "id")
+        }
+        "ollama" => {
+            let base_url = config.url.as_deref().unwrap_or("http://localhost:11434");
+            let url = format!("{}/api/tags", base_url.trim_end_matches('/'));
+            (client.get(url), "models", "name")
+        }
+        "mock" => return Ok(None),
+        _ => return Err("Unknown provider".into()),
+    };
+    Ok(Some(request))
+}
+
+fn parse_models(
+    data: &serde_json::Value,
+    list_key: &str,
+    id_key: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut models: Vec<String> = data[list_key]
+        .as_array()
+        .ok_or("Failed to parse model list")?
+        .iter()
+        .filter_map(|m| m[id_key].as_str())
+        // Gemini names are resource paths like "models/gemini-1.5-flash"
+        .map(|id| id.strip_prefix("models/").unwrap_or(id).to_string())
+        .collect();
+    models.sort();
+    Ok(models)
+}
+
+/// Outcome of a connection test against a provider configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStatus {
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub model_count: usize,
+    pub message: String,
+}
+
+/// How long a connection test waits before reporting the provider as unreachable
+const TEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Check that the provider is reachable and accepts the credentials by listing its
+/// models once. No retries, so the latency reflects a single round trip.
+pub async fn test_provider(config: &ProviderConfig) -> Result<ProviderStatus, String> {
+    let failed = |latency_ms: u64, message: String| ProviderStatus {
+        ok: false,
+        latency_ms,
+        model_count: 0,
+        message,
+    };
+
+    let client = reqwest::Client::new();
+    let (request, list_key, id_key) = match models_request(&client, config) {
+        Ok(Some(request)) => request,
+        Ok(None) => {
+            return Ok(ProviderStatus {
+                ok: true,
+                latency_ms: 0,
+                model_count: 1,
+                message: "Mock provider is always available".to_string(),
+            })
+        }
+        Err(e) => return Ok(failed(0, format!("Configuration error: {}", e))),
+    };
+
+    let started = std::time::Instant::now();
+    let response = request.timeout(TEST_TIMEOUT).send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let response = match response {
+        Ok(response) => response,
+        Err(e) if e.is_timeout() => {
+            return Ok(failed(
+                latency_ms,
+                format!(
+                    "Network error: no response within {}s",
+                    TEST_TIMEOUT.as_secs()
+                ),
+            ))
+        }
+        Err(e) => return Ok(failed(latency_ms, format!("Network error: {}", e))),
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        // Gemini rejects a bad key with 400 rather than 401
+        let message = if matches!(status.as_u16(), 401 | 403) || body.contains("API_KEY_INVALID") {
+            format!("Authentication failed ({}): check the API key", status)
+        } else {
+            format!("Provider error ({}): {}", status, body.trim())
+        };
+        return Ok(failed(latency_ms, message));
+    }
+
+    let models = match response.json::<serde_json::Value>().await {
+        Ok(data) => parse_models(&data, list_key, id_key).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let models = match models {
+        Ok(models) => models,
+        Err(e) => {
+            return Ok(failed(
+                latency_ms,
+                format!("Unexpected response from provider: {}", e),
+            ))
+        }
+    };
+
+    let mut message = format!(
+        "Connected in {} ms, {} models available",
+        latency_ms,
+        models.len()
+    );
+    if let Some(model) = config.model.as_deref().filter(|m| !m.is_empty()) {
+        // Ollama lists tags in full ("llama3:latest") while configs often omit them
+        let listed = models
+            .iter()
+            .any(|m| m == model || m.strip_suffix(":latest") == Some(model));
+        if !listed {
+            message.push_str(&format!("; model \"{}\" was not found", model));
+        }
+    }
+
+    Ok(ProviderStatus {
+        ok: true,
+        latency_ms,
+        model_count: models.len(),
+        message,
+    })
+}
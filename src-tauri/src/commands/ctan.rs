@@ -1,6 +1,15 @@
 use crate::types::ctan::{CTANPackage, CTANTopic};
-use serde::Serialize;
-use std::sync::OnceLock;
+use futures_util::StreamExt;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
 
 /// Embedded CTAN package database JSON (compiled into the binary).
 const CTAN_DB_JSON: &str = include_str!("../../../src/assets/CTANpackageDatabase.json");
@@ -19,38 +28,83 @@ struct PackageDatabase {
     search_index: Vec<SearchIndex>,
 }
 
-/// Static storage for parsed packages with search index.
-static CTAN_DB: OnceLock<PackageDatabase> = OnceLock::new();
+/// Parsed packages with search index. Starts as the embedded copy and is swapped out
+/// when a cached or freshly downloaded database is loaded.
+static CTAN_DB: RwLock<Option<Arc<PackageDatabase>>> = RwLock::new(None);
 
-/// Returns a reference to the package database with search index.
-fn get_db() -> &'static PackageDatabase {
-    CTAN_DB.get_or_init(|| {
-        let mut packages: Vec<CTANPackage> =
+/// File in the app data dir holding the last database fetched from CTAN.
+const CTAN_CACHE_FILE: &str = "ctan_packages.json";
+
+/// Returns the current package database with search index.
+fn get_db() -> Arc<PackageDatabase> {
+    if let Some(db) = CTAN_DB.read().unwrap().as_ref() {
+        return db.clone();
+    }
+    let mut slot = CTAN_DB.write().unwrap();
+    slot.get_or_insert_with(|| {
+        let packages: Vec<CTANPackage> =
             serde_json::from_str(CTAN_DB_JSON).expect("Failed to parse CTAN database");
+        Arc::new(build_database(packages))
+    })
+    .clone()
+}
+
+fn set_db(packages: Vec<CTANPackage>) -> usize {
+    let db = build_database(packages);
+    let count = db.packages.len();
+    *CTAN_DB.write().unwrap() = Some(Arc::new(db));
+    count
+}
+
+fn build_database(mut packages: Vec<CTANPackage>) -> PackageDatabase {
+    // Pre-sort alphabetically by name (case-insensitive)
+    packages.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+
+    // Pre-compute search index (lowercase fields computed once, not per-search)
+    let search_index: Vec<SearchIndex> = packages
+        .iter()
+        .map(|pkg| SearchIndex {
+            name_lower: pkg.name.to_lowercase(),
+            id_lower: pkg.id.to_lowercase(),
+            caption_lower: Some(pkg.caption.to_lowercase()),
+            topic_keys: pkg
+                .topics
+                .as_ref()
+                .map(|t| t.iter().map(|topic| topic.key.clone()).collect())
+                .unwrap_or_default(),
+        })
+        .collect();
 
-        // Pre-sort alphabetically by name (case-insensitive)
-        packages.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-
-        // Pre-compute search index (lowercase fields computed once, not per-search)
-        let search_index: Vec<SearchIndex> = packages
-            .iter()
-            .map(|pkg| SearchIndex {
-                name_lower: pkg.name.to_lowercase(),
-                id_lower: pkg.id.to_lowercase(),
-                caption_lower: Some(pkg.caption.to_lowercase()),
-                topic_keys: pkg
-                    .topics
-                    .as_ref()
-                    .map(|t| t.iter().map(|topic| topic.key.clone()).collect())
-                    .unwrap_or_default(),
-            })
-            .collect();
-
-        PackageDatabase {
-            packages,
-            search_index,
+    PackageDatabase {
+        packages,
+        search_index,
+    }
+}
+
+fn cache_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    Ok(dir.join(CTAN_CACHE_FILE))
+}
+
+/// Use the database saved by the last `refresh_ctan_db`, if there is one.
+/// Called at startup; a missing or unreadable cache keeps the embedded copy.
+pub fn load_cached_db(app_handle: &AppHandle) {
+    let Ok(path) = cache_path(app_handle) else {
+        return;
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<Vec<CTANPackage>>(&content) {
+        Ok(packages) => {
+            let count = set_db(packages);
+            println!("[CTAN] Loaded {} packages from {:?}", count, path);
         }
-    })
+        Err(e) => println!("[CTAN] Ignoring unreadable cache {:?}: {}", path, e),
+    }
 }
 
 /// Lightweight struct for list view - only essential fields to minimize IPC payload.
@@ -62,10 +116,12 @@ pub struct PackageListItem {
     pub version: String,
     pub home: Option<String>,
     pub ctan: Option<String>,
+    /// Fuzzy relevance to the search query; `None` when listing without a query
+    pub score: Option<i64>,
 }
 
 impl PackageListItem {
-    fn from_package(pkg: &CTANPackage) -> Self {
+    fn from_package(pkg: &CTANPackage, score: Option<i64>) -> Self {
         Self {
             id: pkg.id.clone(),
             name: pkg.name.clone(),
@@ -73,6 +129,7 @@ impl PackageListItem {
             version: pkg.version.clone(),
             home: pkg.home.clone(),
             ctan: pkg.ctan.clone(),
+            score,
         }
     }
 }
@@ -86,8 +143,33 @@ pub struct PackageResponse {
     pub packages: Vec<PackageListItem>,
 }
 
-/// Fetches packages with optional filtering, pagination, and alphabetical sorting.
-/// Uses pre-computed search index for fast string matching.
+/// Name and id matches count this much more than caption matches
+const NAME_WEIGHT: i64 = 2;
+/// Added when the query is exactly a package's name or id, so `tikz` beats `tikz-cd`
+const EXACT_MATCH_BONUS: i64 = 1000;
+
+/// Fuzzy relevance of a package to a lowercase query, or `None` if nothing matches.
+fn score_package(matcher: &SkimMatcherV2, idx: &SearchIndex, query_lower: &str) -> Option<i64> {
+    let name = matcher
+        .fuzzy_match(&idx.name_lower, query_lower)
+        .max(matcher.fuzzy_match(&idx.id_lower, query_lower))
+        .map(|s| s * NAME_WEIGHT);
+    let caption = idx
+        .caption_lower
+        .as_ref()
+        .and_then(|c| matcher.fuzzy_match(c, query_lower));
+
+    let score = name.max(caption)?;
+    if idx.name_lower == query_lower || idx.id_lower == query_lower {
+        Some(score + EXACT_MATCH_BONUS)
+    } else {
+        Some(score)
+    }
+}
+
+/// Fetches packages with optional filtering and pagination.
+/// Without a query results are alphabetical; with one they are ranked by fuzzy score
+/// over the pre-computed lowercase index.
 #[tauri::command]
 pub fn get_packages(
     query: Option<String>,
@@ -99,45 +181,43 @@ pub fn get_packages(
     let start = Instant::now();
 
     let db = get_db();
-    let query_lower = query.clone().map(|q| q.to_lowercase()).unwrap_or_default();
+    let query_lower = query
+        .clone()
+        .map(|q| q.trim().to_lowercase())
+        .unwrap_or_default();
     let topic_key = topic.clone().unwrap_or_default();
+    let matcher = SkimMatcherV2::default();
 
     // Filter using pre-computed index (no allocations during search)
-    let filtered_indices: Vec<usize> = db
+    let mut scored: Vec<(usize, Option<i64>)> = db
         .search_index
         .iter()
         .enumerate()
-        .filter(|(_, idx)| {
-            // Match query against pre-computed lowercase fields
-            let matches_query = query_lower.is_empty() || {
-                idx.name_lower.contains(&query_lower)
-                    || idx.id_lower.contains(&query_lower)
-                    || idx
-                        .caption_lower
-                        .as_ref()
-                        .map(|c| c.contains(&query_lower))
-                        .unwrap_or(false)
-            };
-
-            // Match topic by key
-            let matches_topic =
-                topic_key.is_empty() || idx.topic_keys.iter().any(|k| k == &topic_key);
-
-            matches_query && matches_topic
+        .filter(|(_, idx)| topic_key.is_empty() || idx.topic_keys.iter().any(|k| k == &topic_key))
+        .filter_map(|(i, idx)| {
+            if query_lower.is_empty() {
+                Some((i, None))
+            } else {
+                score_package(&matcher, idx, &query_lower).map(|score| (i, Some(score)))
+            }
         })
-        .map(|(i, _)| i)
         .collect();
 
-    let total = filtered_indices.len();
+    // Stable sort keeps the alphabetical pre-sort among equal scores
+    if !query_lower.is_empty() {
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    }
+
+    let total = scored.len();
     let skip = offset.unwrap_or(0);
     let take = limit.unwrap_or(100);
 
     // Convert to lightweight list items for fast IPC
-    let result_packages: Vec<PackageListItem> = filtered_indices
+    let result_packages: Vec<PackageListItem> = scored
         .into_iter()
         .skip(skip)
         .take(take)
-        .map(|i| PackageListItem::from_package(&db.packages[i]))
+        .map(|(i, score)| PackageListItem::from_package(&db.packages[i], score))
         .collect();
 
     let elapsed = start.elapsed();
@@ -187,3 +267,283 @@ pub fn get_package_by_id(id: String) -> Option<CTANPackage> {
         .position(|idx| idx.id_lower == id_lower)
         .map(|i| db.packages[i].clone())
 }
+
+/// A line of `tlmgr` output, emitted as `tlmgr-progress`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TlmgrProgressEvent {
+    pub package: String,
+    pub stream: String,
+    pub line: String,
+}
+
+/// Only names that exist in the embedded database reach `tlmgr`, so arbitrary strings
+/// (options, shell metacharacters) are never passed along.
+fn validate_package_name(name: &str) -> Result<String, String> {
+    let valid_chars = !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid_chars {
+        return Err(format!("Invalid package name: {}", name));
+    }
+
+    let name_lower = name.to_lowercase();
+    let db = get_db();
+    db.search_index
+        .iter()
+        .position(|idx| idx.id_lower == name_lower)
+        .map(|i| db.packages[i].id.clone())
+        .ok_or_else(|| format!("Unknown CTAN package: {}", name))
+}
+
+fn tlmgr_command() -> Command {
+    let mut cmd = Command::new("tlmgr");
+    cmd.env("PATH", crate::compiler::get_augmented_path());
+    cmd
+}
+
+/// Install a package with `tlmgr install`, streaming its output as `tlmgr-progress`.
+/// Returns the full output once tlmgr exits.
+#[tauri::command]
+pub async fn install_package(name: String, app_handle: AppHandle) -> Result<String, String> {
+    let package = validate_package_name(&name)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut child = tlmgr_command()
+            .args(["install", &package])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run tlmgr: {}", e))?;
+
+        let stdout = spawn_tlmgr_reader(child.stdout.take(), "stdout", &package, &app_handle);
+        let stderr = spawn_tlmgr_reader(child.stderr.take(), "stderr", &package, &app_handle);
+        let status = child.wait().map_err(|e| e.to_string())?;
+
+        let output = format!(
+            "{}{}",
+            stdout.join().unwrap_or_default(),
+            stderr.join().unwrap_or_default()
+        );
+        if status.success() {
+            Ok(output)
+        } else {
+            Err(format!(
+                "tlmgr install {} failed with status {:?}\n{}",
+                package,
+                status.code(),
+                output
+            ))
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Forward each line of a tlmgr pipe as an event and return everything read.
+fn spawn_tlmgr_reader<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    stream: &'static str,
+    package: &str,
+    app_handle: &AppHandle,
+) -> JoinHandle<String> {
+    let package = package.to_string();
+    let app_handle = app_handle.clone();
+
+    std::thread::spawn(move || {
+        let mut collected = String::new();
+        let Some(pipe) = pipe else { return collected };
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            let _ = app_handle.emit(
+                "tlmgr-progress",
+                TlmgrProgressEvent {
+                    package: package.clone(),
+                    stream: stream.to_string(),
+                    line: line.clone(),
+                },
+            );
+            collected.push_str(&line);
+            collected.push('\n');
+        }
+        collected
+    })
+}
+
+/// Whether `tlmgr info --only-installed` reports the package as installed.
+#[tauri::command]
+pub async fn is_package_installed(name: String) -> Result<bool, String> {
+    let package = validate_package_name(&name)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let output = tlmgr_command()
+            .args(["info", "--only-installed", &package])
+            .output()
+            .map_err(|e| format!("Failed to run tlmgr: {}", e))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(output.status.success()
+            && stdout.lines().any(|line| {
+                let line = line.trim();
+                line.starts_with("installed:") && line.contains("Yes")
+            }))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+const CTAN_API: &str = "https://ctan.org/json/2.0";
+/// Package detail requests in flight at once during a refresh
+const CTAN_FETCH_CONCURRENCY: usize = 16;
+/// Share of failed package detail requests (in percent) above which a refresh is abandoned
+const CTAN_MAX_FAILED_PERCENT: usize = 5;
+
+/// Entry of `/packages`
+#[derive(Deserialize)]
+struct ApiPackageSummary {
+    key: String,
+    name: String,
+    #[serde(default)]
+    caption: String,
+}
+
+/// The parts of `/pkg/<key>` the browser uses
+#[derive(Deserialize)]
+struct ApiPackageDetails {
+    version: Option<ApiVersion>,
+    #[serde(default)]
+    topics: Vec<String>,
+    home: Option<String>,
+    ctan: Option<ApiCtanPath>,
+}
+
+#[derive(Deserialize)]
+struct ApiVersion {
+    number: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiCtanPath {
+    path: Option<String>,
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<T, String> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Download the package list and per-package details from CTAN into the embedded format.
+/// Packages whose details could not be fetched keep their details from `previous`; if
+/// too many fail, the whole refresh fails rather than replacing good data with blanks.
+async fn fetch_ctan_packages(previous: &[CTANPackage]) -> Result<Vec<CTANPackage>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let summaries: Vec<ApiPackageSummary> =
+        fetch_json(&client, &format!("{}/packages", CTAN_API)).await?;
+    let topics: Vec<CTANTopic> = fetch_json(&client, &format!("{}/topics", CTAN_API)).await?;
+    let topic_details: HashMap<String, String> =
+        topics.into_iter().map(|t| (t.key, t.details)).collect();
+
+    let previous: HashMap<&str, &CTANPackage> =
+        previous.iter().map(|pkg| (pkg.id.as_str(), pkg)).collect();
+
+    let results = futures_util::stream::iter(summaries)
+        .map(|summary| {
+            let client = &client;
+            let topic_details = &topic_details;
+            let previous = &previous;
+            async move {
+                let url = format!("{}/pkg/{}", CTAN_API, summary.key);
+                let details = fetch_json::<ApiPackageDetails>(client, &url).await;
+                let failed = details.is_err();
+                let (version, topics, home, ctan) = match details {
+                    Ok(d) => (
+                        d.version.and_then(|v| v.number).unwrap_or_default(),
+                        Some(
+                            d.topics
+                                .into_iter()
+                                .map(|key| CTANTopic {
+                                    details: topic_details.get(&key).cloned().unwrap_or_default(),
+                                    key,
+                                })
+                                .collect(),
+                        ),
+                        d.home,
+                        d.ctan.and_then(|c| c.path),
+                    ),
+                    // Keep the package listed, with whatever details we had before
+                    Err(_) => match previous.get(summary.key.as_str()) {
+                        Some(old) => (
+                            old.version.clone(),
+                            old.topics.clone(),
+                            old.home.clone(),
+                            old.ctan.clone(),
+                        ),
+                        None => (String::new(), Some(Vec::new()), None, None),
+                    },
+                };
+                let package = CTANPackage {
+                    id: summary.key,
+                    name: summary.name,
+                    caption: summary.caption,
+                    version,
+                    topics,
+                    home,
+                    ctan,
+                };
+                (package, failed)
+            }
+        })
+        .buffer_unordered(CTAN_FETCH_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    let failed = results.iter().filter(|(_, failed)| *failed).count();
+    if failed * 100 > results.len() * CTAN_MAX_FAILED_PERCENT {
+        return Err(format!(
+            "details of {} of {} packages could not be fetched",
+            failed,
+            results.len()
+        ));
+    }
+
+    Ok(results.into_iter().map(|(package, _)| package).collect())
+}
+
+/// Replace the package database with the live list from CTAN and cache it in the app
+/// data dir for later launches. On network failure, or when too many package details
+/// fail to download, the current (cached or embedded) database stays in use and the
+/// error is returned. Returns the package count.
+#[tauri::command]
+pub async fn refresh_ctan_db(app_handle: AppHandle) -> Result<usize, String> {
+    let current = get_db();
+    let packages = fetch_ctan_packages(&current.packages).await.map_err(|e| {
+        format!(
+            "Could not reach CTAN, keeping the current package list ({} packages): {}",
+            current.packages.len(),
+            e
+        )
+    })?;
+
+    let path = cache_path(&app_handle)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(&packages).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+
+    Ok(set_db(packages))
+}
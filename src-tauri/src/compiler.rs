@@ -1,8 +1,16 @@
 #![allow(dead_code)]
 
+use regex::Regex;
 use std::env;
-use std::path::Path;
-use std::process::Command;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 
 fn is_allowed_engine(engine: &str) -> bool {
     let allowed_engines = [
@@ -18,8 +26,89 @@ fn is_allowed_engine(engine: &str) -> bool {
     allowed_engines.contains(&name.as_str())
 }
 
+fn is_allowed_bib_engine(bib_engine: &str) -> bool {
+    let allowed_bib_engines = ["bibtex", "biber"];
+    let name = Path::new(bib_engine)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default();
+
+    allowed_bib_engines.contains(&name.as_str())
+}
+
+/// Shell-escape mode for a single compile call.
+///
+/// This is opt-in per call and never persisted: `\write18` lets a document run arbitrary
+/// commands, so callers must request it explicitly each time (e.g. for minted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellEscape {
+    Disabled,
+    Enabled,
+    /// Only the commands in the TeX distribution's `shell_escape_commands` allowlist
+    Restricted,
+}
+
+impl ShellEscape {
+    pub fn from_flags(shell_escape: bool, restricted: bool) -> Self {
+        match (shell_escape, restricted) {
+            (true, _) => ShellEscape::Enabled,
+            (false, true) => ShellEscape::Restricted,
+            (false, false) => ShellEscape::Disabled,
+        }
+    }
+}
+
+/// Helper: the shell-escape flag for an engine, if any should be passed.
+fn shell_escape_flag(engine: &str, mode: ShellEscape) -> Option<String> {
+    let prefix = if engine_name(engine) == "latexmk" {
+        "--"
+    } else {
+        "-"
+    };
+
+    match mode {
+        ShellEscape::Disabled => None,
+        ShellEscape::Enabled => Some(format!("{}shell-escape", prefix)),
+        ShellEscape::Restricted => Some(format!("{}shell-restricted", prefix)),
+    }
+}
+
+/// Engine used when a resource has no stored build command
+pub const DEFAULT_ENGINE: &str = "pdflatex";
+
+/// Split a stored build command such as `latexmk -xelatex` into engine and arguments.
+/// Shell-escape flags are dropped since shell escape must be requested per call
+/// (see [`ShellEscape`]); an empty command yields [`DEFAULT_ENGINE`].
+pub fn parse_build_command(command: &str) -> (String, Vec<String>) {
+    let mut parts = command.split_whitespace();
+    let Some(engine) = parts.next() else {
+        return (DEFAULT_ENGINE.to_string(), Vec::new());
+    };
+
+    let args = parts
+        .filter(|arg| {
+            let flag = arg.trim_start_matches('-');
+            !(flag.starts_with("shell-escape")
+                || flag.starts_with("shell-restricted")
+                || flag == "enable-write18")
+        })
+        .map(str::to_string)
+        .collect();
+
+    (engine.to_string(), args)
+}
+
+/// The value of a `-jobname=<name>` (or `--jobname=<name>`) argument, if any.
+fn job_name(args: &[String]) -> Option<&str> {
+    args.iter()
+        .rev()
+        .find_map(|arg| arg.trim_start_matches('-').strip_prefix("jobname="))
+        .filter(|name| !name.is_empty())
+}
+
 // Helper to add common LaTeX paths.
-fn get_augmented_path() -> String {
+pub(crate) fn get_augmented_path() -> String {
     let current_path = env::var("PATH").unwrap_or_default();
     let delimiter = if cfg!(windows) { ";" } else { ":" };
 
@@ -82,12 +171,260 @@ fn run_command_generic(
     }
 }
 
+/// A single diagnostic extracted from a LaTeX `.log` file.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LogMessage {
+    pub severity: String, // "error" | "warning"
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+/// Outcome of a compilation run, including the raw output and (optionally) parsed log messages.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CompileResult {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub messages: Option<Vec<LogMessage>>,
+}
+
+/// Compile a document once. `shell_escape` is opt-in for this call only and is never
+/// persisted; see [`ShellEscape`].
 pub fn compile(
     file_path: &str,
     engine: &str,
     args: Vec<String>,
     output_dir: &str,
+    timeout_secs: Option<u64>,
+    shell_escape: ShellEscape,
 ) -> Result<String, String> {
+    let result = compile_with_result(
+        file_path,
+        engine,
+        args,
+        output_dir,
+        timeout_secs,
+        shell_escape,
+        false,
+    )?;
+
+    if result.success {
+        Ok("Compilation successful".to_string())
+    } else {
+        Err(format!(
+            "Compilation failed with status code: {:?}\n\nSTDOUT:\n{}\n\nSTDERR:\n{}",
+            result.exit_code, result.stdout, result.stderr
+        ))
+    }
+}
+
+/// Like `compile`, but returns a `CompileResult` instead of collapsing failures into `Err`.
+/// When `parse_messages` is set, the `.log` next to the source is parsed into `messages`.
+/// `timeout_secs` kills the engine if it runs longer (e.g. a runaway `\def` loop); `None` waits forever.
+pub fn compile_with_result(
+    file_path: &str,
+    engine: &str,
+    args: Vec<String>,
+    output_dir: &str,
+    timeout_secs: Option<u64>,
+    shell_escape: ShellEscape,
+    parse_messages: bool,
+) -> Result<CompileResult, String> {
+    let path = Path::new(file_path);
+    // A `-jobname` renames the log, e.g. when compiling a wrapped `_preview.tex`
+    let log_name = match job_name(&args) {
+        Some(job) => format!("{}.log", job),
+        None => path
+            .with_extension("log")
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+    };
+    let (mut cmd, new_path_env) =
+        build_compile_command(file_path, engine, args, output_dir, shell_escape)?;
+
+    // Execute command with enhanced error mapping.
+    let output = run_with_timeout(&mut cmd, timeout_secs).map_err(|e| match e {
+        RunError::TimedOut(secs) => format!("Compilation timed out after {} seconds", secs),
+        RunError::Io(e) => format!(
+            "Failed to execute command '{}'. \nSystem Error: {} \nDebug Path: {}",
+            engine, e, new_path_env
+        ),
+    })?;
+
+    let messages = if parse_messages {
+        // The log is written next to the PDF, i.e. in the output directory if one was given
+        let log_dir = resolve_output_dir(path, output_dir)
+            .unwrap_or_else(|| path.parent().unwrap_or(Path::new(".")).to_path_buf());
+        let log_path = log_dir.join(log_name);
+        Some(parse_log(&log_path.to_string_lossy()).unwrap_or_default())
+    } else {
+        None
+    };
+
+    Ok(CompileResult {
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        messages,
+    })
+}
+
+/// Run the full LaTeX build: engine → bib_engine (on the aux/bcf) → remaining engine passes.
+/// `passes` is the total number of engine runs (default 3). Returns the combined log of every stage.
+/// `shell_escape` applies to every engine pass.
+#[allow(clippy::too_many_arguments)]
+pub fn compile_document(
+    file_path: &str,
+    engine: &str,
+    args: Vec<String>,
+    output_dir: &str,
+    bib_engine: Option<&str>,
+    passes: Option<u32>,
+    timeout_secs: Option<u64>,
+    shell_escape: ShellEscape,
+) -> Result<String, String> {
+    if let Some(bib) = bib_engine {
+        if !is_allowed_bib_engine(bib) {
+            return Err(format!(
+                "Invalid bibliography engine: {}. Allowed engines are: bibtex, biber",
+                bib
+            ));
+        }
+    }
+
+    let passes = passes.unwrap_or(3).max(1);
+    let path = Path::new(file_path);
+    let parent_dir = path.parent().unwrap_or(Path::new("."));
+    let job_name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid file name")?
+        .to_string();
+
+    let mut combined_log = String::new();
+
+    for pass in 1..=passes {
+        let result = compile_with_result(
+            file_path,
+            engine,
+            args.clone(),
+            output_dir,
+            timeout_secs,
+            shell_escape,
+            false,
+        )?;
+
+        combined_log.push_str(&format!(
+            "=== Pass {}/{}: {} ===\n{}{}\n",
+            pass, passes, engine, result.stdout, result.stderr
+        ));
+
+        if !result.success {
+            return Err(format!(
+                "Compilation failed in pass {} with status code: {:?}\n\n{}",
+                pass, result.exit_code, combined_log
+            ));
+        }
+
+        // The bibliography runs once, between the first and second engine pass
+        if pass == 1 {
+            if let Some(bib) = bib_engine {
+                let mut cmd = Command::new(bib);
+                cmd.current_dir(parent_dir);
+                cmd.env("PATH", get_augmented_path());
+
+                // The aux/bcf live in the output directory when one is used
+                if let Some(out_dir) = resolve_output_dir(path, output_dir) {
+                    if is_biber(bib) {
+                        cmd.arg(format!("--output-directory={}", out_dir.to_string_lossy()));
+                    } else {
+                        // bibtex must run next to the aux; keep .bib lookups relative to the source
+                        let delimiter = if cfg!(windows) { ";" } else { ":" };
+                        cmd.current_dir(&out_dir);
+                        cmd.env(
+                            "BIBINPUTS",
+                            format!("{}{}", parent_dir.to_string_lossy(), delimiter),
+                        );
+                    }
+                }
+                cmd.arg(&job_name);
+
+                let output = run_with_timeout(&mut cmd, timeout_secs).map_err(|e| match e {
+                    RunError::TimedOut(secs) => {
+                        format!("{} timed out after {} seconds", bib, secs)
+                    }
+                    RunError::Io(e) => format!("Failed to execute command '{}': {}", bib, e),
+                })?;
+
+                // bibtex exits non-zero on mere warnings, so record the status but keep going
+                combined_log.push_str(&format!(
+                    "=== {} (status {:?}) ===\n{}{}\n",
+                    bib,
+                    output.status.code(),
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+        }
+    }
+
+    Ok(combined_log)
+}
+
+/// Helper: resolve `output_dir` to an absolute path, relative to the source's parent.
+/// Returns `None` when no output directory was requested.
+fn resolve_output_dir(source: &Path, output_dir: &str) -> Option<PathBuf> {
+    if output_dir.trim().is_empty() {
+        return None;
+    }
+
+    let out = Path::new(output_dir);
+    if out.is_absolute() {
+        Some(out.to_path_buf())
+    } else {
+        let parent_dir = source.parent().unwrap_or(Path::new("."));
+        let parent_dir = parent_dir
+            .canonicalize()
+            .unwrap_or_else(|_| parent_dir.to_path_buf());
+        Some(parent_dir.join(out))
+    }
+}
+
+/// Helper: the output directory flag understood by each engine.
+fn output_dir_flag(engine: &str, dir: &str) -> Option<String> {
+    match engine_name(engine).as_str() {
+        "pdflatex" | "xelatex" | "lualatex" => Some(format!("-output-directory={}", dir)),
+        "latexmk" => Some(format!("-outdir={}", dir)),
+        _ => None,
+    }
+}
+
+fn engine_name(engine: &str) -> String {
+    Path::new(engine)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default()
+}
+
+fn is_biber(bib_engine: &str) -> bool {
+    engine_name(bib_engine) == "biber"
+}
+
+/// Helper: validate inputs and prepare the engine command (cwd, PATH, args, filename).
+/// Returns the command together with the augmented PATH for error reporting.
+fn build_compile_command(
+    file_path: &str,
+    engine: &str,
+    args: Vec<String>,
+    output_dir: &str,
+    shell_escape: ShellEscape,
+) -> Result<(Command, String), String> {
     // 1. Validate engine
     if !is_allowed_engine(engine) {
         return Err(format!(
@@ -119,34 +456,311 @@ pub fn compile(
         cmd.arg(arg);
     }
 
+    if let Some(flag) = shell_escape_flag(engine, shell_escape) {
+        cmd.arg(flag);
+    }
+
     // Handle output directory
-    if !output_dir.is_empty() {
-        // Note: Output directory args should be handled by the caller/args.
+    if let Some(out_dir) = resolve_output_dir(path, output_dir) {
+        fs::create_dir_all(&out_dir)
+            .map_err(|e| format!("Failed to create output directory {:?}: {}", out_dir, e))?;
+        if let Some(flag) = output_dir_flag(engine, &out_dir.to_string_lossy()) {
+            cmd.arg(flag);
+        }
     }
 
     // Always add the filename last
     cmd.arg(file_name);
 
-    // Execute command with enhanced error mapping.
-    let output = cmd.output().map_err(|e| {
-        format!(
-            "Failed to execute command '{}'. \nSystem Error: {} \nDebug Path: {}",
-            engine, e, new_path_env
-        )
-    })?;
+    Ok((cmd, new_path_env))
+}
 
-    if output.status.success() {
-        Ok("Compilation successful".to_string())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        Err(format!(
-            "Compilation failed with status code: {:?}\n\nSTDOUT:\n{}\n\nSTDERR:\n{}",
-            output.status.code(),
-            stdout,
-            stderr
-        ))
+/// A line of engine output, emitted as `compile-output`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CompileOutputEvent {
+    pub seq: u64,
+    pub stream: String, // "stdout" | "stderr"
+    pub line: String,
+}
+
+/// Final status of a streamed compilation, emitted as `compile-done`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CompileDoneEvent {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+}
+
+/// Start a compilation and stream its output line-by-line as `compile-output` events,
+/// followed by a single `compile-done` event. Returns as soon as the engine is spawned.
+pub fn compile_streaming(
+    file_path: &str,
+    engine: &str,
+    args: Vec<String>,
+    output_dir: &str,
+    shell_escape: ShellEscape,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let (mut cmd, new_path_env) =
+        build_compile_command(file_path, engine, args, output_dir, shell_escape)?;
+
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            format!(
+                "Failed to execute command '{}'. \nSystem Error: {} \nDebug Path: {}",
+                engine, e, new_path_env
+            )
+        })?;
+
+    // Shared sequence so the frontend can order interleaved stdout/stderr lines
+    let seq = Arc::new(AtomicU64::new(0));
+    let stdout_reader = spawn_line_emitter(child.stdout.take(), "stdout", &seq, &app_handle);
+    let stderr_reader = spawn_line_emitter(child.stderr.take(), "stderr", &seq, &app_handle);
+
+    std::thread::spawn(move || {
+        let _ = stdout_reader.join();
+        let _ = stderr_reader.join();
+
+        let done = match child.wait() {
+            Ok(status) => CompileDoneEvent {
+                success: status.success(),
+                exit_code: status.code(),
+            },
+            Err(_) => CompileDoneEvent {
+                success: false,
+                exit_code: None,
+            },
+        };
+        let _ = app_handle.emit("compile-done", done);
+    });
+
+    Ok(())
+}
+
+fn spawn_line_emitter<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    stream: &'static str,
+    seq: &Arc<AtomicU64>,
+    app_handle: &AppHandle,
+) -> JoinHandle<()> {
+    let seq = Arc::clone(seq);
+    let app_handle = app_handle.clone();
+
+    std::thread::spawn(move || {
+        let Some(pipe) = pipe else { return };
+        let mut reader = BufReader::new(pipe);
+        let mut buf = Vec::new();
+
+        // read_until instead of lines(): TeX output is not guaranteed to be UTF-8
+        while let Ok(n) = reader.read_until(b'\n', &mut buf) {
+            if n == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buf).trim_end().to_string();
+            let _ = app_handle.emit(
+                "compile-output",
+                CompileOutputEvent {
+                    seq: seq.fetch_add(1, Ordering::SeqCst),
+                    stream: stream.to_string(),
+                    line,
+                },
+            );
+            buf.clear();
+        }
+    })
+}
+
+enum RunError {
+    TimedOut(u64),
+    Io(std::io::Error),
+}
+
+/// Helper: run a command to completion, killing it once `timeout_secs` elapses.
+fn run_with_timeout(cmd: &mut Command, timeout_secs: Option<u64>) -> Result<Output, RunError> {
+    // Its own process group, so a timeout also reaches what latexmk or \write18 started
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
+
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(RunError::Io)?;
+
+    // Drain the pipes on background threads so a chatty engine can't block on a full pipe
+    let stdout_reader = spawn_pipe_reader(child.stdout.take());
+    let stderr_reader = spawn_pipe_reader(child.stderr.take());
+
+    let status = match timeout_secs {
+        None => child.wait().map_err(RunError::Io)?,
+        Some(secs) => {
+            let deadline = Instant::now() + Duration::from_secs(secs);
+            loop {
+                if let Some(status) = child.try_wait().map_err(RunError::Io)? {
+                    break status;
+                }
+                if Instant::now() >= deadline {
+                    kill_process_tree(&mut child);
+                    let _ = child.wait();
+                    return Err(RunError::TimedOut(secs));
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/// Kill `child` and every process it started; grandchildren would otherwise keep running
+/// and keep the output files locked.
+fn kill_process_tree(child: &mut std::process::Child) {
+    let pid = child.id().to_string();
+    #[cfg(unix)]
+    let killed = Command::new("kill")
+        .args(["-9", &format!("-{}", pid)])
+        .status();
+    #[cfg(windows)]
+    let killed = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid])
+        .status();
+    #[cfg(not(any(unix, windows)))]
+    let killed: std::io::Result<std::process::ExitStatus> =
+        Err(std::io::ErrorKind::Unsupported.into());
+
+    if !killed.is_ok_and(|status| status.success()) {
+        let _ = child.kill();
+    }
+}
+
+fn spawn_pipe_reader<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Parse a LaTeX `.log` file into structured errors and warnings.
+pub fn parse_log(log_path: &str) -> Result<Vec<LogMessage>, String> {
+    // Logs are not guaranteed to be valid UTF-8 (e.g. latin-1 input files).
+    let bytes = std::fs::read(log_path).map_err(|e| format!("Failed to read log file: {}", e))?;
+    Ok(parse_log_content(&String::from_utf8_lossy(&bytes)))
+}
+
+/// Parse the contents of a LaTeX log. Tracks the file currently being read via the
+/// `(file ... )` nesting that TeX writes, so messages can be attributed to a source file.
+pub fn parse_log_content(content: &str) -> Vec<LogMessage> {
+    let line_marker = Regex::new(r"^l\.(\d+)\s?(.*)$").unwrap();
+    let input_line = Regex::new(r"on input line (\d+)").unwrap();
+    let paragraph_lines = Regex::new(r"at lines? (\d+)").unwrap();
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut messages = Vec::new();
+    let mut file_stack: Vec<String> = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some(error) = line.strip_prefix("! ") {
+            let mut message = error.trim().to_string();
+            let mut line_no = None;
+
+            // Look ahead for the `l.<n>` marker pointing into the source
+            for next in lines.iter().skip(i + 1).take(10) {
+                if let Some(caps) = line_marker.captures(next) {
+                    line_no = caps[1].parse::<u32>().ok();
+                    let context = caps[2].trim();
+                    if message.starts_with("Undefined control sequence") && !context.is_empty() {
+                        message = format!("{} {}", message, context);
+                    }
+                    break;
+                }
+            }
+
+            messages.push(LogMessage {
+                severity: "error".to_string(),
+                file: current_file(&file_stack),
+                line: line_no,
+                message,
+            });
+        } else if line.starts_with("Overfull \\hbox") || line.starts_with("Underfull \\hbox") {
+            messages.push(LogMessage {
+                severity: "warning".to_string(),
+                file: current_file(&file_stack),
+                line: paragraph_lines
+                    .captures(line)
+                    .and_then(|c| c[1].parse::<u32>().ok()),
+                message: line.trim().to_string(),
+            });
+        } else if line.contains("Warning:") {
+            // LaTeX/package warnings may wrap onto following lines
+            let mut message = line.trim().to_string();
+            let mut j = i + 1;
+            while j < lines.len() && !lines[j].trim().is_empty() && !input_line.is_match(&message) {
+                message.push(' ');
+                message.push_str(lines[j].trim());
+                j += 1;
+            }
+
+            messages.push(LogMessage {
+                severity: "warning".to_string(),
+                file: current_file(&file_stack),
+                line: input_line
+                    .captures(&message)
+                    .and_then(|c| c[1].parse::<u32>().ok()),
+                message,
+            });
+        }
+
+        track_open_files(line, &mut file_stack);
+        i += 1;
     }
+
+    messages
+}
+
+/// Helper: update the stack of open files from the parentheses on a log line.
+fn track_open_files(line: &str, file_stack: &mut Vec<String>) {
+    let chars: Vec<char> = line.chars().collect();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        match chars[idx] {
+            '(' => {
+                let name: String = chars[idx + 1..]
+                    .iter()
+                    .take_while(|c| !c.is_whitespace() && **c != '(' && **c != ')')
+                    .collect();
+                idx += name.chars().count();
+                // Only names that look like paths count as files; keep the parens balanced either way
+                if name.contains('.') || name.contains('/') {
+                    file_stack.push(name);
+                } else {
+                    file_stack.push(String::new());
+                }
+            }
+            ')' => {
+                file_stack.pop();
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+}
+
+/// Helper: the innermost real file, skipping placeholders pushed for non-file parens.
+fn current_file(file_stack: &[String]) -> Option<String> {
+    file_stack.iter().rev().find(|f| !f.is_empty()).cloned()
 }
 
 pub fn run_synctex(args: Vec<String>, cwd_path: &str) -> Result<String, String> {
@@ -159,6 +773,93 @@ pub fn run_synctex(args: Vec<String>, cwd_path: &str) -> Result<String, String>
     run_command_generic("synctex", args, cwd)
 }
 
+/// PDF position from a forward (source → PDF) SyncTeX search.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncPos {
+    pub page: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Source location from an inverse (PDF → source) SyncTeX search.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncSource {
+    pub file: String,
+    pub line: u32,
+}
+
+/// Forward search: find where `line` (and `column`, 0 if unknown) of `file` ends up in `pdf`.
+pub fn synctex_forward(file: &str, line: u32, column: u32, pdf: &str) -> Result<SyncPos, String> {
+    let args = vec![
+        "view".to_string(),
+        "-i".to_string(),
+        format!("{}:{}:{}", line, column, file),
+        "-o".to_string(),
+        pdf.to_string(),
+    ];
+    let output = run_command_generic("synctex", args, Path::new(pdf).parent())?;
+    parse_synctex_view(&output)
+}
+
+/// Inverse search: find the source location for a point on a PDF page.
+pub fn synctex_inverse(pdf: &str, page: u32, x: f32, y: f32) -> Result<SyncSource, String> {
+    let args = vec![
+        "edit".to_string(),
+        "-o".to_string(),
+        format!("{}:{}:{}:{}", page, x, y, pdf),
+    ];
+    let output = run_command_generic("synctex", args, Path::new(pdf).parent())?;
+    parse_synctex_edit(&output)
+}
+
+/// Helper: iterate `Key:value` records of synctex output.
+fn synctex_fields(output: &str) -> impl Iterator<Item = (&str, &str)> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// Parse the first result of `synctex view`.
+fn parse_synctex_view(output: &str) -> Result<SyncPos, String> {
+    let mut page = None;
+    let mut x = None;
+    let mut y = None;
+
+    for (key, value) in synctex_fields(output) {
+        match key {
+            "Page" if page.is_none() => page = value.parse::<u32>().ok(),
+            "x" if x.is_none() => x = value.parse::<f32>().ok(),
+            "y" if y.is_none() => y = value.parse::<f32>().ok(),
+            _ => {}
+        }
+    }
+
+    match (page, x, y) {
+        (Some(page), Some(x), Some(y)) => Ok(SyncPos { page, x, y }),
+        _ => Err("SyncTeX returned no result for this location".to_string()),
+    }
+}
+
+/// Parse the first result of `synctex edit`.
+fn parse_synctex_edit(output: &str) -> Result<SyncSource, String> {
+    let mut file = None;
+    let mut line = None;
+
+    for (key, value) in synctex_fields(output) {
+        match key {
+            "Input" if file.is_none() => file = Some(value.to_string()),
+            "Line" if line.is_none() => line = value.parse::<u32>().ok(),
+            _ => {}
+        }
+    }
+
+    match (file, line) {
+        (Some(file), Some(line)) => Ok(SyncSource { file, line }),
+        _ => Err("SyncTeX returned no result for this location".to_string()),
+    }
+}
+
 pub fn run_texcount(args: Vec<String>, cwd_path: &str) -> Result<String, String> {
     let cwd = if cwd_path.is_empty() {
         None
@@ -182,6 +883,47 @@ mod tests {
         assert!(is_allowed_engine("texcount"));
     }
 
+    #[test]
+    fn test_parse_build_command() {
+        assert_eq!(
+            parse_build_command("latexmk -xelatex  -interaction=nonstopmode"),
+            (
+                "latexmk".to_string(),
+                vec![
+                    "-xelatex".to_string(),
+                    "-interaction=nonstopmode".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            parse_build_command("  "),
+            (DEFAULT_ENGINE.to_string(), Vec::new())
+        );
+        assert_eq!(
+            parse_build_command("pdflatex -shell-escape --shell-escape -enable-write18 -synctex=1"),
+            ("pdflatex".to_string(), vec!["-synctex=1".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_job_name() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(job_name(&args(&["-interaction=nonstopmode"])), None);
+        assert_eq!(
+            job_name(&args(&["-synctex=1", "-jobname=chapter"])),
+            Some("chapter")
+        );
+        assert_eq!(job_name(&args(&["--jobname=notes"])), Some("notes"));
+    }
+
+    #[test]
+    fn test_is_allowed_bib_engine() {
+        assert!(is_allowed_bib_engine("bibtex"));
+        assert!(is_allowed_bib_engine("/usr/bin/biber"));
+        assert!(!is_allowed_bib_engine("pdflatex"));
+        assert!(!is_allowed_bib_engine("rm"));
+    }
+
     #[test]
     fn test_is_allowed_engine_with_paths() {
         assert!(is_allowed_engine("/usr/local/bin/pdflatex"));
@@ -189,4 +931,102 @@ mod tests {
             assert!(is_allowed_engine("C:\\texlive\\bin\\pdflatex.exe"));
         }
     }
+
+    #[test]
+    fn test_output_dir_flag_per_engine() {
+        assert_eq!(
+            output_dir_flag("pdflatex", "/tmp/out").as_deref(),
+            Some("-output-directory=/tmp/out")
+        );
+        assert_eq!(
+            output_dir_flag("xelatex", "/tmp/out").as_deref(),
+            Some("-output-directory=/tmp/out")
+        );
+        assert_eq!(
+            output_dir_flag("lualatex", "/tmp/out").as_deref(),
+            Some("-output-directory=/tmp/out")
+        );
+        assert_eq!(
+            output_dir_flag("/usr/bin/latexmk", "/tmp/out").as_deref(),
+            Some("-outdir=/tmp/out")
+        );
+        assert_eq!(output_dir_flag("texcount", "/tmp/out"), None);
+    }
+
+    #[test]
+    fn test_shell_escape_flag_only_when_requested() {
+        assert_eq!(
+            shell_escape_flag("pdflatex", ShellEscape::from_flags(false, false)),
+            None
+        );
+        assert_eq!(
+            shell_escape_flag("pdflatex", ShellEscape::from_flags(true, false)).as_deref(),
+            Some("-shell-escape")
+        );
+        assert_eq!(
+            shell_escape_flag("latexmk", ShellEscape::from_flags(true, false)).as_deref(),
+            Some("--shell-escape")
+        );
+        assert_eq!(
+            shell_escape_flag("xelatex", ShellEscape::from_flags(false, true)).as_deref(),
+            Some("-shell-restricted")
+        );
+    }
+
+    #[test]
+    fn test_parse_synctex_output() {
+        let view = "This is SyncTeX command line utility, version 1.5\n\
+SyncTeX result begin\n\
+Output:/tmp/main.pdf\n\
+Page:2\n\
+x:133.768356\n\
+y:250.131058\n\
+h:133.768356\n\
+SyncTeX result end\n";
+        assert_eq!(
+            parse_synctex_view(view),
+            Ok(SyncPos {
+                page: 2,
+                x: 133.768_36,
+                y: 250.131_06
+            })
+        );
+
+        let edit = "SyncTeX result begin\n\
+Output:/tmp/main.pdf\n\
+Input:/tmp/./chapter.tex\n\
+Line:42\n\
+Column:-1\n\
+SyncTeX result end\n";
+        assert_eq!(
+            parse_synctex_edit(edit),
+            Ok(SyncSource {
+                file: "/tmp/./chapter.tex".to_string(),
+                line: 42
+            })
+        );
+
+        assert!(parse_synctex_view("SyncTeX result begin\nSyncTeX result end\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_log_content() {
+        let log = "(./main.tex\n\
+! Undefined control sequence.\n\
+l.12 \\foo\n\
+\n\
+Overfull \\hbox (3.2pt too wide) in paragraph at lines 20--22\n\
+LaTeX Warning: Reference `fig:a' on page 1 undefined on input line 30.\n\
+)";
+        let messages = parse_log_content(log);
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].severity, "error");
+        assert_eq!(messages[0].file.as_deref(), Some("./main.tex"));
+        assert_eq!(messages[0].line, Some(12));
+        assert_eq!(messages[0].message, "Undefined control sequence. \\foo");
+        assert_eq!(messages[1].severity, "warning");
+        assert_eq!(messages[1].line, Some(20));
+        assert_eq!(messages[2].line, Some(30));
+    }
 }
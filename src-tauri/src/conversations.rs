@@ -0,0 +1,188 @@
+//! Agent Conversation Persistence
+//!
+//! Saves the agent's full message list, including tool calls and tool outputs, so a
+//! session can be resumed later without losing the tool context.
+
+use sqlx::{Pool, Row, Sqlite};
+use uuid::Uuid;
+
+use crate::agent::AgentMessage;
+
+/// A saved conversation as listed in the UI
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub message_count: i64,
+}
+
+/// Characters of the first user message used when no title is given
+const TITLE_LENGTH: usize = 60;
+
+/// Save `messages` as conversation `id`, replacing its previous transcript, or as a
+/// new conversation when `id` is `None`. System messages are skipped since the agent
+/// rebuilds its system prompt on every run. Returns the conversation id.
+pub async fn save_conversation(
+    pool: &Pool<Sqlite>,
+    id: Option<&str>,
+    title: Option<&str>,
+    messages: &[AgentMessage],
+) -> Result<String, String> {
+    let id = id
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let title = title
+        .map(str::to_string)
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| default_title(messages));
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        "INSERT INTO conversations (id, title) VALUES (?, ?)
+         ON CONFLICT(id) DO UPDATE SET title = excluded.title, updated_at = datetime('now')",
+    )
+    .bind(&id)
+    .bind(&title)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM messages WHERE conversation_id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for (position, msg) in messages.iter().filter(|m| m.role != "system").enumerate() {
+        let tool_calls = msg
+            .tool_calls
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let usage = msg
+            .usage
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+
+        sqlx::query(
+            "INSERT INTO messages (conversation_id, position, role, content, tool_calls, tool_call_id, usage)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(position as i64)
+        .bind(&msg.role)
+        .bind(&msg.content)
+        .bind(tool_calls)
+        .bind(&msg.tool_call_id)
+        .bind(usage)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// The first user message, shortened to `TITLE_LENGTH` characters
+fn default_title(messages: &[AgentMessage]) -> String {
+    let first = messages
+        .iter()
+        .find(|m| m.role == "user")
+        .and_then(|m| m.content.as_deref())
+        .map(|c| c.lines().next().unwrap_or("").trim())
+        .filter(|c| !c.is_empty());
+
+    match first {
+        Some(text) if text.chars().count() > TITLE_LENGTH => {
+            format!("{}…", text.chars().take(TITLE_LENGTH).collect::<String>())
+        }
+        Some(text) => text.to_string(),
+        None => "Untitled conversation".to_string(),
+    }
+}
+
+/// Messages of a saved conversation in their original order
+pub async fn load_conversation(pool: &Pool<Sqlite>, id: &str) -> Result<Vec<AgentMessage>, String> {
+    let exists: Option<String> = sqlx::query_scalar("SELECT id FROM conversations WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if exists.is_none() {
+        return Err(format!("Conversation not found: {}", id));
+    }
+
+    let rows = sqlx::query(
+        "SELECT role, content, tool_calls, tool_call_id, usage FROM messages
+         WHERE conversation_id = ? ORDER BY position",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let tool_calls: Option<String> = row.get("tool_calls");
+            let usage: Option<String> = row.get("usage");
+            Ok(AgentMessage {
+                role: row.get("role"),
+                content: row.get("content"),
+                tool_calls: tool_calls
+                    .map(|json| serde_json::from_str(&json))
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+                tool_call_id: row.get("tool_call_id"),
+                usage: usage
+                    .map(|json| serde_json::from_str(&json))
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+/// Saved conversations, most recently updated first
+pub async fn list_conversations(pool: &Pool<Sqlite>) -> Result<Vec<ConversationSummary>, String> {
+    let rows = sqlx::query(
+        "SELECT c.id, c.title, c.created_at, c.updated_at,
+                (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id) AS message_count
+         FROM conversations c
+         ORDER BY c.updated_at DESC",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ConversationSummary {
+            id: row.get("id"),
+            title: row.get("title"),
+            created_at: row
+                .get::<Option<String>, _>("created_at")
+                .unwrap_or_default(),
+            updated_at: row
+                .get::<Option<String>, _>("updated_at")
+                .unwrap_or_default(),
+            message_count: row.get("message_count"),
+        })
+        .collect())
+}
+
+/// Remove a conversation; its messages go with it via `ON DELETE CASCADE`
+pub async fn delete_conversation(pool: &Pool<Sqlite>, id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM conversations WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -14,6 +14,15 @@ pub struct Collection {
     pub created_at: Option<String>,
 }
 
+/// A collection plus how many resources it holds, for the sidebar
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct CollectionWithCount {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub collection: Collection,
+    pub resource_count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Resource {
     pub id: String,
@@ -28,6 +37,41 @@ pub struct Resource {
     pub updated_at: Option<String>,
 }
 
+/// Resources sharing the same file content
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub content_hash: String,
+    pub resources: Vec<Resource>,
+}
+
+/// A ranked full-text match from `resources_fts`
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SearchHit {
+    pub resource_id: String,
+    pub path: String,
+    pub title: Option<String>,
+    pub collection: String,
+    pub snippet: String,
+    pub rank: f64,
+}
+
+/// A user table and its columns, as reported by `PRAGMA table_info`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<ColumnInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// Declared type, e.g. "TEXT"; empty when the column has none
+    #[serde(rename = "type")]
+    pub data_type: String,
+    pub not_null: bool,
+    pub primary_key: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Document {
     pub id: String,
@@ -1,12 +1,46 @@
-use crate::database::entities::{Collection, Resource};
-use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
+use crate::database::entities::{
+    Collection, CollectionWithCount, ColumnInfo, DuplicateGroup, Resource, SearchHit, TableSchema,
+};
+use crate::types::metadata::typed_metadata_tables;
+use futures_util::TryStreamExt;
+use sqlx::{
+    migrate::MigrateDatabase,
+    sqlite::{
+        SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePoolOptions, SqliteRow,
+    },
+    Column, Connection, Pool, Row, Sqlite, TypeInfo, ValueRef,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Pool size used by the app; WAL lets these readers run alongside one writer
+pub const DEFAULT_POOL_SIZE: u32 = 8;
+
+/// How long a connection waits on a locked database before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Row cap for `run_query` so a careless SELECT can't flood the UI
+const MAX_QUERY_ROWS: usize = 10_000;
+
+/// Schema version of databases created before `user_version` was tracked: every
+/// migration up to 015_file_history
+const LEGACY_SCHEMA_VERSION: usize = 15;
+
+/// Version earlier builds stamped on legacy databases, which skipped the later migrations
+const OLD_LEGACY_STAMP: usize = 20;
+
+/// Schema version once 016_resources_fts has been applied
+const FTS_SCHEMA_VERSION: usize = 16;
 
 pub struct DatabaseManager {
     pub pool: Pool<Sqlite>,
+    db_path: String,
 }
 
 impl DatabaseManager {
-    pub async fn new(data_dir: &str) -> Result<Self, sqlx::Error> {
+    pub async fn new(data_dir: &str, max_connections: u32) -> Result<Self, sqlx::Error> {
         let db_path = format!("{}/project.db", data_dir);
         let db_url = format!("sqlite://{}", db_path);
 
@@ -14,15 +48,65 @@ impl DatabaseManager {
             Sqlite::create_database(&db_url).await?;
         }
 
-        let pool = SqlitePoolOptions::new().connect(&db_url).await?;
+        let connect_options = SqliteConnectOptions::from_str(&db_url)?
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(BUSY_TIMEOUT)
+            .foreign_keys(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections.max(1))
+            .connect_with(connect_options)
+            .await?;
 
         // Initialize schema
-        Self::init_schema(&pool).await?;
+        let previous_version = Self::init_schema(&pool).await?;
+        let manager = Self { pool, db_path };
 
-        Ok(Self { pool })
+        // The FTS migration can only seed titles and paths; read the content from disk
+        if previous_version < FTS_SCHEMA_VERSION {
+            if let Err(e) = manager.reindex_all_content().await {
+                eprintln!("Failed to index resource content: {}", e);
+            }
+        }
+
+        Ok(manager)
     }
 
-    async fn init_schema(pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
+    /// Rebuild the database file to reclaim space left by deleted rows
+    pub async fn vacuum(&self) -> Result<(), String> {
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Copy the live database to `dest_path` with SQLite's online backup API.
+    /// Runs on its own connection and yields between steps, so concurrent queries keep working.
+    pub async fn backup_to(&self, dest_path: &str) -> Result<(), String> {
+        let src_path = self.db_path.clone();
+        let dest_path = dest_path.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let src = rusqlite::Connection::open_with_flags(
+                &src_path,
+                rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+            )
+            .map_err(|e| e.to_string())?;
+            let mut dest = rusqlite::Connection::open(&dest_path).map_err(|e| e.to_string())?;
+
+            let backup =
+                rusqlite::backup::Backup::new(&src, &mut dest).map_err(|e| e.to_string())?;
+            backup
+                .run_to_completion(100, std::time::Duration::from_millis(10), None)
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    /// Apply pending migrations; returns the schema version the database started at
+    async fn init_schema(pool: &Pool<Sqlite>) -> Result<usize, sqlx::Error> {
         // Load all schema files in numeric order
         // New migrations should be added at the end with incrementing numbers
         let schemas = [
@@ -41,6 +125,8 @@ impl DatabaseManager {
             include_str!("../../migrations/013_resource_dtx_ins.sql"), // 12
             include_str!("../../migrations/014_add_collection_path.sql"), // 13
             include_str!("../../migrations/015_file_history.sql"), // 14 - Local history
+            include_str!("../../migrations/016_resources_fts.sql"), // 15 - Full-text search
+            include_str!("../../migrations/017_agent_conversations.sql"), // 16 - Agent conversations
         ];
 
         // Check current version
@@ -61,14 +147,35 @@ impl DatabaseManager {
             .unwrap_or((0,));
 
             if has_preamble_types.0 > 0 {
-                println!("Detected legacy DB with preamble_types. Setting version to 20.");
-                current_version = 20;
+                println!(
+                    "Detected legacy DB with preamble_types. Setting version to {}.",
+                    LEGACY_SCHEMA_VERSION
+                );
+                current_version = LEGACY_SCHEMA_VERSION;
                 sqlx::query(&format!("PRAGMA user_version = {}", current_version))
                     .execute(pool)
                     .await?;
             }
         }
 
+        // Repair databases an earlier build stamped past migrations it never ran
+        if current_version == OLD_LEGACY_STAMP {
+            let has_fts: (i32,) = sqlx::query_as(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='resources_fts'",
+            )
+            .fetch_one(pool)
+            .await
+            .unwrap_or((0,));
+            if has_fts.0 == 0 {
+                println!(
+                    "Legacy DB is missing later migrations. Resuming from version {}.",
+                    LEGACY_SCHEMA_VERSION
+                );
+                current_version = LEGACY_SCHEMA_VERSION;
+            }
+        }
+        let previous_version = current_version;
+
         for (i, init_script) in schemas.iter().enumerate() {
             if i < current_version {
                 continue;
@@ -111,24 +218,43 @@ impl DatabaseManager {
                 }
             }
 
+            // Each migration runs in its own transaction: a real failure rolls it back and
+            // leaves user_version untouched, so the next launch retries from the same point
+            let mut tx = pool.begin().await?;
+            let mut failures = Vec::new();
             for stmt in statements {
                 let stmt = stmt.trim();
-                if !stmt.is_empty() {
-                    if let Err(e) = sqlx::query(stmt).execute(pool).await {
-                        eprintln!("SQL Warning in migration {}: {}", i, e);
-                        // Depending on policy, we might want to stop here.
-                        // But for now, detailed logging is good.
+                if stmt.is_empty() {
+                    continue;
+                }
+                if let Err(e) = sqlx::query(stmt).execute(&mut *tx).await {
+                    if is_idempotent_migration_error(&e) {
+                        println!("Migration {}: skipping already-applied statement: {}", i, e);
+                    } else {
+                        eprintln!("SQL error in migration {}: {}\n{}", i, e, stmt);
+                        failures.push(e.to_string());
                     }
                 }
             }
 
+            if !failures.is_empty() {
+                tx.rollback().await?;
+                return Err(sqlx::Error::Protocol(format!(
+                    "Migration {} failed ({} error(s)): {}",
+                    i,
+                    failures.len(),
+                    failures.join("; ")
+                )));
+            }
+
             // Update version after success
             let new_version = i + 1;
             sqlx::query(&format!("PRAGMA user_version = {}", new_version))
-                .execute(pool)
+                .execute(&mut *tx)
                 .await?;
+            tx.commit().await?;
         }
-        Ok(())
+        Ok(previous_version)
     }
 
     // --- New Methods ---
@@ -140,6 +266,19 @@ impl DatabaseManager {
             .map_err(|e| e.to_string())
     }
 
+    /// All collections with their resource counts in a single query
+    pub async fn get_collections_with_counts(&self) -> Result<Vec<CollectionWithCount>, String> {
+        sqlx::query_as::<_, CollectionWithCount>(
+            "SELECT c.*, COUNT(r.id) AS resource_count
+             FROM collections c
+             LEFT JOIN resources r ON r.collection = c.name
+             GROUP BY c.name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.to_string())
+    }
+
     pub async fn get_resources_by_collection(
         &self,
         collection: &str,
@@ -176,6 +315,57 @@ impl DatabaseManager {
         q.fetch_all(&self.pool).await.map_err(|e| e.to_string())
     }
 
+    /// Directory a collection lives in: its stored `path`, or else the deepest directory
+    /// containing all of its resources. `None` for an unknown or empty collection.
+    pub async fn collection_root(&self, name: &str) -> Result<Option<std::path::PathBuf>, String> {
+        let stored: Option<(Option<String>,)> =
+            sqlx::query_as("SELECT path FROM collections WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        let Some((path,)) = stored else {
+            return Ok(None);
+        };
+        if let Some(path) = path.filter(|p| !p.is_empty()) {
+            return Ok(Some(std::path::PathBuf::from(path)));
+        }
+
+        let paths: Vec<(String,)> =
+            sqlx::query_as("SELECT path FROM resources WHERE collection = ?")
+                .bind(name)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| e.to_string())?;
+
+        let mut root: Option<std::path::PathBuf> = None;
+        for (path,) in &paths {
+            let Some(dir) = std::path::Path::new(path).parent() else {
+                continue;
+            };
+            root = Some(match root {
+                None => dir.to_path_buf(),
+                Some(current) => current
+                    .components()
+                    .zip(dir.components())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect(),
+            });
+        }
+        Ok(root.filter(|r| r.parent().is_some()))
+    }
+
+    pub async fn collection_exists(&self, name: &str) -> Result<bool, String> {
+        let exists: Option<(String,)> =
+            sqlx::query_as("SELECT name FROM collections WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        Ok(exists.is_some())
+    }
+
     pub async fn create_collection(&self, collection: &Collection) -> Result<(), String> {
         sqlx::query(
             "INSERT OR IGNORE INTO collections (name, description, icon, type, path) VALUES (?, ?, ?, ?, ?)",
@@ -192,23 +382,133 @@ impl DatabaseManager {
     }
 
     pub async fn add_resource(&self, resource: &Resource) -> Result<(), String> {
+        let mut conn = self.pool.acquire().await.map_err(|e| e.to_string())?;
+        Self::insert_resource(&mut *conn, resource).await?;
+
+        let content = indexable_content(&resource.path).await;
+        Self::index_resource(&mut conn, resource, &content).await
+    }
+
+    /// Register a file that already exists on disk (e.g. one just created in-app).
+    /// Returns the new resource id.
+    pub async fn create_resource(
+        &self,
+        path: &str,
+        kind: &str,
+        collection: &str,
+        title: Option<&str>,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        if !self.collection_exists(collection).await? {
+            return Err(format!("Collection '{}' not found", collection));
+        }
+
+        let content_hash = crate::history::hash_file(std::path::Path::new(path))
+            .map_err(|e| format!("Failed to hash {}: {}", path, e))?;
+
+        let resource = Resource {
+            id: uuid::Uuid::new_v4().to_string(),
+            path: path.to_string(),
+            kind: kind.to_string(),
+            collection: collection.to_string(),
+            title: title.map(str::to_string),
+            content_hash: Some(content_hash),
+            metadata: Some(metadata.unwrap_or(serde_json::json!({}))),
+            created_at: None,
+            updated_at: None,
+        };
+        self.add_resource(&resource).await?;
+        Ok(resource.id)
+    }
+
+    /// Insert a resource using any executor, so callers can batch inserts in a transaction
+    pub async fn insert_resource<'e, E>(executor: E, resource: &Resource) -> Result<(), String>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
         // Serialize metadata to JSON string
         let meta_str = serde_json::to_string(&resource.metadata).unwrap_or("{}".to_string());
 
         sqlx::query("INSERT OR REPLACE INTO resources (id, path, type, collection, title, content_hash, metadata) VALUES (?, ?, ?, ?, ?, ?, ?)")
             .bind(&resource.id)
-            .bind(&resource.path)
+            .bind(normalize_resource_path(&resource.path))
             .bind(&resource.kind)
             .bind(&resource.collection)
             .bind(&resource.title)
             .bind(&resource.content_hash)
             .bind(&meta_str)
-            .execute(&self.pool)
+            .execute(executor)
             .await
             .map_err(|e| e.to_string())?;
         Ok(())
     }
 
+    /// Replace the full-text index entry for a resource.
+    /// Binary or unreadable files are indexed with empty content so title and path stay searchable.
+    pub async fn index_resource(
+        conn: &mut SqliteConnection,
+        resource: &Resource,
+        content: &str,
+    ) -> Result<(), String> {
+        sqlx::query("DELETE FROM resources_fts WHERE resource_id = ?")
+            .bind(&resource.id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        sqlx::query(
+            "INSERT INTO resources_fts (resource_id, title, content, path) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&resource.id)
+        .bind(resource.title.as_deref().unwrap_or(""))
+        .bind(content)
+        .bind(normalize_resource_path(&resource.path))
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Re-read every resource from disk into the full-text index
+    pub async fn reindex_all_content(&self) -> Result<usize, String> {
+        let resources = sqlx::query_as::<_, Resource>("SELECT * FROM resources")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        for resource in &resources {
+            let content = indexable_content(&resource.path).await;
+            Self::index_resource(&mut tx, resource, &content).await?;
+        }
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(resources.len())
+    }
+
+    /// Ranked full-text search over indexed resources, best matches first
+    pub async fn search_fts(&self, query: &str, limit: i64) -> Result<Vec<SearchHit>, String> {
+        let match_expr = fts_match_expression(query);
+        if match_expr.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        sqlx::query_as::<_, SearchHit>(
+            "SELECT r.id AS resource_id, r.path, r.title, r.collection,
+                    snippet(resources_fts, 2, '<b>', '</b>', '...', 16) AS snippet,
+                    bm25(resources_fts) AS rank
+             FROM resources_fts
+             JOIN resources r ON r.id = resources_fts.resource_id
+             WHERE resources_fts MATCH ?
+             ORDER BY rank
+             LIMIT ?",
+        )
+        .bind(&match_expr)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.to_string())
+    }
+
     pub async fn validate_identifier(&self, table: &str, column: Option<&str>) -> bool {
         let is_valid_name = |s: &str| s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
         if !is_valid_name(table) {
@@ -222,6 +522,39 @@ impl DatabaseManager {
         true
     }
 
+    /// List every user table (skipping SQLite's internal `sqlite_*` tables) with its columns
+    pub async fn list_tables(&self) -> Result<Vec<TableSchema>, String> {
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let mut tables = Vec::with_capacity(names.len());
+        for name in names {
+            let rows = sqlx::query("SELECT * FROM pragma_table_info(?)")
+                .bind(&name)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let columns = rows
+                .iter()
+                .map(|r| ColumnInfo {
+                    name: r.get("name"),
+                    data_type: r.get("type"),
+                    not_null: r.get::<i64, _>("notnull") != 0,
+                    primary_key: r.get::<i64, _>("pk") != 0,
+                })
+                .collect();
+
+            tables.push(TableSchema { name, columns });
+        }
+
+        Ok(tables)
+    }
+
     pub async fn get_table_data(
         &self,
         table_name: String,
@@ -242,6 +575,14 @@ impl DatabaseManager {
             .map_err(|e| e.to_string())?;
 
         let columns: Vec<String> = schema_rows.iter().map(|r| r.get("name")).collect();
+        let affinities: Vec<ColumnAffinity> = schema_rows
+            .iter()
+            .map(|r| ColumnAffinity::from_declared(&r.get::<String, _>("type")))
+            .collect();
+        let timestamps: Vec<bool> = schema_rows
+            .iter()
+            .map(|r| is_timestamp_column(&r.get::<String, _>("name"), &r.get::<String, _>("type")))
+            .collect();
 
         // 2. Build Where Clause
         let mut where_clause = String::new();
@@ -299,18 +640,15 @@ impl DatabaseManager {
         let mut result_data = Vec::new();
         for row in rows {
             let mut map = serde_json::Map::new();
-            for col in &columns {
-                let val_res: Result<String, _> = row.try_get(col.as_str());
-                if let Ok(v) = val_res {
-                    map.insert(col.clone(), serde_json::Value::String(v));
+            for ((col, affinity), is_timestamp) in columns.iter().zip(&affinities).zip(&timestamps)
+            {
+                let value = cell_to_json(&row, col, *affinity)?;
+                let value = if *is_timestamp {
+                    normalize_timestamp(value)
                 } else {
-                    let int_res: Result<i64, _> = row.try_get(col.as_str());
-                    if let Ok(v) = int_res {
-                        map.insert(col.clone(), serde_json::Value::Number(v.into()));
-                    } else {
-                        map.insert(col.clone(), serde_json::Value::Null);
-                    }
-                }
+                    value
+                };
+                map.insert(col.clone(), value);
             }
             result_data.push(serde_json::Value::Object(map));
         }
@@ -318,6 +656,62 @@ impl DatabaseManager {
         Ok((result_data, total_count, columns))
     }
 
+    /// Run an ad-hoc read-only query with positional `?` params, returning rows like
+    /// `get_table_data` (at most `MAX_QUERY_ROWS`). Only a single SELECT/WITH statement is
+    /// accepted, and it runs on a read-only connection, which is what prevents writes.
+    pub async fn run_query(
+        &self,
+        sql: &str,
+        params: Vec<String>,
+    ) -> Result<(Vec<serde_json::Value>, i64, Vec<String>), String> {
+        let sql = ensure_read_only_query(sql)?;
+
+        let options = SqliteConnectOptions::new()
+            .filename(&self.db_path)
+            .read_only(true)
+            .busy_timeout(BUSY_TIMEOUT);
+        let mut conn = SqliteConnection::connect_with(&options)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut query = sqlx::query(sql);
+        for p in &params {
+            query = query.bind(p);
+        }
+
+        let mut columns: Vec<String> = Vec::new();
+        let mut affinities: Vec<ColumnAffinity> = Vec::new();
+        let mut result_data = Vec::new();
+        {
+            let mut rows = query.fetch(&mut conn);
+            while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+                if columns.is_empty() {
+                    columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    affinities = row
+                        .columns()
+                        .iter()
+                        .map(|c| ColumnAffinity::from_declared(c.type_info().name()))
+                        .collect();
+                }
+
+                let mut map = serde_json::Map::new();
+                for (col, affinity) in columns.iter().zip(&affinities) {
+                    map.insert(col.clone(), cell_to_json(&row, col, *affinity)?);
+                }
+                result_data.push(serde_json::Value::Object(map));
+
+                if result_data.len() == MAX_QUERY_ROWS {
+                    break;
+                }
+            }
+        }
+
+        let _ = conn.close().await;
+
+        let count = result_data.len() as i64;
+        Ok((result_data, count, columns))
+    }
+
     pub async fn update_cell(
         &self,
         table_name: String,
@@ -340,33 +734,541 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Update several columns of one row in a single statement.
+    /// Fails without writing anything if any column name is invalid.
+    pub async fn update_row(
+        &self,
+        table_name: String,
+        id: String,
+        values: HashMap<String, String>,
+    ) -> Result<(), String> {
+        if !self.validate_identifier(&table_name, None).await {
+            return Err("Invalid table name".to_string());
+        }
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        for column in values.keys() {
+            if !self.validate_identifier(&table_name, Some(column)).await {
+                return Err(format!("Invalid column name: {}", column));
+            }
+        }
+
+        let entries: Vec<(&String, &String)> = values.iter().collect();
+        let assignments: Vec<String> = entries.iter().map(|(c, _)| format!("{} = ?", c)).collect();
+        let query = format!(
+            "UPDATE {} SET {} WHERE id = ?",
+            table_name,
+            assignments.join(", ")
+        );
+
+        let mut q = sqlx::query(&query);
+        for (_, value) in &entries {
+            q = q.bind(value);
+        }
+        q.bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Delete one row by id, returning how many rows matched
+    pub async fn delete_row(&self, table_name: String, id: String) -> Result<u64, String> {
+        if !self.validate_identifier(&table_name, None).await {
+            return Err("Invalid table name".to_string());
+        }
+
+        let query = format!("DELETE FROM {} WHERE id = ?", table_name);
+        let result = sqlx::query(&query)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Write every row of a table to `output_path` as "csv" or "json", returning the path written
+    pub async fn export_table(
+        &self,
+        table_name: String,
+        format: &str,
+        output_path: &str,
+    ) -> Result<String, String> {
+        if !self.validate_identifier(&table_name, None).await {
+            return Err("Invalid table name".to_string());
+        }
+        if format != "csv" && format != "json" {
+            return Err(format!("Unsupported export format: {}", format));
+        }
+
+        let schema_rows = sqlx::query(&format!("PRAGMA table_info({})", table_name))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        if schema_rows.is_empty() {
+            return Err(format!("Table not found: {}", table_name));
+        }
+        let columns: Vec<String> = schema_rows.iter().map(|r| r.get("name")).collect();
+        let affinities: Vec<ColumnAffinity> = schema_rows
+            .iter()
+            .map(|r| ColumnAffinity::from_declared(&r.get::<String, _>("type")))
+            .collect();
+
+        let file = std::fs::File::create(output_path).map_err(|e| e.to_string())?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        let query = format!("SELECT * FROM {}", table_name);
+        let mut rows = sqlx::query(&query).fetch(&self.pool);
+
+        if format == "csv" {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            csv_writer
+                .write_record(&columns)
+                .map_err(|e| e.to_string())?;
+
+            while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+                let mut record = Vec::with_capacity(columns.len());
+                for (col, affinity) in columns.iter().zip(&affinities) {
+                    record.push(match cell_to_json(&row, col, *affinity)? {
+                        serde_json::Value::Null => String::new(),
+                        serde_json::Value::String(s) => s,
+                        other => other.to_string(),
+                    });
+                }
+                csv_writer
+                    .write_record(&record)
+                    .map_err(|e| e.to_string())?;
+            }
+            csv_writer.flush().map_err(|e| e.to_string())?;
+        } else {
+            writer.write_all(b"[").map_err(|e| e.to_string())?;
+            let mut first = true;
+            while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+                let mut map = serde_json::Map::new();
+                for (col, affinity) in columns.iter().zip(&affinities) {
+                    map.insert(col.clone(), cell_to_json(&row, col, *affinity)?);
+                }
+
+                if !first {
+                    writer.write_all(b",").map_err(|e| e.to_string())?;
+                }
+                first = false;
+                writer.write_all(b"\n  ").map_err(|e| e.to_string())?;
+                serde_json::to_writer(&mut writer, &map).map_err(|e| e.to_string())?;
+            }
+            writer.write_all(b"\n]\n").map_err(|e| e.to_string())?;
+            writer.flush().map_err(|e| e.to_string())?;
+        }
+
+        Ok(output_path.to_string())
+    }
+
     pub async fn delete_collection(&self, collection_name: &str) -> Result<(), String> {
-        // First, delete all resources associated with this collection
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        // First, drop typed metadata for every resource in the collection
+        let resources: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, type FROM resources WHERE collection = ?")
+                .bind(collection_name)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        for (id, kind) in &resources {
+            Self::delete_typed_metadata_rows(&mut tx, id, kind).await?;
+        }
+
+        // Then the resources themselves
         sqlx::query("DELETE FROM resources WHERE collection = ?")
             .bind(collection_name)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await
             .map_err(|e| e.to_string())?;
 
-        // Then, delete the collection itself
+        // Finally, delete the collection itself
         sqlx::query("DELETE FROM collections WHERE name = ?")
             .bind(collection_name)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await
             .map_err(|e| e.to_string())?;
 
-        Ok(())
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+
+    /// Rename a collection and re-point everything that references it by name.
+    /// Fails if `new_name` is already taken.
+    pub async fn rename_collection(&self, old_name: &str, new_name: &str) -> Result<(), String> {
+        if old_name == new_name {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        let taken: Option<(String,)> =
+            sqlx::query_as("SELECT name FROM collections WHERE name = ?")
+                .bind(new_name)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        if taken.is_some() {
+            return Err(format!("Collection '{}' already exists", new_name));
+        }
+
+        let renamed = sqlx::query("UPDATE collections SET name = ? WHERE name = ?")
+            .bind(new_name)
+            .bind(old_name)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        if renamed.rows_affected() == 0 {
+            return Err(format!("Collection '{}' not found", old_name));
+        }
+
+        // ON UPDATE CASCADE covers these when foreign keys are enforced; update explicitly
+        // so databases opened without them don't end up with orphaned rows
+        for table in ["resources", "documents", "bibliography"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET collection = ? WHERE collection = ?",
+                table
+            ))
+            .bind(new_name)
+            .bind(old_name)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+
+    pub async fn move_resource(
+        &self,
+        resource_id: &str,
+        target_collection: &str,
+    ) -> Result<(), String> {
+        self.move_resources(&[resource_id.to_string()], target_collection)
+            .await
+    }
+
+    /// Move resources into `target_collection` in one transaction; nothing moves if the
+    /// collection or any of the resources doesn't exist.
+    pub async fn move_resources(
+        &self,
+        ids: &[String],
+        target_collection: &str,
+    ) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        let exists: Option<(String,)> =
+            sqlx::query_as("SELECT name FROM collections WHERE name = ?")
+                .bind(target_collection)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        if exists.is_none() {
+            return Err(format!("Collection '{}' not found", target_collection));
+        }
+
+        for id in ids {
+            let moved = sqlx::query(
+                "UPDATE resources SET collection = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            )
+            .bind(target_collection)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+            if moved.rows_affected() == 0 {
+                return Err(format!("Resource '{}' not found", id));
+            }
+        }
+
+        tx.commit().await.map_err(|e| e.to_string())
     }
 
     pub async fn delete_resource(&self, id: &str) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        Self::delete_resource_rows(&mut tx, id).await?;
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+
+    async fn delete_resource_rows(conn: &mut SqliteConnection, id: &str) -> Result<(), String> {
+        // Remove typed metadata with the resource so junction tables don't keep stale rows
+        let kind: Option<(String,)> = sqlx::query_as("SELECT type FROM resources WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some((kind,)) = kind {
+            Self::delete_typed_metadata_rows(&mut *conn, id, &kind).await?;
+        }
+
         sqlx::query("DELETE FROM resources WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await
             .map_err(|e| e.to_string())?;
         Ok(())
     }
 
+    /// Groups of two or more resources with the same non-null `content_hash`
+    pub async fn find_duplicates(&self) -> Result<Vec<DuplicateGroup>, String> {
+        let resources = sqlx::query_as::<_, Resource>(
+            "SELECT * FROM resources WHERE content_hash IN (
+                 SELECT content_hash FROM resources
+                 WHERE content_hash IS NOT NULL
+                 GROUP BY content_hash HAVING COUNT(*) > 1
+             )
+             ORDER BY content_hash, path",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let mut groups: Vec<DuplicateGroup> = Vec::new();
+        for resource in resources {
+            let hash = resource.content_hash.clone().unwrap_or_default();
+            match groups.last_mut() {
+                Some(group) if group.content_hash == hash => group.resources.push(resource),
+                _ => groups.push(DuplicateGroup {
+                    content_hash: hash,
+                    resources: vec![resource],
+                }),
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Fold `remove_ids` into `keep_id`: their dependency edges are repointed to the kept
+    /// resource, then they are deleted. Every removed resource must have the kept one's
+    /// content hash, so only true duplicates are merged. Returns the removed resources.
+    pub async fn merge_duplicates(
+        &self,
+        keep_id: &str,
+        remove_ids: &[String],
+    ) -> Result<Vec<Resource>, String> {
+        if remove_ids.iter().any(|id| id == keep_id) {
+            return Err("The kept resource can't also be removed".to_string());
+        }
+
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        let kept: Option<(Option<String>,)> =
+            sqlx::query_as("SELECT content_hash FROM resources WHERE id = ?")
+                .bind(keep_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        let Some((Some(kept_hash),)) = kept else {
+            return Err(match kept {
+                None => format!("Resource not found: {}", keep_id),
+                Some(_) => format!("Resource {} has no content hash to compare", keep_id),
+            });
+        };
+
+        let mut removed = Vec::new();
+        for id in remove_ids {
+            let resource = sqlx::query_as::<_, Resource>("SELECT * FROM resources WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Resource not found: {}", id))?;
+            // Dropping the transaction rolls back anything merged before this one
+            if resource.content_hash.as_deref() != Some(kept_hash.as_str()) {
+                return Err(format!(
+                    "{} is not a duplicate of {}: their contents differ",
+                    resource.path, keep_id
+                ));
+            }
+
+            // Edges the kept resource already has would collide; those are dropped below
+            for column in ["source_id", "target_id"] {
+                sqlx::query(&format!(
+                    "UPDATE OR IGNORE dependencies SET {0} = ? WHERE {0} = ?",
+                    column
+                ))
+                .bind(keep_id)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+            sqlx::query("DELETE FROM dependencies WHERE source_id = ? OR target_id = ?")
+                .bind(id)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            Self::delete_typed_metadata_rows(&mut tx, id, &resource.kind).await?;
+            sqlx::query("DELETE FROM resources WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            removed.push(resource);
+        }
+
+        // A duplicate that depended on its twin would leave a self-loop behind
+        sqlx::query("DELETE FROM dependencies WHERE source_id = ? AND target_id = ?")
+            .bind(keep_id)
+            .bind(keep_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(removed)
+    }
+
+    /// Resources at `path` or inside it, as `(id, normalized stored path)`. Stored paths
+    /// are normalized too, so rows saved with a different spelling still match.
+    async fn resources_at_path(&self, path: &str) -> Result<Vec<(String, String)>, String> {
+        let path = normalize_resource_path(path);
+        let prefix = format!("{}{}", path, std::path::MAIN_SEPARATOR);
+
+        let rows: Vec<(String, String)> = sqlx::query_as("SELECT id, path FROM resources")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, stored)| (id, normalize_resource_path(&stored)))
+            .filter(|(_, stored)| *stored == path || stored.starts_with(&prefix))
+            .collect())
+    }
+
+    /// Point resources at `from` (or inside it, for directories) to the matching path under `to`.
+    /// A resource already registered at a destination path was overwritten on disk, so it
+    /// is deleted first. Returns the `(old, new)` path of every moved resource.
+    pub async fn move_resource_paths(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<Vec<(String, String)>, String> {
+        let from_len = normalize_resource_path(from).len();
+        let to = normalize_resource_path(to);
+
+        let moves: Vec<(String, String, String)> = self
+            .resources_at_path(from)
+            .await?
+            .into_iter()
+            .map(|(id, path)| {
+                let new_path = format!("{}{}", to, &path[from_len..]);
+                (id, path, new_path)
+            })
+            .collect();
+        let moved_ids: HashSet<&str> = moves.iter().map(|(id, _, _)| id.as_str()).collect();
+
+        let mut replaced = Vec::new();
+        for (_, _, new_path) in &moves {
+            if let Some(existing) = self.get_resource_by_path(new_path).await? {
+                if !moved_ids.contains(existing.id.as_str()) {
+                    replaced.push(existing.id);
+                }
+            }
+        }
+
+        // Rows already at a target path are replaced in the same transaction as the move
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        for id in &replaced {
+            Self::delete_resource_rows(&mut tx, id).await?;
+        }
+        for (id, _, new_path) in &moves {
+            sqlx::query(
+                "UPDATE resources SET path = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            )
+            .bind(new_path)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+            sqlx::query("UPDATE resources_fts SET path = ? WHERE resource_id = ?")
+                .bind(new_path)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(moves
+            .into_iter()
+            .map(|(_, old_path, new_path)| (old_path, new_path))
+            .collect())
+    }
+
+    /// Delete resources at `path` or inside it. Returns the paths of the removed resources.
+    pub async fn delete_resources_at_path(&self, path: &str) -> Result<Vec<String>, String> {
+        let resources = self.resources_at_path(path).await?;
+        for (id, _) in &resources {
+            self.delete_resource(id).await?;
+        }
+        Ok(resources.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// Delete typed metadata and all junction rows for a resource, keeping the resource itself
+    pub async fn delete_typed_metadata(
+        &self,
+        resource_id: &str,
+        resource_type: &str,
+    ) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        Self::delete_typed_metadata_rows(&mut tx, resource_id, resource_type).await?;
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+
+    async fn delete_typed_metadata_rows(
+        conn: &mut SqliteConnection,
+        resource_id: &str,
+        resource_type: &str,
+    ) -> Result<(), String> {
+        for (table, id_column) in typed_metadata_tables(resource_type) {
+            sqlx::query(&format!("DELETE FROM {} WHERE {} = ?", table, id_column))
+                .bind(resource_id)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    // --- Tags ---
+
+    /// Ids of every resource carrying `tag`, across all resource types
+    pub async fn get_resources_by_tag(&self, tag: &str) -> Result<Vec<String>, String> {
+        let query = format!(
+            "SELECT DISTINCT resource_id FROM ({}) WHERE tag = ? ORDER BY resource_id",
+            tag_union_query()
+        );
+        let rows: Vec<(String,)> = sqlx::query_as(&query)
+            .bind(tag)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Every tag in use with the number of resources carrying it, most used first
+    pub async fn get_all_tags(&self) -> Result<Vec<(String, i64)>, String> {
+        let query = format!(
+            "SELECT tag, COUNT(DISTINCT resource_id) AS usage
+             FROM ({})
+             GROUP BY tag
+             ORDER BY usage DESC, tag",
+            tag_union_query()
+        );
+        sqlx::query_as(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
     // --- Dependency Management ---
 
     pub async fn add_dependency(
@@ -375,6 +1277,13 @@ impl DatabaseManager {
         target_id: &str,
         relation_type: &str,
     ) -> Result<(), String> {
+        // Reject edges that would close a cycle, e.g. documents \input-ing each other
+        let edges = self.get_all_dependencies().await?;
+        let adjacency = build_adjacency(edges.iter().map(|(s, t, _)| (s.as_str(), t.as_str())));
+        if let Some(path) = find_path(&adjacency, target_id, source_id) {
+            return Err(cycle_error(source_id, target_id, &path));
+        }
+
         sqlx::query("INSERT OR REPLACE INTO dependencies (source_id, target_id, relation_type) VALUES (?, ?, ?)")
             .bind(source_id)
             .bind(target_id)
@@ -385,6 +1294,46 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Add many dependencies at once, loading the graph a single time. Each edge is checked
+    /// for cycles against the stored edges plus those accepted before it, and all are
+    /// inserted in one transaction. Returns the outcome of every edge, in order.
+    pub async fn add_dependencies(
+        &self,
+        links: &[(String, String, &str)],
+    ) -> Result<Vec<Result<(), String>>, String> {
+        let edges = self.get_all_dependencies().await?;
+        let mut adjacency = build_adjacency(edges.iter().map(|(s, t, _)| (s.as_str(), t.as_str())));
+
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        let mut outcomes = Vec::with_capacity(links.len());
+        for (source_id, target_id, relation_type) in links {
+            if let Some(path) = find_path(&adjacency, target_id, source_id) {
+                outcomes.push(Err(cycle_error(source_id, target_id, &path)));
+                continue;
+            }
+            let inserted = sqlx::query(
+                "INSERT OR REPLACE INTO dependencies (source_id, target_id, relation_type) VALUES (?, ?, ?)",
+            )
+            .bind(source_id)
+            .bind(target_id)
+            .bind(relation_type)
+            .execute(&mut *tx)
+            .await;
+            match inserted {
+                Ok(_) => {
+                    adjacency
+                        .entry(source_id.as_str())
+                        .or_default()
+                        .insert(target_id.as_str());
+                    outcomes.push(Ok(()));
+                }
+                Err(e) => outcomes.push(Err(e.to_string())),
+            }
+        }
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(outcomes)
+    }
+
     pub async fn get_dependencies(
         &self,
         source_id: &str,
@@ -409,6 +1358,66 @@ impl DatabaseManager {
         q.fetch_all(&self.pool).await.map_err(|e| e.to_string())
     }
 
+    /// Resources that depend on `target_id` (the reverse of `get_dependencies`)
+    pub async fn get_dependents(
+        &self,
+        target_id: &str,
+        relation_type: Option<&str>,
+    ) -> Result<Vec<Resource>, String> {
+        let query = if relation_type.is_some() {
+            "SELECT r.* FROM resources r
+             JOIN dependencies d ON r.id = d.source_id
+             WHERE d.target_id = ? AND d.relation_type = ?"
+        } else {
+            "SELECT DISTINCT r.* FROM resources r
+             JOIN dependencies d ON r.id = d.source_id
+             WHERE d.target_id = ?"
+        };
+
+        let mut q = sqlx::query_as::<_, Resource>(query).bind(target_id);
+
+        if let Some(rt) = relation_type {
+            q = q.bind(rt);
+        }
+
+        q.fetch_all(&self.pool).await.map_err(|e| e.to_string())
+    }
+
+    /// The `build_command` stored in a resource's typed metadata, for the types that have one
+    pub async fn get_build_command(&self, id: &str, kind: &str) -> Result<Option<String>, String> {
+        let table = match kind {
+            "file" => "resource_files",
+            "document" => "resource_documents",
+            "figure" => "resource_figures",
+            _ => return Ok(None),
+        };
+
+        let row: Option<(Option<String>,)> = sqlx::query_as(&format!(
+            "SELECT build_command FROM {} WHERE resource_id = ?",
+            table
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(row
+            .and_then(|(command,)| command)
+            .filter(|command| !command.trim().is_empty()))
+    }
+
+    /// Contents of a resource's file as UTF-8, whatever encoding it is stored in
+    pub async fn read_resource(&self, id: &str) -> Result<String, String> {
+        let resource = self
+            .get_resource_by_id(id)
+            .await?
+            .ok_or_else(|| format!("Resource '{}' not found", id))?;
+        let bytes = tokio::fs::read(&resource.path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", resource.path, e))?;
+        crate::text_encoding::decode_text(&bytes).map_err(|e| format!("{}: {}", resource.path, e))
+    }
+
     pub async fn get_resource_by_id(&self, id: &str) -> Result<Option<Resource>, String> {
         let r = sqlx::query_as::<_, Resource>("SELECT * FROM resources WHERE id = ?")
             .bind(id)
@@ -418,6 +1427,50 @@ impl DatabaseManager {
         Ok(r)
     }
 
+    pub async fn get_resource_by_path(&self, path: &str) -> Result<Option<Resource>, String> {
+        // Rows stored before paths were normalized may still hold the raw spelling
+        sqlx::query_as::<_, Resource>("SELECT * FROM resources WHERE path = ? OR path = ?")
+            .bind(normalize_resource_path(path))
+            .bind(path)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Every stored resource path, for watching the files on disk
+    pub async fn get_all_resource_paths(&self) -> Result<Vec<String>, String> {
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT path FROM resources")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(rows.into_iter().map(|(path,)| path).collect())
+    }
+
+    /// Record a new content hash and re-index the file's current content
+    pub async fn update_resource_hash(&self, id: &str, content_hash: &str) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        sqlx::query(
+            "UPDATE resources SET content_hash = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(content_hash)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let resource = sqlx::query_as::<_, Resource>("SELECT * FROM resources WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(resource) = resource {
+            let content = indexable_content(&resource.path).await;
+            Self::index_resource(&mut tx, &resource, &content).await?;
+        }
+        tx.commit().await.map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
     pub async fn get_all_dependencies(&self) -> Result<Vec<(String, String, String)>, String> {
         let rows = sqlx::query("SELECT source_id, target_id, relation_type FROM dependencies")
             .fetch_all(&self.pool)
@@ -433,4 +1486,376 @@ impl DatabaseManager {
         }
         Ok(results)
     }
+
+    /// Find dependency cycles; each cycle lists resource ids in edge order
+    pub async fn detect_cycles(&self) -> Result<Vec<Vec<String>>, String> {
+        let edges = self.get_all_dependencies().await?;
+        let adjacency = build_adjacency(edges.iter().map(|(s, t, _)| (s.as_str(), t.as_str())));
+        Ok(find_cycles(&adjacency))
+    }
+}
+
+/// Junction tables linking resources to `custom_tags`
+const TAG_TABLES: [&str; 7] = [
+    "resource_file_tags",
+    "resource_document_tags",
+    "resource_table_tags",
+    "resource_figure_tags",
+    "resource_command_tags",
+    "resource_package_tags",
+    "resource_class_tags",
+];
+
+/// `(resource_id, tag)` rows from every tag junction table
+fn tag_union_query() -> String {
+    TAG_TABLES
+        .iter()
+        .map(|table| format!("SELECT resource_id, tag FROM {}", table))
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ")
+}
+
+type Adjacency<'a> = BTreeMap<&'a str, BTreeSet<&'a str>>;
+
+fn build_adjacency<'a>(edges: impl Iterator<Item = (&'a str, &'a str)>) -> Adjacency<'a> {
+    let mut adjacency: Adjacency = BTreeMap::new();
+    for (source, target) in edges {
+        adjacency.entry(source).or_default().insert(target);
+    }
+    adjacency
+}
+
+fn cycle_error(source_id: &str, target_id: &str, path: &[String]) -> String {
+    format!(
+        "Adding dependency {} -> {} would create a cycle: {} -> {}",
+        source_id,
+        target_id,
+        path.join(" -> "),
+        target_id
+    )
+}
+
+/// Path of ids from `from` to `to` (inclusive), if `to` is reachable
+fn find_path(adjacency: &Adjacency, from: &str, to: &str) -> Option<Vec<String>> {
+    let mut visited = HashSet::new();
+    let mut stack = vec![vec![from]];
+    while let Some(path) = stack.pop() {
+        let node = *path.last()?;
+        if node == to {
+            return Some(path.iter().map(|n| n.to_string()).collect());
+        }
+        if !visited.insert(node) {
+            continue;
+        }
+        for next in adjacency.get(node).into_iter().flatten() {
+            let mut next_path = path.clone();
+            next_path.push(next);
+            stack.push(next_path);
+        }
+    }
+    None
+}
+
+/// DFS over the graph, recording a cycle for every back edge found
+fn find_cycles(adjacency: &Adjacency) -> Vec<Vec<String>> {
+    fn visit<'a>(
+        node: &'a str,
+        adjacency: &Adjacency<'a>,
+        done: &mut HashSet<&'a str>,
+        stack: &mut Vec<&'a str>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        stack.push(node);
+        for &next in adjacency.get(node).into_iter().flatten() {
+            if let Some(pos) = stack.iter().position(|&n| n == next) {
+                cycles.push(stack[pos..].iter().map(|n| n.to_string()).collect());
+            } else if !done.contains(next) {
+                visit(next, adjacency, done, stack, cycles);
+            }
+        }
+        stack.pop();
+        done.insert(node);
+    }
+
+    let mut done = HashSet::new();
+    let mut cycles = Vec::new();
+    for &node in adjacency.keys() {
+        if !done.contains(node) {
+            visit(node, adjacency, &mut done, &mut Vec::new(), &mut cycles);
+        }
+    }
+    cycles
+}
+
+/// Canonical spelling of a resource path, applied when paths are stored and when they
+/// are looked up so symlinked or relative spellings of one file match. Components that
+/// don't exist (yet, or any more) are appended to their deepest existing ancestor.
+pub fn normalize_resource_path(path: &str) -> String {
+    let original = std::path::Path::new(path);
+    let mut existing = original;
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            _ => return path.to_string(),
+        }
+    }
+
+    // dunce keeps Windows paths in their usual form instead of `\\?\` verbatim paths
+    let Ok(mut resolved) = dunce::canonicalize(existing) else {
+        return path.to_string();
+    };
+    for name in rest.iter().rev() {
+        resolved.push(name);
+    }
+    resolved.to_string_lossy().to_string()
+}
+
+/// Text of a file for the full-text index; empty for binary or unreadable files
+async fn indexable_content(path: &str) -> String {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => crate::text_encoding::decode_text(&bytes).unwrap_or_default(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Quote each whitespace-separated term so LaTeX input like `\\section{` is not parsed as FTS5 syntax
+fn fts_match_expression(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Errors from re-running a statement whose effect is already in place (e.g. a legacy
+/// database that predates `user_version` tracking). Anything else is a real failure.
+fn is_idempotent_migration_error(e: &sqlx::Error) -> bool {
+    let message = e.to_string().to_lowercase();
+    message.contains("already exists") || message.contains("duplicate column name")
+}
+
+/// Accept a single SELECT (or WITH ... SELECT) statement, returning it without the
+/// optional trailing semicolon. Nothing here keeps a query from writing; that is the
+/// read-only connection's job. This only rejects other statements with a clear message.
+fn ensure_read_only_query(sql: &str) -> Result<&str, String> {
+    let masked = mask_sql_literals(sql);
+    let end = masked
+        .iter()
+        .rposition(|c| !c.is_ascii_whitespace() && *c != b';')
+        .map_or(0, |p| p + 1);
+    let start = masked
+        .iter()
+        .position(|c| !c.is_ascii_whitespace())
+        .unwrap_or(end);
+    if start >= end {
+        return Err("Query is empty".to_string());
+    }
+    if masked[start..end].contains(&b';') {
+        return Err("Only a single statement is allowed".to_string());
+    }
+
+    let first_word: String = masked[start..end]
+        .iter()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .map(|&c| c.to_ascii_uppercase() as char)
+        .collect();
+    match first_word.as_str() {
+        "SELECT" | "WITH" => Ok(&sql[start..end]),
+        _ => Err("Only SELECT queries are allowed".to_string()),
+    }
+}
+
+/// The bytes of `sql` with comments and the contents of string literals and quoted
+/// identifiers blanked out (their quotes are kept), so a `;` inside them isn't mistaken
+/// for syntax. Offsets are unchanged.
+fn mask_sql_literals(sql: &str) -> Vec<u8> {
+    let bytes = sql.as_bytes();
+    let mut masked = bytes.to_vec();
+    let find = |from: usize, needle: &[u8]| {
+        bytes[from..]
+            .windows(needle.len())
+            .position(|w| w == needle)
+            .map(|p| from + p)
+    };
+
+    let mut i = 0;
+    while i < bytes.len() {
+        // Range to blank and where scanning resumes
+        let (blank, next) = match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                // A doubled quote is an escaped one inside the literal
+                let mut j = i + 1;
+                let close = loop {
+                    match bytes.get(j) {
+                        None => break None,
+                        Some(&c) if c == quote && bytes.get(j + 1) == Some(&quote) => j += 2,
+                        Some(&c) if c == quote => break Some(j),
+                        Some(_) => j += 1,
+                    }
+                };
+                match close {
+                    Some(close) => (i + 1..close, close + 1),
+                    None => (i..bytes.len(), bytes.len()),
+                }
+            }
+            b'[' => match find(i, b"]") {
+                Some(close) => (i + 1..close, close + 1),
+                None => (i..bytes.len(), bytes.len()),
+            },
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                let end = find(i, b"\n").unwrap_or(bytes.len());
+                (i..end, end)
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = find(i + 2, b"*/").map_or(bytes.len(), |p| p + 2);
+                (i..end, end)
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        masked[blank].fill(b' ');
+        i = next;
+    }
+    masked
+}
+
+/// SQLite type affinity derived from a column's declared type
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnAffinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+impl ColumnAffinity {
+    /// Apply SQLite's affinity rules (https://www.sqlite.org/datatype3.html#determination_of_column_affinity)
+    fn from_declared(declared: &str) -> Self {
+        let declared = declared.to_uppercase();
+        if declared.contains("INT") {
+            Self::Integer
+        } else if ["CHAR", "CLOB", "TEXT"]
+            .iter()
+            .any(|t| declared.contains(t))
+        {
+            Self::Text
+        } else if declared.is_empty() || declared.contains("BLOB") {
+            Self::Blob
+        } else if ["REAL", "FLOA", "DOUB"]
+            .iter()
+            .any(|t| declared.contains(t))
+        {
+            Self::Real
+        } else {
+            Self::Numeric
+        }
+    }
+}
+
+/// Prefix marking base64-encoded BLOB cells so the grid can tell them from text
+const BLOB_MARKER: &str = "base64:";
+
+/// Read a cell as JSON, decoding by the column's affinity first and falling back
+/// to the value's own storage class, since SQLite doesn't enforce declared types
+fn cell_to_json(
+    row: &SqliteRow,
+    col: &str,
+    affinity: ColumnAffinity,
+) -> Result<serde_json::Value, String> {
+    let raw = row.try_get_raw(col).map_err(|e| e.to_string())?;
+    if raw.is_null() {
+        return Ok(serde_json::Value::Null);
+    }
+
+    let decoded = match affinity {
+        ColumnAffinity::Integer => row.try_get::<i64, _>(col).ok().map(Into::into),
+        ColumnAffinity::Real => row.try_get::<f64, _>(col).ok().and_then(float_to_json),
+        ColumnAffinity::Text => row
+            .try_get::<String, _>(col)
+            .ok()
+            .map(serde_json::Value::String),
+        ColumnAffinity::Blob => row.try_get::<Vec<u8>, _>(col).ok().map(blob_to_json),
+        ColumnAffinity::Numeric => row
+            .try_get::<i64, _>(col)
+            .ok()
+            .map(Into::into)
+            .or_else(|| row.try_get::<f64, _>(col).ok().and_then(float_to_json)),
+    };
+    if let Some(value) = decoded {
+        return Ok(value);
+    }
+
+    let storage = raw.type_info().name().to_string();
+    let value = match storage.as_str() {
+        "INTEGER" => row.try_get::<i64, _>(col).map(Into::into),
+        "REAL" => row
+            .try_get::<f64, _>(col)
+            .map(|v| float_to_json(v).unwrap_or(serde_json::Value::Null)),
+        "BLOB" => row.try_get::<Vec<u8>, _>(col).map(blob_to_json),
+        _ => row.try_get::<String, _>(col).map(serde_json::Value::String),
+    };
+    value.map_err(|e| format!("Failed to read column '{}': {}", col, e))
+}
+
+/// Columns declared as DATE/DATETIME/TIMESTAMP, plus the conventional `created_at`/`updated_at`
+fn is_timestamp_column(name: &str, declared: &str) -> bool {
+    let declared = declared.to_uppercase();
+    declared.contains("DATE")
+        || declared.contains("TIME")
+        || matches!(name, "created_at" | "updated_at")
+}
+
+/// Epoch values above this are taken to be milliseconds (year 5138 in seconds)
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Render a timestamp cell as ISO-8601 UTC, whether it was stored as unix epoch seconds or
+/// milliseconds, or as SQLite `CURRENT_TIMESTAMP` text. Unrecognized values pass through.
+fn normalize_timestamp(value: serde_json::Value) -> serde_json::Value {
+    use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+
+    let parsed: Option<DateTime<Utc>> = match &value {
+        serde_json::Value::Number(n) => n.as_i64().and_then(|secs| {
+            if secs.abs() >= EPOCH_MILLIS_THRESHOLD {
+                DateTime::from_timestamp_millis(secs)
+            } else {
+                DateTime::from_timestamp(secs, 0)
+            }
+        }),
+        // CURRENT_TIMESTAMP is "YYYY-MM-DD HH:MM:SS" in UTC
+        serde_json::Value::String(text) => DateTime::parse_from_rfc3339(text)
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok()
+            .or_else(|| {
+                ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+                    .iter()
+                    .find_map(|fmt| NaiveDateTime::parse_from_str(text, fmt).ok())
+                    .map(|naive| naive.and_utc())
+            }),
+        _ => None,
+    };
+
+    match parsed {
+        Some(dt) => serde_json::Value::String(dt.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        None => value,
+    }
+}
+
+fn float_to_json(v: f64) -> Option<serde_json::Value> {
+    serde_json::Number::from_f64(v).map(serde_json::Value::Number)
+}
+
+fn blob_to_json(bytes: Vec<u8>) -> serde_json::Value {
+    use base64::Engine;
+    serde_json::Value::String(format!(
+        "{}{}",
+        BLOB_MARKER,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
 }
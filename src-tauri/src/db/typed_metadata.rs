@@ -81,6 +81,34 @@ pub fn save_file_metadata(
         }
     }
 
+    // Save required packages
+    if let Some(packages) = &metadata.required_packages {
+        conn.execute(
+            "DELETE FROM resource_file_packages WHERE resource_id = ?1",
+            params![resource_id],
+        )?;
+        for package_id in packages {
+            conn.execute(
+                "INSERT OR IGNORE INTO resource_file_packages (resource_id, package_id) VALUES (?1, ?2)",
+                params![resource_id, package_id],
+            )?;
+        }
+    }
+
+    // Save bibliography entries
+    if let Some(bib_entries) = &metadata.bib_entries {
+        conn.execute(
+            "DELETE FROM resource_file_bib_entries WHERE resource_id = ?1",
+            params![resource_id],
+        )?;
+        for bib_id in bib_entries {
+            conn.execute(
+                "INSERT OR IGNORE INTO resource_file_bib_entries (resource_id, bib_id) VALUES (?1, ?2)",
+                params![resource_id, bib_id],
+            )?;
+        }
+    }
+
     // Save custom tags
     if let Some(tags) = &metadata.custom_tags {
         conn.execute(
@@ -131,6 +159,42 @@ pub fn save_document_metadata(
         ],
     )?;
 
+    // Save included files
+    if let Some(files) = &metadata.included_files {
+        conn.execute(
+            "DELETE FROM resource_document_files WHERE document_id = ?1",
+            params![resource_id],
+        )?;
+        for file in files {
+            conn.execute(
+                "INSERT OR REPLACE INTO resource_document_files (
+                    document_id, file_id, order_index, files_database_source, database_type
+                ) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    resource_id,
+                    file.file_id,
+                    file.order_index,
+                    file.files_database_source,
+                    file.database_type,
+                ],
+            )?;
+        }
+    }
+
+    // Save bibliography entries
+    if let Some(bib_entries) = &metadata.bib_entries {
+        conn.execute(
+            "DELETE FROM resource_document_bib_entries WHERE resource_id = ?1",
+            params![resource_id],
+        )?;
+        for bib_id in bib_entries {
+            conn.execute(
+                "INSERT OR IGNORE INTO resource_document_bib_entries (resource_id, bib_id) VALUES (?1, ?2)",
+                params![resource_id, bib_id],
+            )?;
+        }
+    }
+
     // Save custom tags
     if let Some(tags) = &metadata.custom_tags {
         conn.execute(
@@ -219,6 +283,24 @@ pub fn load_file_metadata(conn: &Connection, resource_id: &str) -> Result<Option
             meta.exercise_types = Some(exercise_types);
         }
 
+        // Load required packages
+        let packages: Vec<String> = conn
+            .prepare("SELECT package_id FROM resource_file_packages WHERE resource_id = ?1")?
+            .query_map(params![resource_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        if !packages.is_empty() {
+            meta.required_packages = Some(packages);
+        }
+
+        // Load bibliography entries
+        let bib_entries: Vec<String> = conn
+            .prepare("SELECT bib_id FROM resource_file_bib_entries WHERE resource_id = ?1")?
+            .query_map(params![resource_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        if !bib_entries.is_empty() {
+            meta.bib_entries = Some(bib_entries);
+        }
+
         // Load custom tags
         let tags: Vec<String> = conn
             .prepare("SELECT tag FROM resource_file_tags WHERE resource_id = ?1")?
@@ -273,6 +355,35 @@ pub fn load_document_metadata(
         .optional()?;
 
     if let Some(mut meta) = metadata {
+        // Load included files in document order
+        let included_files: Vec<IncludedFile> = conn
+            .prepare(
+                "SELECT file_id, order_index, files_database_source, database_type
+                 FROM resource_document_files WHERE document_id = ?1
+                 ORDER BY order_index",
+            )?
+            .query_map(params![resource_id], |row| {
+                Ok(IncludedFile {
+                    file_id: row.get(0)?,
+                    order_index: row.get(1)?,
+                    files_database_source: row.get(2)?,
+                    database_type: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<IncludedFile>>>()?;
+        if !included_files.is_empty() {
+            meta.included_files = Some(included_files);
+        }
+
+        // Load bibliography entries
+        let bib_entries: Vec<String> = conn
+            .prepare("SELECT bib_id FROM resource_document_bib_entries WHERE resource_id = ?1")?
+            .query_map(params![resource_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        if !bib_entries.is_empty() {
+            meta.bib_entries = Some(bib_entries);
+        }
+
         // Load custom tags
         let tags: Vec<String> = conn
             .prepare("SELECT tag FROM resource_document_tags WHERE resource_id = ?1")?
@@ -288,6 +399,258 @@ pub fn load_document_metadata(
     Ok(None)
 }
 
+/// Load TableMetadata from database
+pub fn load_table_metadata(conn: &Connection, resource_id: &str) -> Result<Option<TableMetadata>> {
+    let metadata = conn
+        .query_row(
+            "SELECT table_type_id, date, content, caption
+             FROM resource_tables WHERE resource_id = ?1",
+            params![resource_id],
+            |row| {
+                Ok(TableMetadata {
+                    table_type_id: row.get(0)?,
+                    date: row.get(1)?,
+                    content: row.get(2)?,
+                    caption: row.get(3)?,
+                    required_packages: None,
+                    custom_tags: None,
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut meta) = metadata else {
+        return Ok(None);
+    };
+    meta.required_packages = load_id_list(
+        conn,
+        "SELECT package_id FROM resource_table_packages WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    meta.custom_tags = load_id_list(
+        conn,
+        "SELECT tag FROM resource_table_tags WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    Ok(Some(meta))
+}
+
+/// Load FigureMetadata from database
+pub fn load_figure_metadata(
+    conn: &Connection,
+    resource_id: &str,
+) -> Result<Option<FigureMetadata>> {
+    let metadata = conn
+        .query_row(
+            "SELECT figure_type_id, environment, date, content, caption,
+                    preamble_id, build_command, description
+             FROM resource_figures WHERE resource_id = ?1",
+            params![resource_id],
+            |row| {
+                Ok(FigureMetadata {
+                    plot_type_id: row.get(0)?,
+                    environment: row.get(1)?,
+                    date: row.get(2)?,
+                    content: row.get(3)?,
+                    caption: row.get(4)?,
+                    preamble_id: row.get(5)?,
+                    build_command: row.get(6)?,
+                    description: row.get(7)?,
+                    required_packages: None,
+                    custom_tags: None,
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut meta) = metadata else {
+        return Ok(None);
+    };
+    meta.required_packages = load_id_list(
+        conn,
+        "SELECT package_id FROM resource_figure_packages WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    meta.custom_tags = load_id_list(
+        conn,
+        "SELECT tag FROM resource_figure_tags WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    Ok(Some(meta))
+}
+
+/// Load CommandMetadata from database
+pub fn load_command_metadata(
+    conn: &Connection,
+    resource_id: &str,
+) -> Result<Option<CommandMetadata>> {
+    let metadata = conn
+        .query_row(
+            "SELECT name, command_type_id, content, description, built_in
+             FROM resource_commands WHERE resource_id = ?1",
+            params![resource_id],
+            |row| {
+                Ok(CommandMetadata {
+                    name: row.get(0)?,
+                    file_type_id: row.get(1)?,
+                    content: row.get(2)?,
+                    description: row.get(3)?,
+                    built_in: row.get(4)?,
+                    macro_command_type_id: None,
+                    required_packages: None,
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut meta) = metadata else {
+        return Ok(None);
+    };
+    meta.required_packages = load_id_list(
+        conn,
+        "SELECT package_id FROM resource_command_packages WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    Ok(Some(meta))
+}
+
+/// Load PackageMetadata from database
+pub fn load_package_metadata(
+    conn: &Connection,
+    resource_id: &str,
+) -> Result<Option<PackageMetadata>> {
+    let metadata = conn
+        .query_row(
+            "SELECT name, topic_id, date, content, description
+             FROM resource_packages WHERE resource_id = ?1",
+            params![resource_id],
+            |row| {
+                Ok(PackageMetadata {
+                    name: row.get(0)?,
+                    topic_id: row.get(1)?,
+                    date: row.get(2)?,
+                    content: row.get(3)?,
+                    description: row.get(4)?,
+                    dependencies: None,
+                    topics: None,
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut meta) = metadata else {
+        return Ok(None);
+    };
+    meta.dependencies = load_id_list(
+        conn,
+        "SELECT package_id FROM resource_package_dependencies WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    meta.topics = load_id_list(
+        conn,
+        "SELECT topic_id FROM resource_package_topics WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    Ok(Some(meta))
+}
+
+/// Load PreambleMetadata from database
+pub fn load_preamble_metadata(
+    conn: &Connection,
+    resource_id: &str,
+) -> Result<Option<PreambleMetadata>> {
+    let metadata = conn
+        .query_row(
+            "SELECT name, preamble_type_id, content, description, built_in
+             FROM resource_preambles WHERE resource_id = ?1",
+            params![resource_id],
+            |row| {
+                Ok(PreambleMetadata {
+                    name: row.get(0)?,
+                    file_type_id: row.get(1)?,
+                    content: row.get(2)?,
+                    description: row.get(3)?,
+                    built_in: row.get(4)?,
+                    required_packages: None,
+                    command_types: None,
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut meta) = metadata else {
+        return Ok(None);
+    };
+    meta.required_packages = load_id_list(
+        conn,
+        "SELECT package_id FROM resource_preamble_packages WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    meta.command_types = load_id_list(
+        conn,
+        "SELECT command_type_id FROM resource_preamble_command_types WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    Ok(Some(meta))
+}
+
+/// Load ClassMetadata from database
+pub fn load_class_metadata(conn: &Connection, resource_id: &str) -> Result<Option<ClassMetadata>> {
+    let metadata = conn
+        .query_row(
+            "SELECT name, file_type_id, date, content, description
+             FROM resource_classes WHERE resource_id = ?1",
+            params![resource_id],
+            |row| {
+                Ok(ClassMetadata {
+                    name: row.get(0)?,
+                    file_type_id: row.get(1)?,
+                    date: row.get(2)?,
+                    content: row.get(3)?,
+                    description: row.get(4)?,
+                    custom_tags: None,
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut meta) = metadata else {
+        return Ok(None);
+    };
+    meta.custom_tags = load_id_list(
+        conn,
+        "SELECT tag FROM resource_class_tags WHERE resource_id = ?1",
+        resource_id,
+    )?;
+    Ok(Some(meta))
+}
+
+/// Run a single-column junction query; `None` when there are no rows, matching how
+/// the save functions treat an absent list
+fn load_id_list(conn: &Connection, sql: &str, resource_id: &str) -> Result<Option<Vec<String>>> {
+    let ids = conn
+        .prepare(sql)?
+        .query_map(params![resource_id], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(if ids.is_empty() { None } else { Some(ids) })
+}
+
+// ============================================================================
+// DELETE Operations
+// ============================================================================
+
+/// Delete typed metadata and all junction rows for a resource in one transaction
+pub fn delete_typed_metadata(conn: &Connection, resource_id: &str, resource_type: &str) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    for (table, id_column) in typed_metadata_tables(resource_type) {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE {} = ?1", table, id_column),
+            params![resource_id],
+        )?;
+    }
+    tx.commit()
+}
+
 // ============================================================================
 // Generic load function by resource type
 // ============================================================================
@@ -312,6 +675,12 @@ pub fn load_typed_metadata(
                 Ok(None)
             }
         }
+        "table" => Ok(load_table_metadata(conn, resource_id)?.map(TypedMetadata::Table)),
+        "figure" => Ok(load_figure_metadata(conn, resource_id)?.map(TypedMetadata::Figure)),
+        "command" => Ok(load_command_metadata(conn, resource_id)?.map(TypedMetadata::Command)),
+        "package" => Ok(load_package_metadata(conn, resource_id)?.map(TypedMetadata::Package)),
+        "preamble" => Ok(load_preamble_metadata(conn, resource_id)?.map(TypedMetadata::Preamble)),
+        "class" => Ok(load_class_metadata(conn, resource_id)?.map(TypedMetadata::Class)),
         _ => Ok(None),
     }
 }
@@ -70,7 +70,7 @@ pub fn save_figure_metadata(
 ) -> Result<()> {
     conn.execute(
         "INSERT OR REPLACE INTO resource_figures (
-            resource_id, plot_type_id, environment, date, content,
+            resource_id, figure_type_id, environment, date, content,
             caption, preamble_id, build_command, description
         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         params![
@@ -132,9 +132,8 @@ pub fn save_command_metadata(
 ) -> Result<()> {
     conn.execute(
         "INSERT OR REPLACE INTO resource_commands (
-            resource_id, name, file_type_id, content, description,
-            built_in, macro_command_type_id
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            resource_id, name, command_type_id, content, description, built_in
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![
             resource_id,
             metadata.name,
@@ -142,7 +141,6 @@ pub fn save_command_metadata(
             metadata.content,
             metadata.description,
             metadata.built_in,
-            metadata.macro_command_type_id,
         ],
     )?;
 
@@ -228,7 +226,7 @@ pub fn save_preamble_metadata(
 ) -> Result<()> {
     conn.execute(
         "INSERT OR REPLACE INTO resource_preambles (
-            resource_id, name, file_type_id, content, description, built_in
+            resource_id, name, preamble_type_id, content, description, built_in
         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         params![
             resource_id,
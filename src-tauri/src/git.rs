@@ -6,7 +6,8 @@ use git2::{
     Commit, Cred, DiffOptions, FetchOptions, Oid, PushOptions, RemoteCallbacks, Repository,
     Signature, StatusOptions,
 };
-use std::path::Path;
+use std::path::{Component, Path};
+use tauri::{AppHandle, Emitter};
 
 /// Git repository information
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -16,6 +17,8 @@ pub struct GitRepoInfo {
     pub remote_url: Option<String>,
     pub is_dirty: bool,
     pub head_commit: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
 }
 
 /// Git file status
@@ -39,6 +42,14 @@ pub struct GitCommitInfo {
     pub refs: Vec<String>,
 }
 
+/// One page of `get_log`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GitLogPage {
+    pub commits: Vec<GitCommitInfo>,
+    /// More matching commits exist past this page
+    pub has_more: bool,
+}
+
 /// Detect Git repository from a path (searches upward)
 pub fn detect_repo(path: &str) -> Result<Option<GitRepoInfo>, String> {
     let path = Path::new(path);
@@ -80,15 +91,55 @@ pub fn detect_repo(path: &str) -> Result<Option<GitRepoInfo>, String> {
         .and_then(|h| h.peel_to_commit().ok())
         .map(|c| c.id().to_string());
 
+    // Get sync state against the upstream branch
+    let (ahead, behind) = ahead_behind(&repo);
+
     Ok(Some(GitRepoInfo {
         path: repo_path,
         branch,
         remote_url,
         is_dirty,
         head_commit,
+        ahead,
+        behind,
     }))
 }
 
+/// Get ahead/behind commit counts of the current branch relative to its upstream
+pub fn get_ahead_behind(repo_path: &str) -> Result<(usize, usize), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    Ok(ahead_behind(&repo))
+}
+
+/// Helper: compute ahead/behind, returning (0, 0) when there is no upstream
+fn ahead_behind(repo: &Repository) -> (usize, usize) {
+    let head = match repo.head() {
+        Ok(h) if h.is_branch() => h,
+        _ => return (0, 0),
+    };
+
+    let branch_name = match head.shorthand() {
+        Some(name) => name.to_string(),
+        None => return (0, 0),
+    };
+
+    let branch = match repo.find_branch(&branch_name, git2::BranchType::Local) {
+        Ok(b) => b,
+        Err(_) => return (0, 0),
+    };
+
+    // No upstream configured (e.g. fresh repo) is not an error
+    let upstream = match branch.upstream() {
+        Ok(u) => u,
+        Err(_) => return (0, 0),
+    };
+
+    match (branch.get().target(), upstream.get().target()) {
+        (Some(local), Some(remote)) => repo.graph_ahead_behind(local, remote).unwrap_or((0, 0)),
+        _ => (0, 0),
+    }
+}
+
 /// Get status of files in repository
 pub fn get_status(repo_path: &str) -> Result<Vec<GitFileStatus>, String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
@@ -187,8 +238,87 @@ pub fn unstage_file(repo_path: &str, file_path: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// Create a commit
-pub fn commit(repo_path: &str, message: &str) -> Result<String, String> {
+/// Commit types accepted by the "conventional" style
+const CONVENTIONAL_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+/// Longest header line the "conventional" style accepts
+const MAX_HEADER_LENGTH: usize = 72;
+
+/// Check a commit message against a style: "plain" (non-empty) or
+/// "conventional" (`type(scope)!: subject`, header of at most 72 characters,
+/// blank line before any body).
+pub fn validate_commit_message(message: &str, style: &str) -> Result<(), String> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+
+    match style {
+        "plain" => Ok(()),
+        "conventional" => validate_conventional_message(message),
+        other => Err(format!("Unknown commit message style: {}", other)),
+    }
+}
+
+fn validate_conventional_message(message: &str) -> Result<(), String> {
+    let mut lines = message.lines();
+    let header = lines.next().unwrap_or_default();
+
+    if let Some(second) = lines.next() {
+        if !second.trim().is_empty() {
+            return Err("Separate the header from the body with a blank line".to_string());
+        }
+    }
+
+    let header_length = header.chars().count();
+    if header_length > MAX_HEADER_LENGTH {
+        return Err(format!(
+            "Header is {} characters long; keep it to {} or fewer",
+            header_length, MAX_HEADER_LENGTH
+        ));
+    }
+
+    let (prefix, subject) = header
+        .split_once(": ")
+        .ok_or("Header must look like \"type(scope): subject\"")?;
+
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let commit_type = match prefix.split_once('(') {
+        Some((commit_type, scope)) => {
+            let scope = scope
+                .strip_suffix(')')
+                .ok_or("Scope must be closed with \")\"")?;
+            if scope.trim().is_empty() || scope.contains(['(', ')']) {
+                return Err(format!("Invalid scope: \"{}\"", scope));
+            }
+            commit_type
+        }
+        None => prefix,
+    };
+
+    if !CONVENTIONAL_TYPES.contains(&commit_type) {
+        return Err(format!(
+            "Unknown commit type \"{}\"; expected one of: {}",
+            commit_type,
+            CONVENTIONAL_TYPES.join(", ")
+        ));
+    }
+
+    if subject.trim().is_empty() {
+        return Err("Subject must not be empty".to_string());
+    }
+
+    Ok(())
+}
+
+/// Create a commit. When `style` is given the message is validated first.
+pub fn commit(repo_path: &str, message: &str, style: Option<&str>) -> Result<String, String> {
+    if let Some(style) = style {
+        validate_commit_message(message, style)?;
+    }
+
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
 
     let mut index = repo.index().map_err(|e| e.to_string())?;
@@ -220,16 +350,134 @@ pub fn commit(repo_path: &str, message: &str) -> Result<String, String> {
     Ok(commit_id.to_string())
 }
 
-/// Get commit log
+/// Get commit log, paged with `skip`/`limit` and optionally restricted to
+/// commits that touch `file_path`
 pub fn get_log(
     repo_path: &str,
     limit: Option<i32>,
+    skip: Option<usize>,
     all: bool,
-) -> Result<Vec<GitCommitInfo>, String> {
+    file_path: Option<&str>,
+) -> Result<GitLogPage, String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
     let limit = limit.unwrap_or(200) as usize; // Increase default limit for graph
+    let refs_map = collect_ref_labels(&repo);
+
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+
+    if all {
+        revwalk
+            .push_glob("refs/heads/*")
+            .map_err(|e| e.to_string())?;
+        // Also push tags to make sure they are included?
+        // revwalk.push_glob("refs/tags/*").ok();
+        // If we want graph to show all tags even if not on main branches:
+        let _ = revwalk.push_glob("refs/tags/*");
+        let _ = revwalk.push_head();
+    } else {
+        revwalk.push_head().map_err(|e| e.to_string())?;
+    }
+
+    revwalk
+        .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    let mut to_skip = skip.unwrap_or(0);
+    let mut has_more = false;
+
+    for oid in revwalk {
+        let oid = oid.map_err(|e| e.to_string())?;
+        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+
+        if let Some(path) = file_path {
+            if !commit_touches_path(&repo, &commit, path)? {
+                continue;
+            }
+        }
+
+        if to_skip > 0 {
+            to_skip -= 1;
+            continue;
+        }
+
+        // One match past the page is enough to know there is another page
+        if result.len() == limit {
+            has_more = true;
+            break;
+        }
+
+        result.push(commit_info(&commit, &refs_map));
+    }
+
+    Ok(GitLogPage {
+        commits: result,
+        has_more,
+    })
+}
+
+/// Search all local branches and tags for commits whose message and/or author
+/// name/email contains `query` (case-insensitive)
+pub fn search_log(
+    repo_path: &str,
+    query: &str,
+    search_messages: bool,
+    search_authors: bool,
+    limit: Option<i32>,
+) -> Result<Vec<GitCommitInfo>, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(200) as usize;
+    let query = query.trim().to_lowercase();
+    if query.is_empty() || !(search_messages || search_authors) {
+        return Ok(Vec::new());
+    }
 
-    // NEW: Pre-fetch refs (branches and tags)
+    let refs_map = collect_ref_labels(&repo);
+
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    revwalk
+        .push_glob("refs/heads/*")
+        .map_err(|e| e.to_string())?;
+    let _ = revwalk.push_glob("refs/tags/*");
+    let _ = revwalk.push_head();
+    revwalk
+        .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+
+    for oid in revwalk {
+        if result.len() == limit {
+            break;
+        }
+
+        let oid = oid.map_err(|e| e.to_string())?;
+        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+
+        let message_matches = search_messages
+            && commit
+                .message()
+                .is_some_and(|m| m.to_lowercase().contains(&query));
+        let author_matches = search_authors && {
+            let author = commit.author();
+            author
+                .name()
+                .is_some_and(|n| n.to_lowercase().contains(&query))
+                || author
+                    .email()
+                    .is_some_and(|e| e.to_lowercase().contains(&query))
+        };
+
+        if message_matches || author_matches {
+            result.push(commit_info(&commit, &refs_map));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Branch, tag and detached-HEAD labels keyed by the commit they point at
+fn collect_ref_labels(repo: &Repository) -> std::collections::HashMap<Oid, Vec<String>> {
     let mut refs_map: std::collections::HashMap<Oid, Vec<String>> =
         std::collections::HashMap::new();
 
@@ -279,54 +527,58 @@ pub fn get_log(
         }
     }
 
-    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    refs_map
+}
 
-    if all {
-        revwalk
-            .push_glob("refs/heads/*")
-            .map_err(|e| e.to_string())?;
-        // Also push tags to make sure they are included?
-        // revwalk.push_glob("refs/tags/*").ok();
-        // If we want graph to show all tags even if not on main branches:
-        let _ = revwalk.push_glob("refs/tags/*");
-        let _ = revwalk.push_head();
-    } else {
-        revwalk.push_head().map_err(|e| e.to_string())?;
+fn commit_info(
+    commit: &Commit,
+    refs_map: &std::collections::HashMap<Oid, Vec<String>>,
+) -> GitCommitInfo {
+    let oid = commit.id();
+    let short_id = commit
+        .as_object()
+        .short_id()
+        .map(|s| s.as_str().unwrap_or("").to_string())
+        .unwrap_or_else(|_| oid.to_string()[..7].to_string());
+
+    GitCommitInfo {
+        id: oid.to_string(),
+        short_id,
+        message: commit.message().unwrap_or("").to_string(),
+        author_name: commit.author().name().unwrap_or("Unknown").to_string(),
+        author_email: commit.author().email().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds(),
+        parent_ids: commit.parent_ids().map(|id| id.to_string()).collect(),
+        refs: refs_map.get(&oid).cloned().unwrap_or_default(),
     }
+}
 
-    revwalk
-        .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
-        .map_err(|e| e.to_string())?;
+/// Whether `commit` changes `path` (a file or directory). Merge commits only
+/// count when they differ from every parent, like `git log -- <path>`.
+fn commit_touches_path(repo: &Repository, commit: &Commit, path: &str) -> Result<bool, String> {
+    let tree = commit.tree().map_err(|e| e.to_string())?;
 
-    let mut result = Vec::new();
+    let differs_from = |parent_tree: Option<&git2::Tree>| -> Result<bool, String> {
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.pathspec(path);
+        let diff = repo
+            .diff_tree_to_tree(parent_tree, Some(&tree), Some(&mut diff_opts))
+            .map_err(|e| e.to_string())?;
+        Ok(diff.deltas().len() > 0)
+    };
 
-    for oid in revwalk.take(limit) {
-        let oid = oid.map_err(|e| e.to_string())?;
-        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+    if commit.parent_count() == 0 {
+        return differs_from(None);
+    }
 
-        let short_id = commit
-            .as_object()
-            .short_id()
-            .map(|s| s.as_str().unwrap_or("").to_string())
-            .unwrap_or_else(|_| oid.to_string()[..7].to_string());
-
-        let parent_ids: Vec<String> = commit.parent_ids().map(|id| id.to_string()).collect();
-
-        let commit_refs = refs_map.get(&oid).cloned().unwrap_or_default();
-
-        result.push(GitCommitInfo {
-            id: oid.to_string(),
-            short_id,
-            message: commit.message().unwrap_or("").to_string(),
-            author_name: commit.author().name().unwrap_or("Unknown").to_string(),
-            author_email: commit.author().email().unwrap_or("").to_string(),
-            timestamp: commit.time().seconds(),
-            parent_ids,
-            refs: commit_refs,
-        });
+    for parent in commit.parents() {
+        let parent_tree = parent.tree().map_err(|e| e.to_string())?;
+        if !differs_from(Some(&parent_tree))? {
+            return Ok(false);
+        }
     }
 
-    Ok(result)
+    Ok(true)
 }
 
 /// Get diff for a file (unstaged changes)
@@ -425,11 +677,19 @@ pub fn init_repo(path: &str) -> Result<GitRepoInfo, String> {
         remote_url: None,
         is_dirty: false,
         head_commit: None,
+        ahead: 0,
+        behind: 0,
     })
 }
 
 /// Get HEAD content for a file (for diff comparison)
 pub fn get_head_file_content(repo_path: &str, file_path: &str) -> Result<String, String> {
+    let bytes = get_head_file_bytes(repo_path, file_path)?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// Raw HEAD content of a file (empty for new repos/new files)
+fn get_head_file_bytes(repo_path: &str, file_path: &str) -> Result<Vec<u8>, String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
 
     // Handle unborn branch (new repo with no commits)
@@ -438,7 +698,7 @@ pub fn get_head_file_content(repo_path: &str, file_path: &str) -> Result<String,
         Err(e) => {
             // Check if this is an unborn branch (no commits yet)
             if e.code() == git2::ErrorCode::UnbornBranch {
-                return Ok(String::new());
+                return Ok(Vec::new());
             }
             return Err(e.to_string());
         }
@@ -446,14 +706,14 @@ pub fn get_head_file_content(repo_path: &str, file_path: &str) -> Result<String,
 
     let head_commit = match head.peel_to_commit() {
         Ok(c) => c,
-        Err(_) => return Ok(String::new()), // No commit yet
+        Err(_) => return Ok(Vec::new()), // No commit yet
     };
 
     let tree = head_commit.tree().map_err(|e| e.to_string())?;
 
     let entry = match tree.get_path(Path::new(file_path)) {
         Ok(e) => e,
-        Err(_) => return Ok(String::new()), // File doesn't exist in HEAD (new file)
+        Err(_) => return Ok(Vec::new()), // File doesn't exist in HEAD (new file)
     };
 
     let blob = entry
@@ -462,11 +722,7 @@ pub fn get_head_file_content(repo_path: &str, file_path: &str) -> Result<String,
         .peel_to_blob()
         .map_err(|e| e.to_string())?;
 
-    let content = std::str::from_utf8(blob.content())
-        .map_err(|e| e.to_string())?
-        .to_string();
-
-    Ok(content)
+    Ok(blob.content().to_vec())
 }
 
 /// Structured diff line for frontend rendering
@@ -476,6 +732,9 @@ pub struct DiffLine {
     pub old_line_no: Option<u32>,
     pub new_line_no: Option<u32>,
     pub content: String,
+    /// Changed character ranges `(start, end)` within `content` for modified lines
+    #[serde(default)]
+    pub highlights: Vec<crate::history::CharRange>,
 }
 
 /// Structured diff result
@@ -486,6 +745,52 @@ pub struct StructuredDiff {
     pub new_content: String,
     pub lines: Vec<DiffLine>,
     pub stats: DiffStats,
+    /// Either side contains NUL bytes; `lines` is empty
+    #[serde(default)]
+    pub is_binary: bool,
+    /// Either side exceeds `MAX_DIFF_BYTES`; `lines` is empty
+    #[serde(default)]
+    pub too_large: bool,
+}
+
+/// Files larger than this are not diffed line by line
+const MAX_DIFF_BYTES: u64 = 2 * 1024 * 1024;
+/// Like git, only the start of a file is checked for NUL bytes
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// Placeholder for a change that can't be shown line by line
+fn unrenderable_diff(file_path: &str, is_binary: bool, too_large: bool) -> StructuredDiff {
+    StructuredDiff {
+        file_path: file_path.to_string(),
+        old_content: String::new(),
+        new_content: String::new(),
+        lines: Vec::new(),
+        stats: DiffStats {
+            additions: 0,
+            deletions: 0,
+        },
+        is_binary,
+        too_large,
+    }
+}
+
+/// Diff two versions given as raw bytes, guarding against binary and oversized content
+fn build_guarded_diff(file_path: &str, old: &[u8], new: &[u8]) -> StructuredDiff {
+    if old.len() as u64 > MAX_DIFF_BYTES || new.len() as u64 > MAX_DIFF_BYTES {
+        return unrenderable_diff(file_path, false, true);
+    }
+    if looks_binary(old) || looks_binary(new) {
+        return unrenderable_diff(file_path, true, false);
+    }
+    build_structured_diff(
+        file_path,
+        String::from_utf8_lossy(old).to_string(),
+        String::from_utf8_lossy(new).to_string(),
+    )
 }
 
 /// Diff statistics
@@ -499,13 +804,30 @@ pub struct DiffStats {
 pub fn get_structured_diff(repo_path: &str, file_path: &str) -> Result<StructuredDiff, String> {
     let full_path = Path::new(repo_path).join(file_path);
 
+    // Don't load huge generated files just to find out they can't be shown
+    let size = std::fs::metadata(&full_path)
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .len();
+    if size > MAX_DIFF_BYTES {
+        return Ok(unrenderable_diff(file_path, false, true));
+    }
+
     // Get current file content
     let new_content =
-        std::fs::read_to_string(&full_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        std::fs::read(&full_path).map_err(|e| format!("Failed to read file: {}", e))?;
 
     // Get HEAD content (empty for new repos/new files)
-    let old_content = get_head_file_content(repo_path, file_path)?;
+    let old_content = get_head_file_bytes(repo_path, file_path)?;
+
+    Ok(build_guarded_diff(file_path, &old_content, &new_content))
+}
 
+/// Helper: classify lines between two versions of a file using `similar`
+fn build_structured_diff(
+    file_path: &str,
+    old_content: String,
+    new_content: String,
+) -> StructuredDiff {
     // Use similar crate for reliable diff generation
     use similar::{ChangeTag, TextDiff};
 
@@ -541,10 +863,19 @@ pub fn get_structured_diff(repo_path: &str, file_path: &str) -> Result<Structure
             old_line_no: old_no,
             new_line_no: new_no,
             content: change.value().trim_end_matches('\n').to_string(),
+            highlights: Vec::new(),
         });
     }
 
-    Ok(StructuredDiff {
+    let tags: Vec<ChangeTag> = diff.iter_all_changes().map(|c| c.tag()).collect();
+    for (old_idx, new_idx) in crate::history::modified_line_pairs(&tags) {
+        let (old_ranges, new_ranges) =
+            crate::history::word_highlights(&lines[old_idx].content, &lines[new_idx].content);
+        lines[old_idx].highlights = old_ranges;
+        lines[new_idx].highlights = new_ranges;
+    }
+
+    StructuredDiff {
         file_path: file_path.to_string(),
         old_content,
         new_content,
@@ -553,9 +884,347 @@ pub fn get_structured_diff(repo_path: &str, file_path: &str) -> Result<Structure
             additions,
             deletions,
         },
+        is_binary: false,
+        too_large: false,
+    }
+}
+
+/// Get structured diffs between two commits ("WORKDIR" as `new_commit` diffs against the working tree)
+pub fn get_commit_diff(
+    repo_path: &str,
+    old_commit: &str,
+    new_commit: &str,
+    file_path: Option<&str>,
+) -> Result<Vec<StructuredDiff>, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+
+    let old_tree = repo
+        .revparse_single(old_commit)
+        .and_then(|obj| obj.peel_to_tree())
+        .map_err(|e| format!("Commit not found: {}", e))?;
+
+    let mut diff_opts = DiffOptions::new();
+    if let Some(path) = file_path {
+        diff_opts.pathspec(path);
+    }
+
+    let is_workdir = new_commit == "WORKDIR";
+
+    let diff = if is_workdir {
+        diff_opts
+            .include_untracked(true)
+            .recurse_untracked_dirs(true);
+        repo.diff_tree_to_workdir_with_index(Some(&old_tree), Some(&mut diff_opts))
+            .map_err(|e| e.to_string())?
+    } else {
+        let new_tree = repo
+            .revparse_single(new_commit)
+            .and_then(|obj| obj.peel_to_tree())
+            .map_err(|e| format!("Commit not found: {}", e))?;
+        repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut diff_opts))
+            .map_err(|e| e.to_string())?
+    };
+
+    let workdir = repo.workdir().map(|p| p.to_path_buf());
+    let blob_bytes = |oid: Oid| -> Vec<u8> {
+        if oid.is_zero() {
+            return Vec::new();
+        }
+        repo.find_blob(oid)
+            .map(|b| b.content().to_vec())
+            .unwrap_or_default()
+    };
+
+    let mut result = Vec::new();
+
+    for delta in diff.deltas() {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+
+        // Working tree files are not hashed into the odb, read them from disk
+        let workdir_file = workdir
+            .as_ref()
+            .filter(|_| is_workdir && delta.status() != git2::Delta::Deleted)
+            .map(|dir| dir.join(&path));
+        let new_size = match &workdir_file {
+            Some(file) => std::fs::metadata(file).map(|m| m.len()).unwrap_or(0),
+            None => delta.new_file().size(),
+        };
+        if delta.old_file().size() > MAX_DIFF_BYTES || new_size > MAX_DIFF_BYTES {
+            result.push(unrenderable_diff(&path, false, true));
+            continue;
+        }
+
+        let old_content = blob_bytes(delta.old_file().id());
+        let new_content = match &workdir_file {
+            Some(file) => std::fs::read(file).unwrap_or_default(),
+            None => blob_bytes(delta.new_file().id()),
+        };
+
+        result.push(build_guarded_diff(&path, &old_content, &new_content));
+    }
+
+    Ok(result)
+}
+
+/// Structured diff of the staged (`HEAD` → index) or unstaged (index → working tree)
+/// changes of a file. Hunk headers and line numbers refer to these diffs, as `stage_hunk` and
+/// `unstage_hunk` expect.
+pub fn get_index_diff(
+    repo_path: &str,
+    file_path: &str,
+    staged: bool,
+) -> Result<StructuredDiff, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let index_content = index_file_bytes(&repo, file_path)?;
+
+    let (old, new) = if staged {
+        (get_head_file_bytes(repo_path, file_path)?, index_content)
+    } else {
+        let worktree = std::fs::read(Path::new(repo_path).join(file_path)).unwrap_or_default();
+        (index_content, worktree)
+    };
+    Ok(build_guarded_diff(file_path, &old, &new))
+}
+
+/// Stage one hunk of `get_index_diff(.., staged = false)`, identified by its header
+/// (e.g. "@@ -12,5 +12,7 @@"). With `lines`, only those lines of the hunk are staged.
+pub fn stage_hunk(
+    repo_path: &str,
+    file_path: &str,
+    hunk_header: &str,
+    lines: Vec<DiffLine>,
+) -> Result<(), String> {
+    let lines = hunk_lines(repo_path, file_path, hunk_header, lines, false)?;
+    stage_lines(repo_path, file_path, lines)
+}
+
+/// Unstage one hunk of `get_index_diff(.., staged = true)`, or the given lines of it
+pub fn unstage_hunk(
+    repo_path: &str,
+    file_path: &str,
+    hunk_header: &str,
+    lines: Vec<DiffLine>,
+) -> Result<(), String> {
+    let lines = hunk_lines(repo_path, file_path, hunk_header, lines, true)?;
+    unstage_lines(repo_path, file_path, lines)
+}
+
+/// Helper: the changed lines of the hunk at `hunk_header`, or the given subset of them.
+/// Selected lines outside the hunk are rejected.
+fn hunk_lines(
+    repo_path: &str,
+    file_path: &str,
+    hunk_header: &str,
+    lines: Vec<DiffLine>,
+    staged: bool,
+) -> Result<Vec<DiffLine>, String> {
+    let ((old_start, old_count), (new_start, new_count)) = parse_hunk_header(hunk_header)?;
+    let in_hunk = |line: &DiffLine| match line.line_type.as_str() {
+        "delete" => line
+            .old_line_no
+            .is_some_and(|n| n >= old_start && n < old_start + old_count),
+        "add" => line
+            .new_line_no
+            .is_some_and(|n| n >= new_start && n < new_start + new_count),
+        _ => false,
+    };
+
+    if lines.is_empty() {
+        let diff = get_index_diff(repo_path, file_path, staged)?;
+        let hunk: Vec<DiffLine> = diff.lines.into_iter().filter(in_hunk).collect();
+        if hunk.is_empty() {
+            return Err(format!("No changes in hunk {}", hunk_header));
+        }
+        return Ok(hunk);
+    }
+    if let Some(line) = lines
+        .iter()
+        .find(|line| matches!(line.line_type.as_str(), "add" | "delete") && !in_hunk(line))
+    {
+        return Err(format!(
+            "Line {:?} is not part of hunk {}",
+            line.content, hunk_header
+        ));
+    }
+    Ok(lines)
+}
+
+/// `(start, count)` of one side of a hunk
+type HunkRange = (u32, u32);
+
+/// Helper: the old and new ranges of a header like "@@ -12,5 +12,7 @@"
+fn parse_hunk_header(hunk_header: &str) -> Result<(HunkRange, HunkRange), String> {
+    let invalid = || format!("Invalid hunk header: {}", hunk_header);
+    let ranges = hunk_header
+        .trim()
+        .strip_prefix("@@")
+        .and_then(|rest| rest.split("@@").next())
+        .ok_or_else(invalid)?;
+
+    // A missing count means a single line
+    let range = |range: &str| -> Option<HunkRange> {
+        let mut parts = range.splitn(2, ',');
+        let start = parts.next()?.parse().ok()?;
+        let count = parts.next().map_or(Some(1), |c| c.parse().ok())?;
+        Some((start, count))
+    };
+    let mut old = None;
+    let mut new = None;
+    for part in ranges.split_whitespace() {
+        if let Some(r) = part.strip_prefix('-') {
+            old = range(r);
+        } else if let Some(r) = part.strip_prefix('+') {
+            new = range(r);
+        }
+    }
+    old.zip(new).ok_or_else(invalid)
+}
+
+/// Stage the given lines of `get_index_diff(.., staged = false)`. Unselected deletions
+/// stay in the index and unselected additions stay unstaged.
+pub fn stage_lines(repo_path: &str, file_path: &str, lines: Vec<DiffLine>) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let index_content = index_file_bytes(&repo, file_path)?;
+    let worktree = std::fs::read(Path::new(repo_path).join(file_path))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let staged = select_changes(&index_content, &worktree, |tag, line_no| {
+        is_selected(&lines, tag, line_no)
+    })?;
+    write_index_content(&repo, file_path, staged.as_bytes(), false)
+}
+
+/// Unstage the given lines of `get_index_diff(.., staged = true)`, restoring them to their
+/// `HEAD` version in the index while the other staged changes stay staged
+pub fn unstage_lines(repo_path: &str, file_path: &str, lines: Vec<DiffLine>) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let head_content = get_head_file_bytes(repo_path, file_path)?;
+    let index_content = index_file_bytes(&repo, file_path)?;
+
+    let staged = select_changes(&head_content, &index_content, |tag, line_no| {
+        !is_selected(&lines, tag, line_no)
+    })?;
+    let in_head = head_file_exists(&repo, file_path);
+    write_index_content(&repo, file_path, staged.as_bytes(), !in_head)
+}
+
+/// Helper: whether a change at `line_no` (old side for deletions, new side for
+/// additions) is among the selected lines
+fn is_selected(lines: &[DiffLine], tag: &str, line_no: u32) -> bool {
+    lines.iter().any(|line| {
+        line.line_type == tag
+            && match tag {
+                "delete" => line.old_line_no == Some(line_no),
+                _ => line.new_line_no == Some(line_no),
+            }
     })
 }
 
+/// Helper: rebuild `old` with only the changes towards `new` that `apply` accepts.
+/// Rejected deletions are kept as context; rejected additions are left out.
+fn select_changes(
+    old: &[u8],
+    new: &[u8],
+    apply: impl Fn(&str, u32) -> bool,
+) -> Result<String, String> {
+    use similar::{ChangeTag, TextDiff};
+
+    if looks_binary(old) || looks_binary(new) {
+        return Err("Line staging is not available for binary files".to_string());
+    }
+    let old = std::str::from_utf8(old).map_err(|_| "File is not valid UTF-8".to_string())?;
+    let new = std::str::from_utf8(new).map_err(|_| "File is not valid UTF-8".to_string())?;
+
+    let mut result = String::with_capacity(old.len().max(new.len()));
+    let mut old_line: u32 = 0;
+    let mut new_line: u32 = 0;
+
+    for change in TextDiff::from_lines(old, new).iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Equal => {
+                old_line += 1;
+                new_line += 1;
+                result.push_str(change.value());
+            }
+            ChangeTag::Delete => {
+                old_line += 1;
+                if !apply("delete", old_line) {
+                    result.push_str(change.value());
+                }
+            }
+            ChangeTag::Insert => {
+                new_line += 1;
+                if apply("add", new_line) {
+                    result.push_str(change.value());
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Helper: content of a file's stage-0 index entry (empty when it is not in the index)
+fn index_file_bytes(repo: &Repository, file_path: &str) -> Result<Vec<u8>, String> {
+    let index = repo.index().map_err(|e| e.to_string())?;
+    let Some(entry) = index.get_path(Path::new(file_path), 0) else {
+        return Ok(Vec::new());
+    };
+    let blob = repo.find_blob(entry.id).map_err(|e| e.to_string())?;
+    Ok(blob.content().to_vec())
+}
+
+/// Helper: whether `HEAD` contains the file
+fn head_file_exists(repo: &Repository, file_path: &str) -> bool {
+    repo.head()
+        .and_then(|head| head.peel_to_tree())
+        .and_then(|tree| tree.get_path(Path::new(file_path)))
+        .is_ok()
+}
+
+/// Helper: replace the index entry of a file with `content`. With `remove_if_empty`, an
+/// empty result drops the entry, so fully unstaging a new file untracks it again.
+fn write_index_content(
+    repo: &Repository,
+    file_path: &str,
+    content: &[u8],
+    remove_if_empty: bool,
+) -> Result<(), String> {
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    let path = Path::new(file_path);
+
+    if remove_if_empty && content.is_empty() {
+        index.remove_path(path).map_err(|e| e.to_string())?;
+        return index.write().map_err(|e| e.to_string());
+    }
+
+    let entry = match index.get_path(path, 0) {
+        Some(entry) => entry,
+        None => git2::IndexEntry {
+            ctime: git2::IndexTime::new(0, 0),
+            mtime: git2::IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode: 0o100644,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            id: Oid::zero(),
+            flags: 0,
+            flags_extended: 0,
+            path: file_path.replace('\\', "/").into_bytes(),
+        },
+    };
+    index
+        .add_frombuffer(&entry, content)
+        .map_err(|e| e.to_string())?;
+    index.write().map_err(|e| e.to_string())
+}
+
 /// Branch information
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct BranchInfo {
@@ -633,11 +1302,13 @@ pub fn create_branch(repo_path: &str, name: &str) -> Result<(), String> {
 pub fn switch_branch(repo_path: &str, name: &str) -> Result<(), String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
 
-    // Logic for switching differs if it's a local branch or remote tracking branch
-    // For simplicity, assuming local branch name or full ref name
-
     // If name doesn't start with refs/, try to look up "refs/heads/<name>"
-    let ref_name = if name.starts_with("refs/") {
+    let ref_name = if name.starts_with("refs/heads/") {
+        name.to_string()
+    } else if let Some(local) = tracking_branch_for_remote(&repo, name)? {
+        // A remote-tracking branch (e.g. "origin/feature") can't be checked out directly
+        format!("refs/heads/{}", local)
+    } else if name.starts_with("refs/") {
         name.to_string()
     } else {
         format!("refs/heads/{}", name)
@@ -657,6 +1328,45 @@ pub fn switch_branch(repo_path: &str, name: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// If `name` refers to a remote-tracking branch with no local branch of the same name,
+/// return the local branch that tracks it, creating it (with upstream set) if needed.
+fn tracking_branch_for_remote(repo: &Repository, name: &str) -> Result<Option<String>, String> {
+    let short = name.strip_prefix("refs/remotes/").unwrap_or(name);
+    if repo.find_branch(short, git2::BranchType::Local).is_ok() {
+        return Ok(None);
+    }
+    let Ok(remote_branch) = repo.find_branch(short, git2::BranchType::Remote) else {
+        return Ok(None);
+    };
+
+    // "origin/feature" -> remote "origin", local "feature"
+    let remote_name = repo
+        .branch_remote_name(&format!("refs/remotes/{}", short))
+        .map_err(|e| e.to_string())?;
+    let remote_name = remote_name.as_str().unwrap_or("");
+    let local_name = short
+        .strip_prefix(remote_name)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .filter(|rest| !rest.is_empty())
+        .ok_or_else(|| format!("Cannot derive a local branch name from {}", name))?;
+
+    if repo
+        .find_branch(local_name, git2::BranchType::Local)
+        .is_err()
+    {
+        let commit = remote_branch
+            .get()
+            .peel_to_commit()
+            .map_err(|e| e.to_string())?;
+        let mut local = repo
+            .branch(local_name, &commit, false)
+            .map_err(|e| e.to_string())?;
+        local.set_upstream(Some(short)).map_err(|e| e.to_string())?;
+    }
+
+    Ok(Some(local_name.to_string()))
+}
+
 /// Delete branch
 pub fn delete_branch(repo_path: &str, name: &str) -> Result<(), String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
@@ -833,10 +1543,73 @@ pub fn list_remotes(repo_path: &str) -> Result<Vec<RemoteInfo>, String> {
     Ok(result)
 }
 
+fn validate_remote(name: &str, url: Option<&str>) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Remote name cannot be empty".to_string());
+    }
+    if !git2::Remote::is_valid_name(name) {
+        return Err(format!("Invalid remote name: {}", name));
+    }
+    if url.is_some_and(|u| u.trim().is_empty()) {
+        return Err("Remote URL cannot be empty".to_string());
+    }
+    Ok(())
+}
+
+/// Add a remote, e.g. to connect a freshly initialized repo to a hosting service
+pub fn add_remote(repo_path: &str, name: &str, url: &str) -> Result<(), String> {
+    validate_remote(name, Some(url))?;
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    repo.remote(name, url.trim()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Remove a remote along with its remote-tracking branches and config
+pub fn remove_remote(repo_path: &str, name: &str) -> Result<(), String> {
+    validate_remote(name, None)?;
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    repo.remote_delete(name).map_err(|e| e.to_string())
+}
+
+/// Point an existing remote at a new URL
+pub fn set_remote_url(repo_path: &str, name: &str, url: &str) -> Result<(), String> {
+    validate_remote(name, Some(url))?;
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    // remote_set_url would silently create config for a missing remote
+    repo.find_remote(name).map_err(|e| e.to_string())?;
+    repo.remote_set_url(name, url.trim())
+        .map_err(|e| e.to_string())
+}
+
+/// Credentials for HTTPS remotes (personal access token)
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct GitCredentials {
+    pub username: Option<String>,
+    pub token: Option<String>,
+}
+
 /// Helper to create callbacks with credentials
-fn create_callbacks<'a>() -> RemoteCallbacks<'a> {
+fn create_callbacks<'a>(credentials: Option<&GitCredentials>) -> RemoteCallbacks<'a> {
+    let token = credentials.and_then(|c| c.token.clone());
+    let username = credentials.and_then(|c| c.username.clone());
+    let mut token_attempted = false;
+
     let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|_url, username_from_url, allowed_types| {
+    callbacks.credentials(move |_url, username_from_url, allowed_types| {
+        if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(token) = token.as_deref() {
+                // libgit2 re-invokes the callback on rejection; don't loop on a bad token
+                if token_attempted {
+                    return Err(git2::Error::from_str(
+                        "Authentication failed: the access token was rejected",
+                    ));
+                }
+                token_attempted = true;
+
+                let user = username.as_deref().or(username_from_url).unwrap_or("git");
+                return Cred::userpass_plaintext(user, token);
+            }
+        }
         if allowed_types.contains(git2::CredentialType::SSH_KEY) {
             // Try ssh-agent
             if let Ok(cred) = Cred::ssh_key_from_agent(username_from_url.unwrap_or("git")) {
@@ -859,12 +1632,114 @@ fn create_callbacks<'a>() -> RemoteCallbacks<'a> {
     callbacks
 }
 
-/// Fetch from remote
-pub fn fetch_remote(repo_path: &str, remote_name: &str) -> Result<(), String> {
+/// Clone progress payload emitted as `clone-progress`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CloneProgress {
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub received_bytes: usize,
+}
+
+/// Payload of `git-transfer-progress`, emitted during fetch and push
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TransferProgress {
+    /// "fetch" or "push"
+    pub operation: String,
+    pub current_objects: usize,
+    pub total_objects: usize,
+    pub bytes: usize,
+}
+
+/// `create_callbacks` plus `git-transfer-progress` events. libgit2 reports every object,
+/// so an event is only emitted when the whole-percent progress changes.
+fn create_callbacks_with_progress<'a>(
+    credentials: Option<&GitCredentials>,
+    app: &'a AppHandle,
+) -> RemoteCallbacks<'a> {
+    let mut callbacks = create_callbacks(credentials);
+
+    let emit = |last_percent: &mut Option<usize>, progress: TransferProgress| {
+        let percent = (progress.current_objects * 100)
+            .checked_div(progress.total_objects)
+            .unwrap_or(0);
+        if *last_percent != Some(percent) {
+            *last_percent = Some(percent);
+            let _ = app.emit("git-transfer-progress", progress);
+        }
+    };
+
+    let mut fetch_percent = None;
+    callbacks.transfer_progress(move |stats| {
+        emit(
+            &mut fetch_percent,
+            TransferProgress {
+                operation: "fetch".to_string(),
+                current_objects: stats.received_objects(),
+                total_objects: stats.total_objects(),
+                bytes: stats.received_bytes(),
+            },
+        );
+        true
+    });
+
+    let mut push_percent = None;
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        emit(
+            &mut push_percent,
+            TransferProgress {
+                operation: "push".to_string(),
+                current_objects: current,
+                total_objects: total,
+                bytes,
+            },
+        );
+    });
+
+    callbacks
+}
+
+/// Clone a remote repository into `dest`, emitting `clone-progress` events
+pub fn clone_repo(
+    url: &str,
+    dest: &str,
+    credentials: Option<&GitCredentials>,
+    app: &AppHandle,
+) -> Result<GitRepoInfo, String> {
+    let mut callbacks = create_callbacks(credentials);
+    callbacks.transfer_progress(|stats| {
+        let _ = app.emit(
+            "clone-progress",
+            CloneProgress {
+                received_objects: stats.received_objects(),
+                total_objects: stats.total_objects(),
+                received_bytes: stats.received_bytes(),
+            },
+        );
+        true
+    });
+
+    let mut fo = FetchOptions::new();
+    fo.remote_callbacks(callbacks);
+
+    git2::build::RepoBuilder::new()
+        .fetch_options(fo)
+        .clone(url, Path::new(dest))
+        .map_err(|e| format!("Clone failed: {}", e))?;
+
+    detect_repo(dest)?.ok_or_else(|| "Cloned repository could not be opened".to_string())
+}
+
+/// Fetch from remote, emitting `git-transfer-progress`
+pub fn fetch_remote(
+    repo_path: &str,
+    remote_name: &str,
+    credentials: Option<&GitCredentials>,
+    app: &AppHandle,
+) -> Result<(), String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
     let mut remote = repo.find_remote(remote_name).map_err(|e| e.to_string())?;
 
-    let callbacks = create_callbacks();
+    let callbacks = create_callbacks_with_progress(credentials, app);
     let mut fo = FetchOptions::new();
     fo.remote_callbacks(callbacks);
 
@@ -876,12 +1751,18 @@ pub fn fetch_remote(repo_path: &str, remote_name: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// Push to remote
-pub fn push_to_remote(repo_path: &str, remote_name: &str, branch_name: &str) -> Result<(), String> {
+/// Push to remote, emitting `git-transfer-progress`
+pub fn push_to_remote(
+    repo_path: &str,
+    remote_name: &str,
+    branch_name: &str,
+    credentials: Option<&GitCredentials>,
+    app: &AppHandle,
+) -> Result<(), String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
     let mut remote = repo.find_remote(remote_name).map_err(|e| e.to_string())?;
 
-    let callbacks = create_callbacks();
+    let callbacks = create_callbacks_with_progress(credentials, app);
     let mut po = PushOptions::new();
     po.remote_callbacks(callbacks);
 
@@ -900,9 +1781,11 @@ pub fn pull_from_remote(
     repo_path: &str,
     remote_name: &str,
     branch_name: &str,
+    credentials: Option<&GitCredentials>,
+    app: &AppHandle,
 ) -> Result<(), String> {
     // 1. Fetch
-    fetch_remote(repo_path, remote_name)?;
+    fetch_remote(repo_path, remote_name, credentials, app)?;
 
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
 
@@ -1115,6 +1998,39 @@ pub fn checkout_commit(repo_path: &str, commit_id: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Reset HEAD to a commit ("soft", "mixed" or "hard")
+pub fn reset_to_commit(repo_path: &str, commit_id: &str, mode: &str) -> Result<(), String> {
+    let reset_type = match mode {
+        "soft" => git2::ResetType::Soft,
+        "mixed" => git2::ResetType::Mixed,
+        "hard" => git2::ResetType::Hard,
+        other => {
+            return Err(format!(
+                "Unknown reset mode '{}'. Expected 'soft', 'mixed' or 'hard'.",
+                other
+            ))
+        }
+    };
+
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+
+    let oid = Oid::from_str(commit_id).map_err(|e| e.to_string())?;
+    let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+
+    // Hard reset must overwrite the working tree
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.force();
+    let checkout = match reset_type {
+        git2::ResetType::Hard => Some(&mut checkout_builder),
+        _ => None,
+    };
+
+    repo.reset(commit.as_object(), reset_type, checkout)
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
 /// Cherry-pick a commit onto current HEAD
 pub fn cherry_pick(repo_path: &str, commit_id: &str) -> Result<String, String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
@@ -1160,17 +2076,19 @@ pub fn cherry_pick(repo_path: &str, commit_id: &str) -> Result<String, String> {
 
 /// Blame line information
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct BlameInfo {
-    pub line_number: usize,
+pub struct BlameLine {
+    pub line_no: u32,
     pub commit_id: String,
     pub short_id: String,
-    pub author: String,
+    pub author_name: String,
+    pub author_email: String,
     pub timestamp: i64,
-    pub line_content: String,
+    pub summary: String,
+    pub content: String,
 }
 
-/// Get blame information for a file
-pub fn git_blame(repo_path: &str, file_path: &str) -> Result<Vec<BlameInfo>, String> {
+/// Get line-by-line authorship for a file (one entry per line of the working file)
+pub fn blame_file(repo_path: &str, file_path: &str) -> Result<Vec<BlameLine>, String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
 
     // Get the relative path
@@ -1184,18 +2102,49 @@ pub fn git_blame(repo_path: &str, file_path: &str) -> Result<Vec<BlameInfo>, Str
         abs_path
     };
 
-    let blame = repo.blame_file(rel_path, None).map_err(|e| e.to_string())?;
+    let committed_blame = repo.blame_file(rel_path, None).map_err(|e| e.to_string())?;
 
-    // Read file content to get line content
+    // Blame the working copy on top so uncommitted edits line up with the editor
     let full_path = repo_root.join(rel_path);
     let content = std::fs::read_to_string(&full_path).unwrap_or_default();
+    let blame = committed_blame
+        .blame_buffer(content.as_bytes())
+        .map_err(|e| e.to_string())?;
     let lines: Vec<&str> = content.lines().collect();
 
+    // Cache commit summaries, hunks frequently share the same commit
+    let mut summaries: std::collections::HashMap<Oid, String> = std::collections::HashMap::new();
     let mut result = Vec::new();
 
     for hunk in blame.iter() {
         let sig = hunk.final_signature();
         let commit_id = hunk.final_commit_id();
+        let is_committed = !commit_id.is_zero();
+
+        let summary = summaries
+            .entry(commit_id)
+            .or_insert_with(|| {
+                if !is_committed {
+                    return "Not Committed Yet".to_string();
+                }
+                repo.find_commit(commit_id)
+                    .ok()
+                    .and_then(|c| c.summary().map(|s| s.to_string()))
+                    .unwrap_or_default()
+            })
+            .clone();
+
+        let (author_name, author_email) = if is_committed {
+            (
+                sig.name().unwrap_or("Unknown").to_string(),
+                sig.email().unwrap_or("").to_string(),
+            )
+        } else {
+            ("Not Committed Yet".to_string(), String::new())
+        };
+
+        let id_str = commit_id.to_string();
+        let short_id = id_str[..7.min(id_str.len())].to_string();
 
         // Git blame hunks can span multiple lines
         let start_line = hunk.final_start_line();
@@ -1208,19 +2157,21 @@ pub fn git_blame(repo_path: &str, file_path: &str) -> Result<Vec<BlameInfo>, Str
                 .unwrap_or(&"")
                 .to_string();
 
-            result.push(BlameInfo {
-                line_number: line_num,
-                commit_id: commit_id.to_string(),
-                short_id: commit_id.to_string()[..7.min(commit_id.to_string().len())].to_string(),
-                author: sig.name().unwrap_or("Unknown").to_string(),
+            result.push(BlameLine {
+                line_no: line_num as u32,
+                commit_id: id_str.clone(),
+                short_id: short_id.clone(),
+                author_name: author_name.clone(),
+                author_email: author_email.clone(),
                 timestamp: sig.when().seconds(),
-                line_content,
+                summary: summary.clone(),
+                content: line_content,
             });
         }
     }
 
     // Sort by line number
-    result.sort_by_key(|b| b.line_number);
+    result.sort_by_key(|b| b.line_no);
 
     Ok(result)
 }
@@ -1318,41 +2269,63 @@ pub fn delete_tag(repo_path: &str, name: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// Revert a commit
+/// Check out a tag as a detached HEAD
+pub fn checkout_tag(repo_path: &str, name: &str) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let commit = repo
+        .find_reference(&format!("refs/tags/{}", name))
+        .and_then(|r| r.peel_to_commit())
+        .map_err(|e| e.to_string())?;
+
+    // Update the working tree first so a dirty file aborts before HEAD moves
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.safe();
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout_builder))
+        .map_err(|e| e.to_string())?;
+    repo.set_head_detached(commit.id())
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Revert a commit (applies the inverse to the index and working tree, then commits)
 pub fn revert_commit(repo_path: &str, commit_id: &str) -> Result<String, String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
 
     let oid = Oid::from_str(commit_id).map_err(|e| e.to_string())?;
     let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
 
-    // Get current HEAD
-    let head = repo.head().map_err(|e| e.to_string())?;
-    let head_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+    // Apply the inverse changes into the index and working tree
+    repo.revert(&commit, None).map_err(|e| e.to_string())?;
 
-    // Revert the commit
-    let mut revert_index = repo
-        .revert_commit(&commit, &head_commit, 0, None)
-        .map_err(|e| e.to_string())?;
+    let mut index = repo.index().map_err(|e| e.to_string())?;
 
-    if revert_index.has_conflicts() {
-        return Err("Revert resulted in conflicts. Please resolve manually.".to_string());
+    if index.has_conflicts() {
+        // Leave the repo in the reverting state for manual resolution
+        let conflicted: Vec<String> = get_conflict_files(repo_path)?
+            .into_iter()
+            .map(|c| c.path)
+            .collect();
+        return Err(format!(
+            "Revert resulted in conflicts in: {}. Please resolve manually.",
+            conflicted.join(", ")
+        ));
     }
 
     // Write the index to a tree
-    let tree_oid = revert_index
-        .write_tree_to(&repo)
-        .map_err(|e| e.to_string())?;
+    let tree_oid = index.write_tree().map_err(|e| e.to_string())?;
     let tree = repo.find_tree(tree_oid).map_err(|e| e.to_string())?;
 
+    // Get current HEAD
+    let head = repo.head().map_err(|e| e.to_string())?;
+    let head_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+
     // Create revert commit
     let sig = repo
         .signature()
         .unwrap_or_else(|_| Signature::now("DataTeX User", "user@datatex.local").unwrap());
 
-    let revert_msg = format!(
-        "Revert \"{}\"",
-        commit.message().unwrap_or("").lines().next().unwrap_or("")
-    );
+    let revert_msg = format!("Revert \"{}\"", commit.summary().unwrap_or(""));
 
     let new_oid = repo
         .commit(
@@ -1365,6 +2338,9 @@ pub fn revert_commit(repo_path: &str, commit_id: &str) -> Result<String, String>
         )
         .map_err(|e| e.to_string())?;
 
+    // Clear REVERT_HEAD and friends now that the revert is committed
+    repo.cleanup_state().map_err(|e| e.to_string())?;
+
     Ok(new_oid.to_string())
 }
 
@@ -1419,6 +2395,85 @@ pub fn get_conflict_files(repo_path: &str) -> Result<Vec<ConflictFile>, String>
     Ok(conflicts)
 }
 
+/// A conflicted file with the contents of each side (`None` where a side
+/// doesn't have the file, e.g. deleted on one branch)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConflictEntry {
+    pub path: String,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+    pub base: Option<String>,
+}
+
+/// Get conflicted files together with their base/ours/theirs contents
+pub fn get_conflicts(repo_path: &str) -> Result<Vec<ConflictEntry>, String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let index = repo.index().map_err(|e| e.to_string())?;
+
+    let blob_text = |entry: &Option<git2::IndexEntry>| -> Result<Option<String>, String> {
+        match entry {
+            Some(entry) => {
+                let blob = repo.find_blob(entry.id).map_err(|e| e.to_string())?;
+                Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+            }
+            None => Ok(None),
+        }
+    };
+
+    let mut conflicts = Vec::new();
+
+    for conflict in index.conflicts().map_err(|e| e.to_string())? {
+        let conflict = conflict.map_err(|e| e.to_string())?;
+        let path = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref())
+            .map(|e| String::from_utf8_lossy(&e.path).to_string())
+            .unwrap_or_default();
+
+        conflicts.push(ConflictEntry {
+            path,
+            ours: blob_text(&conflict.our)?,
+            theirs: blob_text(&conflict.their)?,
+            base: blob_text(&conflict.ancestor)?,
+        });
+    }
+
+    Ok(conflicts)
+}
+
+/// Resolve a conflict: write `resolved_content` to the working tree and stage it
+pub fn resolve_conflict(
+    repo_path: &str,
+    file_path: &str,
+    resolved_content: &str,
+) -> Result<(), String> {
+    let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or("Repository has no working directory")?;
+
+    // Only paths inside the working tree may be written
+    let rel_path = Path::new(file_path);
+    let inside_workdir = rel_path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if file_path.is_empty() || !inside_workdir {
+        return Err(format!("Invalid conflict path: {}", file_path));
+    }
+
+    std::fs::write(workdir.join(rel_path), resolved_content).map_err(|e| e.to_string())?;
+
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    // Drop every stage (base/ours/theirs) before adding the resolved file
+    index.remove_path(rel_path).map_err(|e| e.to_string())?;
+    index.add_path(rel_path).map_err(|e| e.to_string())?;
+    index.write().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
 /// Get content of a blob for conflict resolution
 pub fn get_blob_content(repo_path: &str, blob_oid: &str) -> Result<String, String> {
     let repo = Repository::open(repo_path).map_err(|e| e.to_string())?;
@@ -1522,3 +2577,113 @@ pub fn get_side_by_side_diff(
 
     Ok(generate_side_by_side_diff(&old_content, &new_content))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh repository in a temp dir with `content` committed as `notes.tex`
+    fn repo_with_commit(name: &str, content: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("datatex-git-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).unwrap();
+        std::fs::write(dir.join("notes.tex"), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("notes.tex")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+        dir
+    }
+
+    fn pick(diff: &StructuredDiff, line_type: &str, content: &str) -> Vec<DiffLine> {
+        diff.lines
+            .iter()
+            .filter(|l| l.line_type == line_type && l.content == content)
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn stages_and_unstages_single_lines_of_a_mixed_hunk() {
+        let dir = repo_with_commit("lines", "a\nb\nc\n");
+        let repo_path = dir.to_str().unwrap();
+        // One hunk: "b" replaced by "B" plus a new last line
+        std::fs::write(dir.join("notes.tex"), "a\nB\nc\nd\n").unwrap();
+        // Reopened each time: a Repository caches its index
+        let index_text = || {
+            let repo = Repository::open(repo_path).unwrap();
+            String::from_utf8(index_file_bytes(&repo, "notes.tex").unwrap()).unwrap()
+        };
+
+        let unstaged = get_index_diff(repo_path, "notes.tex", false).unwrap();
+        stage_lines(repo_path, "notes.tex", pick(&unstaged, "add", "d")).unwrap();
+        assert_eq!(index_text(), "a\nb\nc\nd\n");
+
+        // The index now differs from HEAD; the deletion is staged without its replacement
+        let unstaged = get_index_diff(repo_path, "notes.tex", false).unwrap();
+        stage_lines(repo_path, "notes.tex", pick(&unstaged, "delete", "b")).unwrap();
+        assert_eq!(index_text(), "a\nc\nd\n");
+
+        let staged = get_index_diff(repo_path, "notes.tex", true).unwrap();
+        unstage_lines(repo_path, "notes.tex", pick(&staged, "add", "d")).unwrap();
+        assert_eq!(index_text(), "a\nc\n");
+
+        let staged = get_index_diff(repo_path, "notes.tex", true).unwrap();
+        unstage_lines(repo_path, "notes.tex", pick(&staged, "delete", "b")).unwrap();
+        assert_eq!(index_text(), "a\nb\nc\n");
+
+        // The working tree is never touched
+        assert_eq!(
+            std::fs::read_to_string(dir.join("notes.tex")).unwrap(),
+            "a\nB\nc\nd\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn resolve_conflict_refuses_paths_outside_the_working_tree() {
+        let dir = repo_with_commit("resolve", "a\n");
+        let repo_path = dir.to_str().unwrap();
+        let outside = dir.with_extension("outside");
+
+        for file_path in ["../escaped.tex", outside.to_str().unwrap(), ""] {
+            assert!(resolve_conflict(repo_path, file_path, "x\n").is_err());
+        }
+        assert!(!dir.parent().unwrap().join("escaped.tex").exists());
+        assert!(!outside.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stages_and_unstages_hunks_by_header() {
+        let dir = repo_with_commit("hunks", "a\nb\nc\nd\ne\nf\ng\nh\n");
+        let repo_path = dir.to_str().unwrap();
+        // Two hunks far enough apart: "b" -> "B" and "g" -> "G"
+        std::fs::write(dir.join("notes.tex"), "a\nB\nc\nd\ne\nf\nG\nh\n").unwrap();
+        let index_text = || {
+            let repo = Repository::open(repo_path).unwrap();
+            String::from_utf8(index_file_bytes(&repo, "notes.tex").unwrap()).unwrap()
+        };
+
+        stage_hunk(repo_path, "notes.tex", "@@ -5,4 +5,4 @@", Vec::new()).unwrap();
+        assert_eq!(index_text(), "a\nb\nc\nd\ne\nf\nG\nh\n");
+
+        // Lines from another hunk are refused
+        let unstaged = get_index_diff(repo_path, "notes.tex", false).unwrap();
+        assert!(stage_hunk(
+            repo_path,
+            "notes.tex",
+            "@@ -5,4 +5,4 @@",
+            pick(&unstaged, "add", "B")
+        )
+        .is_err());
+
+        unstage_hunk(repo_path, "notes.tex", "@@ -7 +7 @@", Vec::new()).unwrap();
+        assert_eq!(index_text(), "a\nb\nc\nd\ne\nf\ng\nh\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
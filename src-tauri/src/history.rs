@@ -36,6 +36,9 @@ pub struct DiffChange {
     pub new_start: Option<usize>,
     pub new_end: Option<usize>,
     pub content: String,
+    /// Changed character ranges `(start, end)` within `content` for modified lines
+    #[serde(default)]
+    pub highlights: Vec<CharRange>,
 }
 
 /// Statistics for a diff
@@ -53,6 +56,58 @@ pub fn hash_content(content: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Calculate SHA256 hash of a file on disk, streamed in 64KB chunks.
+/// Matches `hash_content` for text files since both hash the raw bytes.
+pub fn hash_file(path: &std::path::Path) -> std::io::Result<String> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Leading bytes of a gzip stream; plain UTF-8 text can never start with them,
+/// so legacy uncompressed rows are told apart without a schema change.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Gzip snapshot content before storing it
+fn compress_content(content: &str) -> Result<Vec<u8>, String> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(content.as_bytes())
+        .map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+/// Inverse of `compress_content`; rows saved before compression are returned as-is
+fn decompress_content(stored: Vec<u8>) -> Result<String, String> {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    if !stored.starts_with(&GZIP_MAGIC) {
+        return String::from_utf8(stored).map_err(|e| e.to_string());
+    }
+    let mut content = String::new();
+    GzDecoder::new(stored.as_slice())
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to decompress snapshot: {}", e))?;
+    Ok(content)
+}
+
 /// Save a snapshot of file content to history
 pub async fn save_snapshot(
     pool: &Pool<Sqlite>,
@@ -85,7 +140,7 @@ pub async fn save_snapshot(
     )
     .bind(&id)
     .bind(file_path)
-    .bind(content)
+    .bind(compress_content(content)?)
     .bind(&content_hash)
     .bind(summary)
     .bind(if is_manual { 1 } else { 0 })
@@ -96,6 +151,42 @@ pub async fn save_snapshot(
     Ok(id)
 }
 
+/// Automatic snapshots closer together than this are skipped
+pub const AUTOSNAPSHOT_MIN_INTERVAL_SECS: f64 = 30.0;
+/// Automatic snapshots kept per file when no limit is given
+pub const DEFAULT_KEEP_SNAPSHOTS: i32 = 50;
+
+/// Snapshot a file on save. Rapid saves are debounced: nothing is written if the latest
+/// snapshot is automatic and younger than `AUTOSNAPSHOT_MIN_INTERVAL_SECS`. After writing,
+/// older automatic snapshots beyond `keep_count` are pruned.
+pub async fn autosnapshot(
+    pool: &Pool<Sqlite>,
+    file_path: &str,
+    content: &str,
+    keep_count: i32,
+) -> Result<String, String> {
+    let last: Option<(i32, f64)> = sqlx::query_as(
+        "SELECT is_manual_snapshot, (julianday('now') - julianday(created_at)) * 86400.0
+         FROM file_history WHERE file_path = ? ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(file_path)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Some((is_manual, age_secs)) = last {
+        if is_manual == 0 && age_secs < AUTOSNAPSHOT_MIN_INTERVAL_SECS {
+            return Ok("debounced".to_string());
+        }
+    }
+
+    let id = save_snapshot(pool, file_path, content, None, false).await?;
+    if id != "no_change" {
+        cleanup_old_snapshots(pool, file_path, keep_count).await?;
+    }
+    Ok(id)
+}
+
 /// Get history entries for a file (without content for performance)
 pub async fn get_file_history(
     pool: &Pool<Sqlite>,
@@ -132,18 +223,95 @@ pub async fn get_file_history(
     Ok(entries)
 }
 
+/// A history entry with its change counts relative to the snapshot before it
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TimelineEntry {
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+    pub stats: DiffStats,
+}
+
+/// History entries, newest first, each with `DiffStats` against the previous snapshot.
+/// The oldest snapshot of a file counts entirely as additions.
+pub async fn get_history_timeline(
+    pool: &Pool<Sqlite>,
+    file_path: &str,
+    limit: Option<i32>,
+) -> Result<Vec<TimelineEntry>, String> {
+    let limit = limit.unwrap_or(50);
+
+    // One extra row so the oldest returned entry still has something to diff against
+    let rows = sqlx::query(
+        "SELECT id, file_path, content_hash, created_at, summary, is_manual_snapshot, content
+         FROM file_history
+         WHERE file_path = ?
+         ORDER BY created_at DESC
+         LIMIT ?",
+    )
+    .bind(file_path)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let contents = rows
+        .iter()
+        .map(|row| decompress_content(row.get("content")))
+        .collect::<Result<Vec<String>, String>>()?;
+
+    let timeline = rows
+        .iter()
+        .take(limit.max(0) as usize)
+        .enumerate()
+        .map(|(i, row)| {
+            let previous = contents.get(i + 1).map(String::as_str).unwrap_or("");
+            TimelineEntry {
+                entry: HistoryEntry {
+                    id: row.get("id"),
+                    file_path: row.get("file_path"),
+                    content_hash: row.get("content_hash"),
+                    created_at: row.get("created_at"),
+                    summary: row.get("summary"),
+                    is_manual_snapshot: row.get::<i32, _>("is_manual_snapshot") == 1,
+                },
+                stats: diff_stats(previous, &contents[i]),
+            }
+        })
+        .collect();
+
+    Ok(timeline)
+}
+
+/// Line counts of a diff, without building the change list
+pub fn diff_stats(old_content: &str, new_content: &str) -> DiffStats {
+    let diff = TextDiff::from_lines(old_content, new_content);
+    let mut stats = DiffStats {
+        additions: 0,
+        deletions: 0,
+        unchanged: 0,
+    };
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Equal => stats.unchanged += 1,
+            ChangeTag::Delete => stats.deletions += 1,
+            ChangeTag::Insert => stats.additions += 1,
+        }
+    }
+    stats
+}
+
 /// Get the content of a specific snapshot
 pub async fn get_snapshot_content(
     pool: &Pool<Sqlite>,
     snapshot_id: &str,
 ) -> Result<String, String> {
-    let content: String = sqlx::query_scalar("SELECT content FROM file_history WHERE id = ?")
+    let content: Vec<u8> = sqlx::query_scalar("SELECT content FROM file_history WHERE id = ?")
         .bind(snapshot_id)
         .fetch_one(pool)
         .await
         .map_err(|e| e.to_string())?;
 
-    Ok(content)
+    decompress_content(content)
 }
 
 /// Restore a file to a specific snapshot (returns the content)
@@ -158,11 +326,90 @@ pub async fn get_restore_content(
         .map_err(|e| e.to_string())?;
 
     let file_path: String = row.get("file_path");
-    let content: String = row.get("content");
+    let content = decompress_content(row.get("content"))?;
 
     Ok((file_path, content))
 }
 
+/// Restore a file on disk to a snapshot. The current on-disk content is first saved as a
+/// manual snapshot, so the restore itself can be undone.
+pub async fn restore_snapshot(pool: &Pool<Sqlite>, snapshot_id: &str) -> Result<(), String> {
+    let (file_path, content) = get_restore_content(pool, snapshot_id).await?;
+
+    let path = std::path::Path::new(&file_path);
+    if !path.exists() {
+        return Err(format!("File no longer exists: {}", file_path));
+    }
+
+    let current = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    save_snapshot(pool, &file_path, &current, Some("Before restore"), true).await?;
+
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", file_path, e))
+}
+
+/// Pair each run of deleted lines with the inserted lines right after it, in order.
+/// These are the lines treated as modified rather than replaced.
+pub fn modified_line_pairs(tags: &[ChangeTag]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let mut i = 0;
+    while i < tags.len() {
+        if tags[i] != ChangeTag::Delete {
+            i += 1;
+            continue;
+        }
+        let deletes_start = i;
+        while i < tags.len() && tags[i] == ChangeTag::Delete {
+            i += 1;
+        }
+        let inserts_start = i;
+        while i < tags.len() && tags[i] == ChangeTag::Insert {
+            i += 1;
+        }
+        let paired = (inserts_start - deletes_start).min(i - inserts_start);
+        pairs.extend((0..paired).map(|k| (deletes_start + k, inserts_start + k)));
+    }
+    pairs
+}
+
+/// Half-open `(start, end)` character range within a line
+pub type CharRange = (usize, usize);
+
+/// Word-level changes between two versions of a line, as `(start, end)` character ranges
+/// into the old and new line. Offsets count chars, not bytes, so Greek text lines up in the UI.
+pub fn word_highlights(old_line: &str, new_line: &str) -> (Vec<CharRange>, Vec<CharRange>) {
+    fn push_range(ranges: &mut Vec<CharRange>, start: usize, end: usize) {
+        match ranges.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let diff = TextDiff::from_words(old_line, new_line);
+    let (mut old_pos, mut new_pos) = (0, 0);
+    let (mut old_ranges, mut new_ranges) = (Vec::new(), Vec::new());
+
+    for change in diff.iter_all_changes() {
+        let len = change.value().chars().count();
+        match change.tag() {
+            ChangeTag::Equal => {
+                old_pos += len;
+                new_pos += len;
+            }
+            ChangeTag::Delete => {
+                push_range(&mut old_ranges, old_pos, old_pos + len);
+                old_pos += len;
+            }
+            ChangeTag::Insert => {
+                push_range(&mut new_ranges, new_pos, new_pos + len);
+                new_pos += len;
+            }
+        }
+    }
+
+    (old_ranges, new_ranges)
+}
+
 /// Generate diff between two snapshots or between snapshot and current content
 pub fn generate_diff(old_content: &str, new_content: &str) -> DiffResult {
     let diff = TextDiff::from_lines(old_content, new_content);
@@ -195,9 +442,20 @@ pub fn generate_diff(old_content: &str, new_content: &str) -> DiffResult {
             new_start: change.new_index(),
             new_end: change.new_index().map(|i| i + 1),
             content: change.value().to_string(),
+            highlights: Vec::new(),
         });
     }
 
+    let tags: Vec<ChangeTag> = diff.iter_all_changes().map(|c| c.tag()).collect();
+    for (old_idx, new_idx) in modified_line_pairs(&tags) {
+        let (old_ranges, new_ranges) = word_highlights(
+            changes[old_idx].content.trim_end_matches('\n'),
+            changes[new_idx].content.trim_end_matches('\n'),
+        );
+        changes[old_idx].highlights = old_ranges;
+        changes[new_idx].highlights = new_ranges;
+    }
+
     DiffResult {
         old_content: old_content.to_string(),
         new_content: new_content.to_string(),
@@ -265,3 +523,43 @@ pub async fn cleanup_old_snapshots(
 
     Ok(result.rows_affected() as usize)
 }
+
+/// Delete automatic snapshots of a file created more than `days` days ago
+pub async fn cleanup_snapshots_older_than(
+    pool: &Pool<Sqlite>,
+    file_path: &str,
+    days: i64,
+) -> Result<usize, String> {
+    let result = sqlx::query(
+        "DELETE FROM file_history
+         WHERE file_path = ?
+         AND is_manual_snapshot = 0
+         AND created_at < datetime('now', ?)",
+    )
+    .bind(file_path)
+    .bind(format!("-{} days", days))
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(result.rows_affected() as usize)
+}
+
+/// Apply the retention policy (last `keep_count` and younger than `keep_days`) to every file
+pub async fn cleanup_all_snapshots(
+    pool: &Pool<Sqlite>,
+    keep_count: i32,
+    keep_days: i64,
+) -> Result<usize, String> {
+    let file_paths: Vec<String> = sqlx::query_scalar("SELECT DISTINCT file_path FROM file_history")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut removed = 0;
+    for file_path in &file_paths {
+        removed += cleanup_old_snapshots(pool, file_path, keep_count).await?;
+        removed += cleanup_snapshots_older_than(pool, file_path, keep_days).await?;
+    }
+    Ok(removed)
+}
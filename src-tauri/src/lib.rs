@@ -3,18 +3,22 @@ use sqlx::Row;
 use std::fs;
 use tauri::{Manager, State};
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 use walkdir::WalkDir; // For typed metadata queries
 
 mod agent;
 mod ai;
 mod compiler;
+mod conversations;
 mod database;
 mod git;
 mod history;
 mod lsp;
 mod search;
+mod tex_dependencies;
 mod texlab_downloader;
+mod text_encoding;
 mod tools;
 mod vectors;
 mod watcher;
@@ -28,7 +32,7 @@ mod commands {
     pub mod ctan;
 }
 
-use database::entities::{Collection, Resource};
+use database::entities::{Collection, Resource, SearchHit};
 use database::DatabaseManager;
 use lsp::TexlabManager;
 use vectors::VectorStoreState;
@@ -69,8 +73,105 @@ fn compile_tex(
     engine: String,
     args: Vec<String>,
     output_dir: String,
+    timeout_secs: Option<u64>,
+    shell_escape: Option<bool>,
+    shell_restricted: Option<bool>,
 ) -> Result<String, String> {
-    compiler::compile(&file_path, &engine, args, &output_dir)
+    let shell_escape = compiler::ShellEscape::from_flags(
+        shell_escape.unwrap_or(false),
+        shell_restricted.unwrap_or(false),
+    );
+    compiler::compile(
+        &file_path,
+        &engine,
+        args,
+        &output_dir,
+        timeout_secs,
+        shell_escape,
+    )
+}
+
+#[tauri::command]
+fn compile_tex_with_log_cmd(
+    file_path: String,
+    engine: String,
+    args: Vec<String>,
+    output_dir: String,
+    timeout_secs: Option<u64>,
+    shell_escape: Option<bool>,
+    shell_restricted: Option<bool>,
+) -> Result<compiler::CompileResult, String> {
+    let shell_escape = compiler::ShellEscape::from_flags(
+        shell_escape.unwrap_or(false),
+        shell_restricted.unwrap_or(false),
+    );
+    compiler::compile_with_result(
+        &file_path,
+        &engine,
+        args,
+        &output_dir,
+        timeout_secs,
+        shell_escape,
+        true,
+    )
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn compile_document_cmd(
+    file_path: String,
+    engine: String,
+    args: Vec<String>,
+    output_dir: String,
+    bib_engine: Option<String>,
+    passes: Option<u32>,
+    timeout_secs: Option<u64>,
+    shell_escape: Option<bool>,
+    shell_restricted: Option<bool>,
+) -> Result<String, String> {
+    let shell_escape = compiler::ShellEscape::from_flags(
+        shell_escape.unwrap_or(false),
+        shell_restricted.unwrap_or(false),
+    );
+    compiler::compile_document(
+        &file_path,
+        &engine,
+        args,
+        &output_dir,
+        bib_engine.as_deref(),
+        passes,
+        timeout_secs,
+        shell_escape,
+    )
+}
+
+#[tauri::command]
+fn compile_tex_streaming_cmd(
+    file_path: String,
+    engine: String,
+    args: Vec<String>,
+    output_dir: String,
+    shell_escape: Option<bool>,
+    shell_restricted: Option<bool>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let shell_escape = compiler::ShellEscape::from_flags(
+        shell_escape.unwrap_or(false),
+        shell_restricted.unwrap_or(false),
+    );
+    compiler::compile_streaming(
+        &file_path,
+        &engine,
+        args,
+        &output_dir,
+        shell_escape,
+        app_handle,
+    )
+}
+
+#[tauri::command]
+fn parse_latex_log_cmd(log_path: String) -> Result<Vec<compiler::LogMessage>, String> {
+    compiler::parse_log(&log_path)
 }
 
 #[tauri::command]
@@ -78,28 +179,83 @@ fn run_synctex_command(args: Vec<String>, cwd: String) -> Result<String, String>
     compiler::run_synctex(args, &cwd)
 }
 
+#[tauri::command]
+fn synctex_forward_cmd(
+    file: String,
+    line: u32,
+    column: Option<u32>,
+    pdf: String,
+) -> Result<compiler::SyncPos, String> {
+    compiler::synctex_forward(&file, line, column.unwrap_or(0), &pdf)
+}
+
+#[tauri::command]
+fn synctex_inverse_cmd(
+    pdf: String,
+    page: u32,
+    x: f32,
+    y: f32,
+) -> Result<compiler::SyncSource, String> {
+    compiler::synctex_inverse(&pdf, page, x, y)
+}
+
 #[tauri::command]
 fn run_texcount_command(args: Vec<String>, cwd: String) -> Result<String, String> {
     compiler::run_texcount(args, &cwd)
 }
 
+/// Compile a resource with its own build command: the typed metadata's `build_command`,
+/// then the legacy `buildCommand` metadata key, then the given `engine`/`args`
+/// (the user's compiler settings), then the default engine. Shell escape comes from the
+/// user's settings too; a build command can't turn it on.
 #[tauri::command]
-async fn compile_resource_cmd(id: String, state: State<'_, AppState>) -> Result<String, String> {
+async fn compile_resource_cmd(
+    id: String,
+    engine: Option<String>,
+    args: Option<Vec<String>>,
+    shell_escape: Option<bool>,
+    shell_restricted: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<compiler::CompileResult, String> {
+    let shell_escape = compiler::ShellEscape::from_flags(
+        shell_escape.unwrap_or(false),
+        shell_restricted.unwrap_or(false),
+    );
+
     let db_guard = state.db_manager.lock().await;
     let db = db_guard.as_ref().ok_or("Database not initialized")?;
 
     let resource_opt = db.get_resource_by_id(&id).await?;
     let resource = resource_opt.ok_or("Resource not found")?;
 
-    // Parse metadata
-    let metadata_json = resource.metadata.as_ref().ok_or("No metadata found")?;
-    let preamble_id_opt = metadata_json.get("preamble").and_then(|v| v.as_str());
-    let build_command = metadata_json
-        .get("buildCommand")
-        .and_then(|v| v.as_str())
-        .unwrap_or("pdflatex");
+    let metadata_json = resource.metadata.clone().unwrap_or_default();
+    let build_command = match db.get_build_command(&resource.id, &resource.kind).await? {
+        Some(command) => command,
+        None => metadata_json
+            .get("buildCommand")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    let (engine, mut args) = match engine {
+        Some(engine) if build_command.trim().is_empty() => {
+            let fallback = format!("{} {}", engine, args.unwrap_or_default().join(" "));
+            compiler::parse_build_command(&fallback)
+        }
+        _ => compiler::parse_build_command(&build_command),
+    };
+    // Never let the engine stop and wait for input on an error
+    if !args.iter().any(|arg| arg.starts_with("-interaction")) {
+        args.insert(0, "-interaction=nonstopmode".to_string());
+    }
+
+    let original_path = std::path::Path::new(&resource.path);
+    let parent_dir = original_path.parent().unwrap_or(std::path::Path::new("."));
+    let output_dir = parent_dir.to_string_lossy().to_string();
 
-    if let Some(preamble_id) = preamble_id_opt {
+    let compile_path = if let Some(preamble_id) =
+        metadata_json.get("preamble").and_then(|v| v.as_str())
+    {
         // Need to wrap content
         let preamble_content = if preamble_id.starts_with("builtin:") {
             // Simple built-in defaults
@@ -122,7 +278,6 @@ async fn compile_resource_cmd(id: String, state: State<'_, AppState>) -> Result<
         };
 
         // Read the actual resource content
-        // Assuming resource.path is valid
         let body_content = fs::read_to_string(&resource.path)
             .map_err(|e| format!("Failed to read resource file: {}", e))?;
 
@@ -133,82 +288,55 @@ async fn compile_resource_cmd(id: String, state: State<'_, AppState>) -> Result<
         );
 
         // Save temp file in same dir to preserve relative paths.
-        let original_path = std::path::Path::new(&resource.path);
-        let parent_dir = original_path.parent().unwrap_or(std::path::Path::new("."));
-        let file_stem = original_path.file_stem().unwrap().to_str().unwrap();
-        let temp_filename = format!("{}_preview.tex", file_stem);
-        let temp_path = parent_dir.join(&temp_filename);
+        let file_stem = original_path
+            .file_stem()
+            .ok_or("Resource path has no file name")?
+            .to_string_lossy();
+        let temp_path = parent_dir.join(format!("{}_preview.tex", file_stem));
 
         fs::write(&temp_path, full_doc).map_err(|e| format!("Failed to write temp file: {}", e))?;
 
-        // Compile
-        let output_dir = parent_dir.to_string_lossy().to_string();
-
         // Use -jobname to output PDF with original filename for viewer compatibility.
-        let jobname_arg = format!("-jobname={}", file_stem);
-
-        let result = compiler::compile(
-            &temp_path.to_string_lossy(),
-            build_command,
-            vec![jobname_arg],
-            &output_dir,
-        );
+        args.push(format!("-jobname={}", file_stem));
+        temp_path.to_string_lossy().to_string()
+    } else {
+        resource.path.clone()
+    };
 
-        // Keep temp file for debugging/logging.
+    // Compiling can take a while; don't hold the database lock meanwhile
+    drop(db_guard);
 
-        // Output path logic (compiler usually replaces extension)
-        match result {
-            Ok(_) => {
-                // Since we used -jobname=file_stem, the output is file_stem.pdf
-                let pdf_name = format!("{}.pdf", file_stem);
-                let pdf_path = parent_dir.join(pdf_name);
-                Ok(pdf_path.to_string_lossy().to_string())
-            }
-            Err(e) => Err(e),
-        }
-    } else {
-        // Normal compilation
-        let parent_dir = std::path::Path::new(&resource.path)
-            .parent()
-            .unwrap_or(std::path::Path::new("."));
-        let output_dir = parent_dir.to_string_lossy().to_string();
-
-        match compiler::compile(&resource.path, build_command, vec![], &output_dir) {
-            Ok(_) => {
-                // Assume PDF is [filename].pdf
-                let original_path = std::path::Path::new(&resource.path);
-                let file_stem = original_path.file_stem().unwrap().to_str().unwrap();
-                let pdf_name = format!("{}.pdf", file_stem);
-                let pdf_path = original_path.with_file_name(pdf_name);
-                Ok(pdf_path.to_string_lossy().to_string())
-            }
-            Err(e) => Err(e),
-        }
-    }
+    tauri::async_runtime::spawn_blocking(move || {
+        compiler::compile_with_result(
+            &compile_path,
+            &engine,
+            args,
+            &output_dir,
+            None,
+            shell_escape,
+            true,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
 fn get_system_fonts() -> Vec<String> {
-    use std::process::Command;
-    let output = if cfg!(target_os = "linux") {
-        Command::new("fc-list").arg(":").arg("family").output().ok()
+    let mut fonts = if cfg!(target_os = "linux") {
+        linux_font_families()
+    } else if cfg!(target_os = "windows") {
+        windows_font_families()
+    } else if cfg!(target_os = "macos") {
+        macos_font_families()
     } else {
-        None
+        Vec::new()
     };
 
-    if let Some(output) = output {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let mut fonts: Vec<String> = stdout
-                .lines()
-                .flat_map(|line| line.split(','))
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
-            fonts.sort();
-            fonts.dedup();
-            return fonts;
-        }
+    if !fonts.is_empty() {
+        fonts.sort();
+        fonts.dedup();
+        return fonts;
     }
     vec![
         "Consolas".to_string(),
@@ -219,6 +347,93 @@ fn get_system_fonts() -> Vec<String> {
     ]
 }
 
+/// Font families via fontconfig (`fc-list : family`)
+fn linux_font_families() -> Vec<String> {
+    use std::process::Command;
+    let output = match Command::new("fc-list").arg(":").arg("family").output() {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .flat_map(|line| line.split(','))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Font families from the machine and per-user `...\CurrentVersion\Fonts` registry keys
+fn windows_font_families() -> Vec<String> {
+    use std::process::Command;
+    const FONT_KEYS: [&str; 2] = [
+        r"HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts",
+        r"HKCU\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts",
+    ];
+    // Style suffixes that registry value names append to the family name
+    const STYLES: [&str; 12] = [
+        "Bold",
+        "Italic",
+        "Oblique",
+        "Light",
+        "Semilight",
+        "Semibold",
+        "Black",
+        "Thin",
+        "Medium",
+        "Regular",
+        "Condensed",
+        "Extra",
+    ];
+
+    let mut families = Vec::new();
+    for key in FONT_KEYS {
+        let output = match Command::new("reg").args(["query", key]).output() {
+            Ok(o) if o.status.success() => o,
+            _ => continue,
+        };
+
+        // Lines look like: "    Arial Bold (TrueType)    REG_SZ    arialbd.ttf"
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some((name, _)) = line.trim().split_once("    REG_SZ") else {
+                continue;
+            };
+            let name = name.split(" (").next().unwrap_or(name);
+
+            // Collections are listed as "Cambria & Cambria Math"
+            for family in name.split(" & ") {
+                let mut words: Vec<&str> = family.split_whitespace().collect();
+                while words.len() > 1 && STYLES.contains(words.last().unwrap()) {
+                    words.pop();
+                }
+                if !words.is_empty() {
+                    families.push(words.join(" "));
+                }
+            }
+        }
+    }
+    families
+}
+
+/// Font families from `system_profiler SPFontsDataType`
+fn macos_font_families() -> Vec<String> {
+    use std::process::Command;
+    let output = match Command::new("system_profiler")
+        .arg("SPFontsDataType")
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Family:"))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
 #[derive(serde::Serialize)]
 struct TableDataResponse {
     data: Vec<serde_json::Value>,
@@ -226,6 +441,15 @@ struct TableDataResponse {
     columns: Vec<String>,
 }
 
+#[tauri::command]
+async fn list_tables_cmd(
+    state: State<'_, AppState>,
+) -> Result<Vec<database::entities::TableSchema>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.list_tables().await
+}
+
 #[tauri::command]
 async fn get_table_data_cmd(
     table_name: String,
@@ -250,6 +474,22 @@ async fn get_table_data_cmd(
     }
 }
 
+#[tauri::command]
+async fn run_query_cmd(
+    sql: String,
+    params: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<TableDataResponse, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    let (data, total_count, columns) = db.run_query(&sql, params).await?;
+    Ok(TableDataResponse {
+        data,
+        total_count,
+        columns,
+    })
+}
+
 #[tauri::command]
 async fn update_cell_cmd(
     table_name: String,
@@ -266,6 +506,50 @@ async fn update_cell_cmd(
     }
 }
 
+#[tauri::command]
+async fn update_row_cmd(
+    table_name: String,
+    id: String,
+    values: std::collections::HashMap<String, String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    if let Some(db) = &*db_guard {
+        db.update_row(table_name, id, values).await
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn delete_row_cmd(
+    table_name: String,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<u64, String> {
+    let db_guard = state.db_manager.lock().await;
+    if let Some(db) = &*db_guard {
+        db.delete_row(table_name, id).await
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn export_table_cmd(
+    table_name: String,
+    format: String,
+    output_path: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let db_guard = state.db_manager.lock().await;
+    if let Some(db) = &*db_guard {
+        db.export_table(table_name, &format, &output_path).await
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
 // ===== New Database Commands =====
 
 #[tauri::command]
@@ -278,6 +562,15 @@ async fn get_collections_cmd(state: State<'_, AppState>) -> Result<Vec<Collectio
     }
 }
 
+#[tauri::command]
+async fn get_collections_with_counts_cmd(
+    state: State<'_, AppState>,
+) -> Result<Vec<database::entities::CollectionWithCount>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.get_collections_with_counts().await
+}
+
 #[tauri::command]
 async fn create_collection_cmd(
     name: String,
@@ -327,12 +620,109 @@ async fn get_resources_by_collections_cmd(
     }
 }
 
+#[tauri::command]
+async fn search_fts_cmd(
+    query: String,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchHit>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    db.search_fts(&query, limit.unwrap_or(50)).await
+}
+
+/// Outcome of a folder import
+#[derive(Debug, Default, serde::Serialize)]
+struct ImportReport {
+    added: usize,
+    /// Stopped early by `cancel_import_cmd`; everything imported before that is kept
+    cancelled: bool,
+    /// Dependency links found in the imported TeX sources
+    dependencies: usize,
+    skipped: Vec<String>,
+    errors: Vec<String>,
+}
+
+/// Payload of `import-progress`
+#[derive(Debug, Clone, serde::Serialize)]
+struct ImportProgress {
+    current: usize,
+    total: usize,
+    path: String,
+}
+
+/// Emit `import-progress` every this many files (and for the last one)
+const IMPORT_PROGRESS_INTERVAL: usize = 50;
+
+/// Cancellation token of the folder import in progress, if any
+#[derive(Default)]
+struct ImportState {
+    cancel_token: std::sync::Mutex<Option<CancellationToken>>,
+}
+
+/// Uninstalls the import's cancellation token when the import ends, however it ends
+struct ImportTokenGuard<'a>(&'a ImportState);
+
+impl Drop for ImportTokenGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut current) = self.0.cancel_token.lock() {
+            *current = None;
+        }
+    }
+}
+
+/// Build artifacts and tooling folders skipped when no exclude patterns are given
+const DEFAULT_IMPORT_EXCLUDES: [&str; 6] = [
+    "**/.git",
+    "**/build",
+    "**/node_modules",
+    "**/*.aux",
+    "**/*.log",
+    "**/*.synctex.gz",
+];
+
+/// Compile import exclude patterns, falling back to `DEFAULT_IMPORT_EXCLUDES`
+fn build_exclude_set(exclude_globs: &[String]) -> Result<globset::GlobSet, String> {
+    let patterns: Vec<String> = if exclude_globs.is_empty() {
+        DEFAULT_IMPORT_EXCLUDES
+            .iter()
+            .map(|p| p.to_string())
+            .collect()
+    } else {
+        exclude_globs.to_vec()
+    };
+
+    let mut builder = globset::GlobSetBuilder::new();
+    for pattern in &patterns {
+        // "build/" should match the directory itself
+        let pattern = pattern.trim_end_matches('/');
+        let glob = globset::Glob::new(pattern)
+            .map_err(|e| format!("Invalid exclude pattern '{}': {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn import_folder_cmd(
     path: String,
     collection_name: String,
+    exclude_globs: Vec<String>,
     state: State<'_, AppState>,
-) -> Result<usize, String> {
+    import_state: State<'_, ImportState>,
+    app_handle: tauri::AppHandle,
+) -> Result<ImportReport, String> {
+    use tauri::Emitter;
+
+    let excludes = build_exclude_set(&exclude_globs)?;
+
+    let cancel_token = CancellationToken::new();
+    if let Ok(mut current) = import_state.cancel_token.lock() {
+        *current = Some(cancel_token.clone());
+    }
+    let _token_guard = ImportTokenGuard(import_state.inner());
+
     let db_guard = state.db_manager.lock().await;
     let db = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -347,13 +737,61 @@ async fn import_folder_cmd(
     };
     db.create_collection(&collection).await?;
 
-    // 2. Walk directory
-    let mut count = 0;
-    for entry in WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
+    // 2. Walk directory, inserting everything in a single transaction
+    let mut report = ImportReport::default();
+    let mut tex_sources = Vec::new();
+    let mut tx = db.pool.begin().await.map_err(|e| e.to_string())?;
+
+    // Match relative to the import root; excluded directories are never descended into
+    let root = std::path::PathBuf::from(&path);
+    let walk = || {
+        WalkDir::new(&path).into_iter().filter_entry(|e| {
+            let rel = e.path().strip_prefix(&root).unwrap_or(e.path());
+            rel.as_os_str().is_empty() || !excludes.is_match(rel)
+        })
+    };
+
+    // A quick first pass gives the progress total
+    let total = walk()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .count();
+    let mut current = 0;
+
+    for entry in walk() {
+        if cancel_token.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let entry_path = e
+                    .path()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.clone());
+                report.skipped.push(format!("{}: {}", entry_path, e));
+                continue;
+            }
+        };
+
         if entry.file_type().is_file() {
             let file_path = entry.path().to_string_lossy().to_string();
             let file_name = entry.file_name().to_string_lossy().to_string();
 
+            current += 1;
+            if current % IMPORT_PROGRESS_INTERVAL == 0 || current == total {
+                let _ = app_handle.emit(
+                    "import-progress",
+                    ImportProgress {
+                        current,
+                        total: total.max(current),
+                        path: file_path.clone(),
+                    },
+                );
+            }
+
             // Simple type detection extension
             let kind = if file_name.ends_with(".tex") {
                 "file"
@@ -376,48 +814,230 @@ async fn import_folder_cmd(
                 "file"
             };
 
+            // An unreadable file can't be hashed; skip it rather than store a stale row
+            let content_hash = match history::hash_file(entry.path()) {
+                Ok(hash) => Some(hash),
+                Err(e) => {
+                    report.skipped.push(format!("{}: {}", file_path, e));
+                    continue;
+                }
+            };
+
             let resource = Resource {
                 id: Uuid::new_v4().to_string(),
                 path: file_path,
                 kind: kind.to_string(),
                 collection: collection_name.clone(),
                 title: Some(file_name),
-                content_hash: None, // TODO: calculate hash
+                content_hash,
                 metadata: Some(serde_json::json!({})),
                 created_at: None,
                 updated_at: None,
             };
 
-            if let Err(e) = db.add_resource(&resource).await {
-                eprintln!("Failed to add resource: {}", e);
-                // Continue despite errors? or fail?
-                // For now, log and continue.
-            } else {
-                count += 1;
+            // Individual insert failures are recorded but don't abort the import
+            let content = fs::read_to_string(&resource.path).unwrap_or_default();
+            let inserted = match DatabaseManager::insert_resource(&mut *tx, &resource).await {
+                Ok(()) => DatabaseManager::index_resource(&mut tx, &resource, &content).await,
+                Err(e) => Err(e),
+            };
+            match inserted {
+                Ok(()) => {
+                    report.added += 1;
+                    let is_tex_source = [".tex", ".sty", ".cls"]
+                        .iter()
+                        .any(|ext| resource.path.ends_with(ext));
+                    if is_tex_source {
+                        tex_sources.push(tex_dependencies::ImportedSource {
+                            id: resource.id,
+                            path: entry.path().to_path_buf(),
+                            content,
+                        });
+                    }
+                }
+                Err(e) => report.errors.push(format!("{}: {}", resource.path, e)),
             }
         }
     }
 
-    Ok(count)
+    // Dropping the transaction on an early return rolls it back
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    // 3. Link \usepackage / \input / \documentclass references between imported files
+    let (linked, link_errors) =
+        tex_dependencies::link_sources(db, &root, &tex_sources, &cancel_token).await;
+    report.dependencies = linked;
+    report.errors.extend(link_errors);
+    report.cancelled = cancel_token.is_cancelled();
+    drop(db_guard);
+
+    // Edits made outside the editor in the new directories should be picked up too
+    if report.added > 0 {
+        if let Err(e) = watch_resource_dirs(&app_handle).await {
+            eprintln!("Failed to watch imported directories: {}", e);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Stop the running folder import; files imported so far are kept
+#[tauri::command]
+fn cancel_import_cmd(import_state: State<'_, ImportState>) {
+    if let Ok(current) = import_state.cancel_token.lock() {
+        if let Some(token) = current.as_ref() {
+            token.cancel();
+        }
+    }
 }
 
 #[tauri::command]
 async fn delete_collection_cmd(
     collection_name: String,
     state: State<'_, AppState>,
+    vector_state: State<'_, VectorStoreState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let paths: Vec<String> = db
+        .get_resources_by_collection(&collection_name)
+        .await?
+        .into_iter()
+        .map(|r| r.path)
+        .collect();
+    db.delete_collection(&collection_name).await?;
+
+    vectors::remove_embeddings(&vector_state, &app_handle, &paths).await?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn rename_collection_cmd(
+    old_name: String,
+    new_name: String,
+    state: State<'_, AppState>,
+    vector_state: State<'_, VectorStoreState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.rename_collection(&old_name, &new_name).await?;
+
+    vectors::rename_embeddings_collection(&vector_state, &app_handle, &old_name, &new_name).await?;
+    Ok(())
+}
+
+/// Read a resource's file as UTF-8, converting from its detected encoding
+#[tauri::command]
+async fn read_resource_cmd(id: String, state: State<'_, AppState>) -> Result<String, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.read_resource(&id).await
+}
+
+#[tauri::command]
+async fn move_resource_cmd(
+    resource_id: String,
+    target_collection: String,
+    state: State<'_, AppState>,
+    vector_state: State<'_, VectorStoreState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.move_resource(&resource_id, &target_collection).await?;
+
+    if let Some(resource) = db.get_resource_by_id(&resource_id).await? {
+        vectors::set_embeddings_collection(
+            &vector_state,
+            &app_handle,
+            &[resource.path],
+            &target_collection,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn move_resources_cmd(
+    ids: Vec<String>,
+    target_collection: String,
+    state: State<'_, AppState>,
+    vector_state: State<'_, VectorStoreState>,
+    app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let db_guard = state.db_manager.lock().await;
     let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.move_resources(&ids, &target_collection).await?;
 
-    db.delete_collection(&collection_name).await
+    let mut paths = Vec::with_capacity(ids.len());
+    for id in &ids {
+        if let Some(resource) = db.get_resource_by_id(id).await? {
+            paths.push(resource.path);
+        }
+    }
+    vectors::set_embeddings_collection(&vector_state, &app_handle, &paths, &target_collection)
+        .await?;
+    Ok(())
 }
 
+/// Delete a resource. Unless `force` is set, a resource that others depend on is
+/// left in place and its dependents are returned so the UI can ask first.
 #[tauri::command]
-async fn delete_resource_cmd(id: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn delete_resource_cmd(
+    id: String,
+    force: Option<bool>,
+    state: State<'_, AppState>,
+    vector_state: State<'_, VectorStoreState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<Resource>, String> {
     let db_guard = state.db_manager.lock().await;
     let db = db_guard.as_ref().ok_or("Database not initialized")?;
 
-    db.delete_resource(&id).await
+    if !force.unwrap_or(false) {
+        let dependents = db.get_dependents(&id, None).await?;
+        if !dependents.is_empty() {
+            return Ok(dependents);
+        }
+    }
+
+    let resource = db.get_resource_by_id(&id).await?;
+    db.delete_resource(&id).await?;
+
+    if let Some(resource) = resource {
+        vectors::remove_embeddings(&vector_state, &app_handle, &[resource.path]).await?;
+    }
+    Ok(Vec::new())
+}
+
+#[tauri::command]
+async fn find_duplicates_cmd(
+    state: State<'_, AppState>,
+) -> Result<Vec<database::entities::DuplicateGroup>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.find_duplicates().await
+}
+
+#[tauri::command]
+async fn merge_duplicates_cmd(
+    keep_id: String,
+    remove_ids: Vec<String>,
+    state: State<'_, AppState>,
+    vector_state: State<'_, VectorStoreState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let removed = db.merge_duplicates(&keep_id, &remove_ids).await?;
+
+    let paths: Vec<String> = removed.into_iter().map(|r| r.path).collect();
+    vectors::remove_embeddings(&vector_state, &app_handle, &paths).await?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -427,11 +1047,17 @@ async fn create_resource_cmd(
     content: String,
     metadata: Option<serde_json::Value>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<String, String> {
     let db_guard = state.db_manager.lock().await;
     let db = db_guard.as_ref().ok_or("Database not initialized")?;
 
+    // Checked before touching the disk so a bad call leaves no file behind
+    if !db.collection_exists(&collection_name).await? {
+        return Err(format!("Collection '{}' not found", collection_name));
+    }
+
     // 1. Write file to disk
+    let existed = std::path::Path::new(&path).exists();
     fs::write(&path, &content).map_err(|e| e.to_string())?;
 
     // 2. Add to database
@@ -457,19 +1083,13 @@ async fn create_resource_cmd(
         "file"
     };
 
-    let resource = Resource {
-        id: Uuid::new_v4().to_string(),
-        path: path.clone(),
-        kind: kind.to_string(),
-        collection: collection_name,
-        title: Some(file_name),
-        content_hash: None,
-        metadata: Some(metadata.unwrap_or(serde_json::json!({}))),
-        created_at: None,
-        updated_at: None,
-    };
-
-    db.add_resource(&resource).await
+    let result = db
+        .create_resource(&path, kind, &collection_name, Some(&file_name), metadata)
+        .await;
+    if result.is_err() && !existed {
+        let _ = fs::remove_file(&path);
+    }
+    result
 }
 
 #[tauri::command]
@@ -666,6 +1286,64 @@ async fn get_linked_resources_cmd(
         .await
 }
 
+#[tauri::command]
+async fn get_dependents_cmd(
+    target_id: String,
+    relation_type: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Resource>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    db.get_dependents(&target_id, relation_type.as_deref())
+        .await
+}
+
+#[tauri::command]
+async fn vacuum_database_cmd(state: State<'_, AppState>) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    db.vacuum().await
+}
+
+#[tauri::command]
+async fn backup_database_cmd(dest_path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    db.backup_to(&dest_path).await
+}
+
+#[tauri::command]
+async fn get_resources_by_tag_cmd(
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    db.get_resources_by_tag(&tag).await
+}
+
+#[tauri::command]
+async fn get_all_tags_cmd(state: State<'_, AppState>) -> Result<Vec<(String, i64)>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    db.get_all_tags().await
+}
+
+#[tauri::command]
+async fn detect_dependency_cycles_cmd(
+    state: State<'_, AppState>,
+) -> Result<Vec<Vec<String>>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    db.detect_cycles().await
+}
+
 #[tauri::command]
 async fn get_all_dependencies_cmd(
     state: State<'_, AppState>,
@@ -756,61 +1434,27 @@ async fn replace_database_files(
 // ===== LSP Commands =====
 
 #[tauri::command]
-async fn lsp_initialize(root_uri: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn lsp_initialize(
+    root_uri: String,
+    request_timeout_ms: Option<u64>,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
     let mut lsp_guard = state.lsp_manager.lock().await;
 
     if lsp_guard.is_none() {
-        let mut manager = TexlabManager::new();
+        let mut manager = TexlabManager::new(app_handle);
+        if let Some(ms) = request_timeout_ms {
+            manager.set_request_timeout(std::time::Duration::from_millis(ms));
+        }
         manager.start().await?;
-
-        let params = serde_json::json!({
-            "processId": std::process::id(),
-            "rootUri": root_uri,
-            "capabilities": {
-                "textDocument": {
-                    "completion": {
-                        "completionItem": {
-                            "snippetSupport": true,
-                            "documentationFormat": ["markdown", "plaintext"]
-                        }
-                    },
-                    "hover": {
-                        "contentFormat": ["markdown", "plaintext"]
-                    },
-                    "definition": {
-                        "linkSupport": true
-                    }
-                }
-            }
-        });
-
-        manager.send_request("initialize", params).await?;
-
-        manager
-            .send_notification("initialized", serde_json::json!({}))
-            .await?;
-
-        let config = serde_json::json!({
-            "settings": {
-                "texlab": {
-                    "completion": {
-                        "matcher": "fuzzy-ignore-case"
-                    },
-                    "build": {
-                        "onSave": false
-                    }
-                }
-            }
-        });
-        manager
-            .send_notification("workspace/didChangeConfiguration", config)
-            .await?;
+        manager.initialize(&root_uri).await?;
 
         *lsp_guard = Some(manager);
-        Ok(())
-    } else {
-        Ok(())
+    } else if let (Some(manager), Some(ms)) = (lsp_guard.as_mut(), request_timeout_ms) {
+        manager.set_request_timeout(std::time::Duration::from_millis(ms));
     }
+    Ok(())
 }
 
 #[tauri::command]
@@ -885,14 +1529,106 @@ async fn get_file_tree_cmd(
         all_resources.extend(resources);
     }
 
-    Ok(tree_builder::build_file_tree(all_resources, &roots))
+    Ok(tree_builder::build_file_tree(all_resources, &roots))
+}
+
+#[tauri::command]
+async fn lsp_definition(
+    uri: String,
+    line: u32,
+    character: u32,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let mut lsp_guard = state.lsp_manager.lock().await;
+
+    if let Some(manager) = lsp_guard.as_mut() {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character }
+        });
+
+        manager
+            .send_request("textDocument/definition", params)
+            .await
+    } else {
+        Err("LSP not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn lsp_document_symbol(
+    uri: String,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let mut lsp_guard = state.lsp_manager.lock().await;
+
+    if let Some(manager) = lsp_guard.as_mut() {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri }
+        });
+
+        manager
+            .send_request("textDocument/documentSymbol", params)
+            .await
+    } else {
+        Err("LSP not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn lsp_references(
+    uri: String,
+    line: u32,
+    character: u32,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let mut lsp_guard = state.lsp_manager.lock().await;
+
+    if let Some(manager) = lsp_guard.as_mut() {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character },
+            "context": { "includeDeclaration": true }
+        });
+
+        manager
+            .send_request("textDocument/references", params)
+            .await
+    } else {
+        Err("LSP not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn lsp_formatting(
+    uri: String,
+    tab_size: u32,
+    insert_spaces: bool,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let mut lsp_guard = state.lsp_manager.lock().await;
+
+    if let Some(manager) = lsp_guard.as_mut() {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "options": { "tabSize": tab_size, "insertSpaces": insert_spaces }
+        });
+
+        manager
+            .send_request("textDocument/formatting", params)
+            .await
+    } else {
+        Err("LSP not initialized".to_string())
+    }
 }
 
+/// `range` is an LSP `Range` (`{ start: { line, character }, end: { line, character } }`)
 #[tauri::command]
-async fn lsp_definition(
+async fn lsp_range_formatting(
     uri: String,
-    line: u32,
-    character: u32,
+    range: serde_json::Value,
+    tab_size: u32,
+    insert_spaces: bool,
     state: State<'_, AppState>,
 ) -> Result<serde_json::Value, String> {
     let mut lsp_guard = state.lsp_manager.lock().await;
@@ -900,11 +1636,12 @@ async fn lsp_definition(
     if let Some(manager) = lsp_guard.as_mut() {
         let params = serde_json::json!({
             "textDocument": { "uri": uri },
-            "position": { "line": line, "character": character }
+            "range": range,
+            "options": { "tabSize": tab_size, "insertSpaces": insert_spaces }
         });
 
         manager
-            .send_request("textDocument/definition", params)
+            .send_request("textDocument/rangeFormatting", params)
             .await
     } else {
         Err("LSP not initialized".to_string())
@@ -967,6 +1704,21 @@ async fn lsp_did_change(
     }
 }
 
+/// Current diagnostics for a document; live updates arrive as `lsp-diagnostics` events
+#[tauri::command]
+async fn lsp_diagnostics(
+    uri: String,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let lsp_guard = state.lsp_manager.lock().await;
+
+    if let Some(manager) = lsp_guard.as_ref() {
+        Ok(manager.diagnostics(&uri))
+    } else {
+        Err("LSP not initialized".to_string())
+    }
+}
+
 // ============================================================================
 // Typed Metadata Commands (sqlx-based)
 // ============================================================================
@@ -3135,6 +3887,20 @@ async fn save_typed_metadata_cmd(
     Ok(())
 }
 
+#[tauri::command]
+async fn delete_typed_metadata_cmd(
+    state: State<'_, AppState>,
+    resource_id: String,
+    resource_type: String,
+) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    manager
+        .delete_typed_metadata(&resource_id, &resource_type)
+        .await
+}
+
 #[tauri::command]
 async fn load_typed_metadata_cmd(
     state: State<'_, AppState>,
@@ -3897,22 +4663,33 @@ pub fn run() {
             app.manage(VectorStoreState(std::sync::Arc::new(
                 tokio::sync::Mutex::new(vector_store),
             )));
+            // Prefer the CTAN package list from the last refresh over the embedded one
+            commands::ctan::load_cached_db(app.handle());
+
             // Initialize Agent State
             app.manage(agent::GlobalAgent(std::sync::Arc::new(
                 tokio::sync::Mutex::new(None),
             )));
+            app.manage(tools::ToolApprovals::default());
 
             let data_dir_str = data_dir.to_string_lossy().to_string();
             println!("Initializing Global DB at: {}", data_dir_str);
 
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                match DatabaseManager::new(&data_dir_str).await {
+                match DatabaseManager::new(&data_dir_str, database::manager::DEFAULT_POOL_SIZE)
+                    .await
+                {
                     Ok(manager) => {
                         let state = app_handle.state::<AppState>();
                         let mut db_guard = state.db_manager.lock().await;
                         *db_guard = Some(manager);
+                        drop(db_guard);
                         println!("Global database initialized successfully.");
+
+                        if let Err(e) = watch_resource_dirs(&app_handle).await {
+                            eprintln!("Failed to watch resource files: {}", e);
+                        }
                     }
                     Err(e) => {
                         eprintln!("Failed to initialize global database: {}", e);
@@ -3927,46 +4704,91 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .manage(Mutex::new(watcher::GitWatcher::new()))
+        .manage(Mutex::new(watcher::ResourceWatcher::new()))
+        .manage(ImportState::default())
         .invoke_handler(tauri::generate_handler![
             git_watch_repo_cmd,
             git_unwatch_repo_cmd,
+            watch_resources_cmd,
+            unwatch_resources_cmd,
             git_read_gitignore_cmd,
             git_write_gitignore_cmd,
             open_project,
             get_db_path,
             compile_tex,
+            compile_tex_with_log_cmd,
+            compile_tex_streaming_cmd,
+            compile_document_cmd,
+            parse_latex_log_cmd,
             run_synctex_command,
+            synctex_forward_cmd,
+            synctex_inverse_cmd,
             run_texcount_command,
             compile_resource_cmd,
             get_system_fonts,
+            list_tables_cmd,
             get_table_data_cmd,
+            run_query_cmd,
             update_cell_cmd,
+            update_row_cmd,
+            delete_row_cmd,
+            export_table_cmd,
             vectors::store_embeddings,
             vectors::search_similar,
             vectors::build_index_cmd, // New Command
+            vectors::clear_index_cmd,
+            vectors::remove_embeddings_cmd,
             // Agent Commands
             agent::start_agent_cmd,
             agent::stop_agent_cmd,
+            save_conversation_cmd,
+            load_conversation_cmd,
+            list_conversations_cmd,
+            delete_conversation_cmd,
+            list_models_cmd,
+            test_provider_cmd,
+            tools::approve_tool_cmd,
+            tools::deny_tool_cmd,
             // New Commands
             get_collections_cmd,
+            get_collections_with_counts_cmd,
             create_collection_cmd,
             get_resources_by_collection_cmd,
             get_resources_by_collections_cmd, // Batch version for performance
+            search_fts_cmd,
             import_folder_cmd,
+            cancel_import_cmd,
             delete_collection_cmd,
+            rename_collection_cmd,
+            move_resource_cmd,
+            read_resource_cmd,
+            move_resources_cmd,
             delete_resource_cmd,
+            find_duplicates_cmd,
+            merge_duplicates_cmd,
             create_resource_cmd,
             create_folder_cmd,
             import_file_cmd,
             reveal_path_cmd,
             link_resources_cmd,
             get_linked_resources_cmd,
+            get_dependents_cmd,
+            detect_dependency_cycles_cmd,
+            get_resources_by_tag_cmd,
+            get_all_tags_cmd,
+            vacuum_database_cmd,
+            backup_database_cmd,
             get_all_dependencies_cmd,
             // LSP Commands
             lsp_initialize,
             lsp_completion,
             lsp_hover,
             lsp_definition,
+            lsp_diagnostics,
+            lsp_document_symbol,
+            lsp_references,
+            lsp_formatting,
+            lsp_range_formatting,
             lsp_did_open,
             lsp_did_change,
             lsp_shutdown,
@@ -4024,6 +4846,7 @@ pub fn run() {
             // Typed Metadata CRUD Commands (sqlx-based)
             save_typed_metadata_cmd,
             load_typed_metadata_cmd,
+            delete_typed_metadata_cmd,
             // New Lookup Commands
             get_package_topics_cmd,
             get_macro_command_types_cmd,
@@ -4035,6 +4858,9 @@ pub fn run() {
             commands::ctan::get_packages,
             commands::ctan::get_all_topics,
             commands::ctan::get_package_by_id,
+            commands::ctan::install_package,
+            commands::ctan::is_package_installed,
+            commands::ctan::refresh_ctan_db,
             // Preamble Types CRUD
             get_preamble_types_cmd,
             create_preamble_type_cmd,
@@ -4044,35 +4870,51 @@ pub fn run() {
             replace_database_files,
             // Local History Commands
             save_history_snapshot_cmd,
+            autosnapshot_on_save,
             get_file_history_cmd,
+            get_history_timeline_cmd,
             get_snapshot_content_cmd,
             restore_snapshot_cmd,
+            restore_snapshot_to_file_cmd,
             diff_snapshots_cmd,
             diff_with_current_cmd,
             delete_snapshot_cmd,
             cleanup_file_history_cmd,
+            cleanup_snapshots_older_than_cmd,
+            cleanup_all_snapshots_cmd,
             // Git Integration Commands
             git_detect_repo_cmd,
+            git_ahead_behind_cmd,
             git_status_cmd,
             git_stage_file_cmd,
             git_stage_all_cmd,
             git_unstage_file_cmd,
+            git_index_diff_cmd,
+            git_stage_hunk_cmd,
+            git_unstage_hunk_cmd,
             git_commit_cmd,
+            git_validate_commit_message_cmd,
             git_log_cmd,
+            git_search_log_cmd,
             git_file_diff_cmd,
             git_file_at_commit_cmd,
             git_discard_changes_cmd,
             git_init_repo_cmd,
             git_get_structured_diff_cmd,
+            git_get_commit_diff_cmd,
             git_get_head_content_cmd,
             git_list_branches_cmd,
             git_create_branch_cmd,
             git_switch_branch_cmd,
             git_delete_branch_cmd,
             git_list_remotes_cmd,
+            git_add_remote_cmd,
+            git_remove_remote_cmd,
+            git_set_remote_url_cmd,
             git_fetch_remote_cmd,
             git_push_remote_cmd,
             git_pull_remote_cmd,
+            git_clone_repo_cmd,
             // Stash Commands
             git_list_stashes_cmd,
             git_create_stash_cmd,
@@ -4085,17 +4927,21 @@ pub fn run() {
             // Checkout & Cherry-pick
             git_checkout_commit_cmd,
             git_cherry_pick_cmd,
+            git_reset_cmd,
             // Blame, Tags, Revert
             git_blame_cmd,
             git_list_tags_cmd,
             git_create_tag_cmd,
             git_delete_tag_cmd,
+            git_checkout_tag_cmd,
             git_revert_commit_cmd,
             // Conflict Detection & Side-by-side Diff
             git_has_conflicts_cmd,
             git_get_conflict_files_cmd,
             git_get_blob_content_cmd,
             git_mark_conflict_resolved_cmd,
+            git_get_conflicts_cmd,
+            git_resolve_conflict_cmd,
             git_get_side_by_side_diff_cmd,
             // Advanced Branch Ops
             git_merge_branch_cmd,
@@ -4216,6 +5062,38 @@ async fn save_history_snapshot_cmd(
     .await
 }
 
+/// Called by the editor on every save; debounced and pruned in `history::autosnapshot`
+#[tauri::command]
+async fn autosnapshot_on_save(
+    file_path: String,
+    content: String,
+    keep_count: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    history::autosnapshot(
+        &manager.pool,
+        &file_path,
+        &content,
+        keep_count.unwrap_or(history::DEFAULT_KEEP_SNAPSHOTS),
+    )
+    .await
+}
+
+#[tauri::command]
+async fn get_history_timeline_cmd(
+    file_path: String,
+    limit: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<history::TimelineEntry>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    history::get_history_timeline(&manager.pool, &file_path, limit).await
+}
+
 #[tauri::command]
 async fn get_file_history_cmd(
     file_path: String,
@@ -4251,6 +5129,18 @@ async fn restore_snapshot_cmd(
     history::get_restore_content(&manager.pool, &snapshot_id).await
 }
 
+/// Writes the snapshot back to disk, snapshotting the current content first
+#[tauri::command]
+async fn restore_snapshot_to_file_cmd(
+    snapshot_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    history::restore_snapshot(&manager.pool, &snapshot_id).await
+}
+
 #[tauri::command]
 async fn diff_snapshots_cmd(
     old_id: String,
@@ -4291,6 +5181,87 @@ async fn cleanup_file_history_cmd(
     history::cleanup_old_snapshots(&manager.pool, &file_path, keep_count).await
 }
 
+#[tauri::command]
+async fn cleanup_snapshots_older_than_cmd(
+    file_path: String,
+    days: i64,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    history::cleanup_snapshots_older_than(&manager.pool, &file_path, days).await
+}
+
+#[tauri::command]
+async fn cleanup_all_snapshots_cmd(
+    keep_count: i32,
+    keep_days: i64,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    history::cleanup_all_snapshots(&manager.pool, keep_count, keep_days).await
+}
+
+// ============================================================================
+// Agent Conversation Commands
+// ============================================================================
+
+/// Save the current agent session, tool calls and outputs included. Pass the `id` of a
+/// loaded conversation to update it in place.
+#[tauri::command]
+async fn save_conversation_cmd(
+    id: Option<String>,
+    title: Option<String>,
+    agent: State<'_, agent::GlobalAgent>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let messages = {
+        let agent_guard = agent.0.lock().await;
+        let session = agent_guard.as_ref().ok_or("No agent session to save")?;
+        session.messages.clone()
+    };
+
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    conversations::save_conversation(&manager.pool, id.as_deref(), title.as_deref(), &messages)
+        .await
+}
+
+/// Messages of a saved conversation; pass them as `chat_history` to `start_agent_cmd`
+/// to resume it
+#[tauri::command]
+async fn load_conversation_cmd(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<agent::AgentMessage>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    conversations::load_conversation(&manager.pool, &id).await
+}
+
+#[tauri::command]
+async fn list_conversations_cmd(
+    state: State<'_, AppState>,
+) -> Result<Vec<conversations::ConversationSummary>, String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    conversations::list_conversations(&manager.pool).await
+}
+
+#[tauri::command]
+async fn delete_conversation_cmd(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let db_guard = state.db_manager.lock().await;
+    let manager = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    conversations::delete_conversation(&manager.pool, &id).await
+}
+
 // ============================================================================
 // Git Integration Commands
 // ============================================================================
@@ -4300,6 +5271,11 @@ fn git_detect_repo_cmd(path: String) -> Result<Option<git::GitRepoInfo>, String>
     git::detect_repo(&path)
 }
 
+#[tauri::command]
+fn git_ahead_behind_cmd(repo_path: String) -> Result<(usize, usize), String> {
+    git::get_ahead_behind(&repo_path)
+}
+
 #[tauri::command]
 fn git_status_cmd(repo_path: String) -> Result<Vec<git::GitFileStatus>, String> {
     git::get_status(&repo_path)
@@ -4310,6 +5286,37 @@ fn git_stage_file_cmd(repo_path: String, file_path: String) -> Result<(), String
     git::stage_file(&repo_path, &file_path)
 }
 
+#[tauri::command]
+fn git_index_diff_cmd(
+    repo_path: String,
+    file_path: String,
+    staged: bool,
+) -> Result<git::StructuredDiff, String> {
+    git::get_index_diff(&repo_path, &file_path, staged)
+}
+
+/// Stage a hunk of the unstaged diff; `lines` optionally narrows it to some of its lines
+#[tauri::command]
+fn git_stage_hunk_cmd(
+    repo_path: String,
+    file_path: String,
+    hunk_header: String,
+    lines: Vec<git::DiffLine>,
+) -> Result<(), String> {
+    git::stage_hunk(&repo_path, &file_path, &hunk_header, lines)
+}
+
+/// Unstage a hunk of the staged diff; `lines` optionally narrows it to some of its lines
+#[tauri::command]
+fn git_unstage_hunk_cmd(
+    repo_path: String,
+    file_path: String,
+    hunk_header: String,
+    lines: Vec<git::DiffLine>,
+) -> Result<(), String> {
+    git::unstage_hunk(&repo_path, &file_path, &hunk_header, lines)
+}
+
 #[tauri::command]
 fn git_stage_all_cmd(repo_path: String) -> Result<(), String> {
     git::stage_all(&repo_path)
@@ -4321,18 +5328,46 @@ fn git_unstage_file_cmd(repo_path: String, file_path: String) -> Result<(), Stri
 }
 
 #[tauri::command]
-fn git_commit_cmd(repo_path: String, message: String) -> Result<String, String> {
-    git::commit(&repo_path, &message)
+fn git_commit_cmd(
+    repo_path: String,
+    message: String,
+    style: Option<String>,
+) -> Result<String, String> {
+    git::commit(&repo_path, &message, style.as_deref())
+}
+
+#[tauri::command]
+fn git_validate_commit_message_cmd(message: String, style: String) -> Result<(), String> {
+    git::validate_commit_message(&message, &style)
 }
 
 #[tauri::command]
 fn git_log_cmd(
     repo_path: String,
     limit: Option<i32>,
+    skip: Option<usize>,
     all: Option<bool>,
-) -> Result<Vec<git::GitCommitInfo>, String> {
+    file_path: Option<String>,
+) -> Result<git::GitLogPage, String> {
     let all = all.unwrap_or(false);
-    git::get_log(&repo_path, limit, all)
+    git::get_log(&repo_path, limit, skip, all, file_path.as_deref())
+}
+
+#[tauri::command]
+fn git_search_log_cmd(
+    repo_path: String,
+    query: String,
+    search_messages: Option<bool>,
+    search_authors: Option<bool>,
+    limit: Option<i32>,
+) -> Result<Vec<git::GitCommitInfo>, String> {
+    git::search_log(
+        &repo_path,
+        &query,
+        search_messages.unwrap_or(true),
+        search_authors.unwrap_or(true),
+        limit,
+    )
 }
 
 #[tauri::command]
@@ -4367,6 +5402,16 @@ fn git_get_structured_diff_cmd(
     git::get_structured_diff(&repo_path, &file_path)
 }
 
+#[tauri::command]
+fn git_get_commit_diff_cmd(
+    repo_path: String,
+    old_commit: String,
+    new_commit: String,
+    file_path: Option<String>,
+) -> Result<Vec<git::StructuredDiff>, String> {
+    git::get_commit_diff(&repo_path, &old_commit, &new_commit, file_path.as_deref())
+}
+
 #[tauri::command]
 fn git_get_head_content_cmd(repo_path: String, file_path: String) -> Result<String, String> {
     git::get_head_file_content(&repo_path, &file_path)
@@ -4417,18 +5462,90 @@ fn git_list_remotes_cmd(repo_path: String) -> Result<Vec<git::RemoteInfo>, Strin
 }
 
 #[tauri::command]
-fn git_fetch_remote_cmd(repo_path: String, remote: String) -> Result<(), String> {
-    git::fetch_remote(&repo_path, &remote)
+fn git_add_remote_cmd(repo_path: String, name: String, url: String) -> Result<(), String> {
+    git::add_remote(&repo_path, &name, &url)
+}
+
+#[tauri::command]
+fn git_remove_remote_cmd(repo_path: String, name: String) -> Result<(), String> {
+    git::remove_remote(&repo_path, &name)
+}
+
+#[tauri::command]
+fn git_set_remote_url_cmd(repo_path: String, name: String, url: String) -> Result<(), String> {
+    git::set_remote_url(&repo_path, &name, &url)
+}
+
+// Network operations run off the main thread so progress events reach the UI while they run
+
+#[tauri::command]
+async fn git_fetch_remote_cmd(
+    repo_path: String,
+    remote: String,
+    credentials: Option<git::GitCredentials>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::fetch_remote(&repo_path, &remote, credentials.as_ref(), &app_handle)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn git_push_remote_cmd(
+    repo_path: String,
+    remote: String,
+    branch: String,
+    credentials: Option<git::GitCredentials>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::push_to_remote(
+            &repo_path,
+            &remote,
+            &branch,
+            credentials.as_ref(),
+            &app_handle,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-fn git_push_remote_cmd(repo_path: String, remote: String, branch: String) -> Result<(), String> {
-    git::push_to_remote(&repo_path, &remote, &branch)
+async fn git_pull_remote_cmd(
+    repo_path: String,
+    remote: String,
+    branch: String,
+    credentials: Option<git::GitCredentials>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::pull_from_remote(
+            &repo_path,
+            &remote,
+            &branch,
+            credentials.as_ref(),
+            &app_handle,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-fn git_pull_remote_cmd(repo_path: String, remote: String, branch: String) -> Result<(), String> {
-    git::pull_from_remote(&repo_path, &remote, &branch)
+async fn git_clone_repo_cmd(
+    url: String,
+    dest: String,
+    credentials: Option<git::GitCredentials>,
+    app_handle: tauri::AppHandle,
+) -> Result<git::GitRepoInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::clone_repo(&url, &dest, credentials.as_ref(), &app_handle)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 // ============================================================================
@@ -4479,6 +5596,11 @@ fn git_checkout_commit_cmd(repo_path: String, commit_id: String) -> Result<(), S
     git::checkout_commit(&repo_path, &commit_id)
 }
 
+#[tauri::command]
+fn git_reset_cmd(repo_path: String, commit_id: String, mode: String) -> Result<(), String> {
+    git::reset_to_commit(&repo_path, &commit_id, &mode)
+}
+
 #[tauri::command]
 fn git_cherry_pick_cmd(repo_path: String, commit_id: String) -> Result<String, String> {
     git::cherry_pick(&repo_path, &commit_id)
@@ -4489,8 +5611,8 @@ fn git_cherry_pick_cmd(repo_path: String, commit_id: String) -> Result<String, S
 // ============================================================================
 
 #[tauri::command]
-fn git_blame_cmd(repo_path: String, file_path: String) -> Result<Vec<git::BlameInfo>, String> {
-    git::git_blame(&repo_path, &file_path)
+fn git_blame_cmd(repo_path: String, file_path: String) -> Result<Vec<git::BlameLine>, String> {
+    git::blame_file(&repo_path, &file_path)
 }
 
 #[tauri::command]
@@ -4513,6 +5635,11 @@ fn git_delete_tag_cmd(repo_path: String, name: String) -> Result<(), String> {
     git::delete_tag(&repo_path, &name)
 }
 
+#[tauri::command]
+fn git_checkout_tag_cmd(repo_path: String, name: String) -> Result<(), String> {
+    git::checkout_tag(&repo_path, &name)
+}
+
 #[tauri::command]
 fn git_revert_commit_cmd(repo_path: String, commit_id: String) -> Result<String, String> {
     git::revert_commit(&repo_path, &commit_id)
@@ -4542,6 +5669,20 @@ fn git_mark_conflict_resolved_cmd(repo_path: String, file_path: String) -> Resul
     git::mark_conflict_resolved(&repo_path, &file_path)
 }
 
+#[tauri::command]
+fn git_get_conflicts_cmd(repo_path: String) -> Result<Vec<git::ConflictEntry>, String> {
+    git::get_conflicts(&repo_path)
+}
+
+#[tauri::command]
+fn git_resolve_conflict_cmd(
+    repo_path: String,
+    file_path: String,
+    resolved_content: String,
+) -> Result<(), String> {
+    git::resolve_conflict(&repo_path, &file_path, &resolved_content)
+}
+
 #[tauri::command]
 fn git_get_side_by_side_diff_cmd(
     repo_path: String,
@@ -4574,9 +5715,14 @@ async fn git_watch_repo_cmd(
 #[tauri::command]
 async fn git_unwatch_repo_cmd(
     watcher: State<'_, Mutex<watcher::GitWatcher>>,
+    repo_path: Option<String>,
 ) -> Result<(), String> {
     let watcher = watcher.lock().await;
-    watcher.unwatch();
+    // Without a path, stop every watcher
+    match repo_path {
+        Some(path) => watcher.unwatch_path(&path),
+        None => watcher.unwatch(),
+    }
     Ok(())
 }
 
@@ -4589,3 +5735,116 @@ fn git_read_gitignore_cmd(repo_path: String) -> Result<String, String> {
 fn git_write_gitignore_cmd(repo_path: String, content: String) -> Result<(), String> {
     git::write_gitignore(&repo_path, &content)
 }
+
+/// Payload of `resource-changed`
+#[derive(Debug, Clone, serde::Serialize)]
+struct ResourceChangedEvent {
+    id: String,
+    path: String,
+}
+
+/// (Re)start the resource watcher on the directories of every stored resource.
+/// Returns how many directories are watched.
+async fn watch_resource_dirs(app_handle: &tauri::AppHandle) -> Result<usize, String> {
+    let state = app_handle.state::<AppState>();
+    let paths = {
+        let db_guard = state.db_manager.lock().await;
+        let db = db_guard.as_ref().ok_or("Database not initialized")?;
+        db.get_all_resource_paths().await?
+    };
+
+    let dirs: std::collections::BTreeSet<std::path::PathBuf> = paths
+        .iter()
+        .filter_map(|p| std::path::Path::new(p).parent().map(|d| d.to_path_buf()))
+        .filter(|d| d.is_dir())
+        .collect();
+
+    let handle = app_handle.clone();
+    let watcher = app_handle.state::<Mutex<watcher::ResourceWatcher>>();
+    watcher.lock().await.watch(&dirs, move |changed| {
+        let handle = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = sync_changed_resources(&handle, changed).await {
+                eprintln!("Failed to sync changed resources: {}", e);
+            }
+        });
+    })?;
+    Ok(dirs.len())
+}
+
+/// Refresh the stored hash of resources whose content changed on disk, snapshot them,
+/// and emit `resource-changed` for each.
+async fn sync_changed_resources(
+    app_handle: &tauri::AppHandle,
+    paths: Vec<std::path::PathBuf>,
+) -> Result<(), String> {
+    use tauri::Emitter;
+
+    let state = app_handle.state::<AppState>();
+    let db_guard = state.db_manager.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    for path in paths {
+        let path_str = path.to_string_lossy().to_string();
+        // Most changes are build output and editor temp files next to the resources
+        let Some(resource) = db.get_resource_by_path(&path_str).await? else {
+            continue;
+        };
+        // The file may be gone again (editors writing via temp files)
+        let Ok(hash) = history::hash_file(&path) else {
+            continue;
+        };
+        if resource.content_hash.as_deref() == Some(hash.as_str()) {
+            continue;
+        }
+
+        db.update_resource_hash(&resource.id, &hash).await?;
+        // Binary resources (figures) have no text history
+        if let Ok(content) = fs::read_to_string(&path) {
+            history::autosnapshot(
+                &db.pool,
+                &path_str,
+                &content,
+                history::DEFAULT_KEEP_SNAPSHOTS,
+            )
+            .await?;
+        }
+
+        let _ = app_handle.emit(
+            "resource-changed",
+            ResourceChangedEvent {
+                id: resource.id,
+                path: path_str,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Re-scan resource directories, e.g. after resources were added outside a folder import
+#[tauri::command]
+async fn watch_resources_cmd(app_handle: tauri::AppHandle) -> Result<usize, String> {
+    watch_resource_dirs(&app_handle).await
+}
+
+#[tauri::command]
+async fn unwatch_resources_cmd(
+    watcher: State<'_, Mutex<watcher::ResourceWatcher>>,
+) -> Result<(), String> {
+    watcher.lock().await.unwatch();
+    Ok(())
+}
+
+// ============================================================================
+// AI Provider Commands
+// ============================================================================
+
+#[tauri::command]
+async fn list_models_cmd(config: ai::ProviderConfig) -> Result<Vec<String>, String> {
+    ai::list_models(&config).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn test_provider_cmd(config: ai::ProviderConfig) -> Result<ai::ProviderStatus, String> {
+    ai::test_provider(&config).await
+}
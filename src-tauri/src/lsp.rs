@@ -1,8 +1,14 @@
 #![allow(dead_code)]
 
 use serde_json::{json, Value};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, Command};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::oneshot;
 
 /// LSP Request structure
 #[derive(Debug, Clone)]
@@ -20,17 +26,42 @@ pub struct LspResponse {
     pub error: Option<Value>,
 }
 
+/// Πόσο περιμένουμε απάντηση σε ένα request πριν τα παρατήσουμε
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// `initialize` indexes the whole workspace, so it gets more room than editor requests
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Manager για το texlab LSP server process
 pub struct TexlabManager {
     process: Option<Child>,
+    stdin: Option<ChildStdin>,
     request_id: i64,
+    /// Requests waiting for their response; filled in by the reader task
+    pending: PendingRequests,
+    /// Latest `textDocument/publishDiagnostics` params per document URI
+    diagnostics: Arc<StdMutex<HashMap<String, Value>>>,
+    /// Cleared by the reader task when the current process's stdout closes
+    alive: Arc<AtomicBool>,
+    /// Workspace root from `initialize`, kept so a crashed server can be re-initialized
+    root_uri: Option<String>,
+    request_timeout: Duration,
+    app_handle: AppHandle,
 }
 
+type PendingRequests = Arc<StdMutex<HashMap<i64, oneshot::Sender<Value>>>>;
+
 impl TexlabManager {
-    pub fn new() -> Self {
+    pub fn new(app_handle: AppHandle) -> Self {
         Self {
             process: None,
+            stdin: None,
             request_id: 0,
+            pending: Arc::new(StdMutex::new(HashMap::new())),
+            diagnostics: Arc::new(StdMutex::new(HashMap::new())),
+            alive: Arc::new(AtomicBool::new(false)),
+            root_uri: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            app_handle,
         }
     }
 
@@ -44,23 +75,154 @@ impl TexlabManager {
         let texlab_path = crate::texlab_downloader::ensure_texlab().await?;
 
         // Δημιουργία child process για το texlab
-        let child = Command::new(&texlab_path)
+        let mut child = Command::new(&texlab_path)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn()
             .map_err(|e| format!("Failed to start texlab at {:?}: {}", texlab_path, e))?;
 
+        // Read stderr in background to suppress errors
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stderr);
+                let mut line = String::new();
+                while let Ok(n) = reader.read_line(&mut line).await {
+                    if n == 0 {
+                        break;
+                    }
+                    // Suppress stderr output
+                    line.clear();
+                }
+            });
+        }
+
+        // A single reader owns stdout for the server's lifetime, so notifications that
+        // arrive between requests (diagnostics) are not lost
+        // Each process gets its own pending map and liveness flag, so a previous reader
+        // winding down can't touch the new one
+        let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
+        self.pending = Arc::new(StdMutex::new(HashMap::new()));
+        self.alive = Arc::new(AtomicBool::new(true));
+        tokio::spawn(read_messages(
+            BufReader::new(stdout),
+            self.pending.clone(),
+            self.diagnostics.clone(),
+            self.alive.clone(),
+            self.app_handle.clone(),
+        ));
+
+        self.stdin = child.stdin.take();
         self.process = Some(child);
         Ok(())
     }
 
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = timeout;
+    }
+
+    /// Στέλνει το `initialize` handshake και τις ρυθμίσεις του texlab
+    pub async fn initialize(&mut self, root_uri: &str) -> Result<(), String> {
+        self.root_uri = Some(root_uri.to_string());
+        self.handshake(root_uri).await
+    }
+
+    async fn handshake(&mut self, root_uri: &str) -> Result<(), String> {
+        let params = json!({
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "capabilities": {
+                "textDocument": {
+                    "completion": {
+                        "completionItem": {
+                            "snippetSupport": true,
+                            "documentationFormat": ["markdown", "plaintext"]
+                        }
+                    },
+                    "hover": {
+                        "contentFormat": ["markdown", "plaintext"]
+                    },
+                    "definition": {
+                        "linkSupport": true
+                    },
+                    "documentSymbol": {
+                        "hierarchicalDocumentSymbolSupport": true
+                    },
+                    "references": {},
+                    "formatting": {},
+                    "rangeFormatting": {},
+                    "publishDiagnostics": {}
+                }
+            }
+        });
+
+        self.request_with_timeout("initialize", params, INITIALIZE_TIMEOUT)
+            .await?;
+
+        self.notify("initialized", json!({})).await?;
+
+        let config = json!({
+            "settings": {
+                "texlab": {
+                    "completion": {
+                        "matcher": "fuzzy-ignore-case"
+                    },
+                    "build": {
+                        "onSave": false
+                    },
+                    "latexFormatter": "latexindent"
+                }
+            }
+        });
+        self.notify("workspace/didChangeConfiguration", config)
+            .await
+    }
+
+    /// Αν το texlab έχει τερματίσει, true
+    fn has_exited(&mut self) -> bool {
+        if !self.alive.load(Ordering::SeqCst) {
+            return true;
+        }
+        match self.process.as_mut() {
+            Some(child) => !matches!(child.try_wait(), Ok(None)),
+            None => true,
+        }
+    }
+
+    /// Restarts and re-initializes texlab if it has crashed. Only servers that completed
+    /// `initialize` are restarted; emits `lsp-restarted` so open documents can be re-sent.
+    async fn ensure_alive(&mut self) -> Result<(), String> {
+        if !self.has_exited() {
+            return Ok(());
+        }
+        let root_uri = self
+            .root_uri
+            .clone()
+            .ok_or("Texlab server is not running".to_string())?;
+
+        println!("[LSP] texlab exited unexpectedly, restarting");
+        if let Some(mut child) = self.process.take() {
+            let _ = child.kill().await;
+        }
+        self.stdin = None;
+        self.diagnostics.lock().unwrap().clear();
+
+        self.start().await?;
+        self.handshake(&root_uri).await?;
+
+        let _ = self.app_handle.emit("lsp-restarted", &root_uri);
+        Ok(())
+    }
+
     /// Σταματάει το texlab server
     pub async fn stop(&mut self) -> Result<(), String> {
-        if let Some(mut child) = self.process.take() {
+        if self.process.is_some() {
             // Προσπάθεια graceful shutdown με LSP shutdown request
             let _ = self.send_shutdown_request().await;
 
+            let mut child = self.process.take().unwrap();
+            self.stdin = None;
+            self.root_uri = None;
             child
                 .kill()
                 .await
@@ -77,211 +239,137 @@ impl TexlabManager {
         self.request_id
     }
 
-    /// Στέλνει LSP request στο texlab
-    pub async fn send_request(&mut self, method: &str, params: Value) -> Result<Value, String> {
-        if self.process.is_none() {
-            return Err("Texlab server is not running".to_string());
-        }
-
-        let id = self.next_request_id();
-
-        // Δημιουργία JSON-RPC 2.0 request
-        let request = json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "method": method,
-            "params": params
-        });
+    /// Latest diagnostics texlab published for `uri` (empty if none yet)
+    pub fn diagnostics(&self, uri: &str) -> Value {
+        self.diagnostics
+            .lock()
+            .unwrap()
+            .get(uri)
+            .and_then(|params| params.get("diagnostics").cloned())
+            .unwrap_or_else(|| json!([]))
+    }
 
-        let request_str = serde_json::to_string(&request)
-            .map_err(|e| format!("Failed to serialize request: {}", e))?;
+    /// Γράφει ένα JSON-RPC message με Content-Length header
+    async fn write_message(&mut self, message: &Value) -> Result<(), String> {
+        let message_str = serde_json::to_string(message)
+            .map_err(|e| format!("Failed to serialize message: {}", e))?;
 
         // Υπολογισμός Content-Length
-        let content_length = request_str.len();
-        let message = format!("Content-Length: {}\r\n\r\n{}", content_length, request_str);
-
-        // Αποστολή του LSP message
-        let child = self.process.as_mut().unwrap();
+        let framed = format!(
+            "Content-Length: {}\r\n\r\n{}",
+            message_str.len(),
+            message_str
+        );
 
-        // Read stderr in background to suppress errors
-        if let Some(stderr) = child.stderr.take() {
-            tokio::spawn(async move {
-                let mut reader = tokio::io::BufReader::new(stderr);
-                let mut line = String::new();
-                while let Ok(n) =
-                    tokio::io::AsyncBufReadExt::read_line(&mut reader, &mut line).await
-                {
-                    if n == 0 {
-                        break;
-                    }
-                    // Suppress stderr output
-                    line.clear();
-                }
-            });
-        }
-
-        let stdin = child
+        let stdin = self
             .stdin
             .as_mut()
-            .ok_or("Failed to get stdin".to_string())?;
-
+            .ok_or("Texlab server is not running".to_string())?;
         stdin
-            .write_all(message.as_bytes())
+            .write_all(framed.as_bytes())
             .await
-            .map_err(|e| format!("Failed to write request: {}", e))?;
+            .map_err(|e| format!("Failed to write message: {}", e))?;
         stdin
             .flush()
             .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
-
-        // Ανάγνωση απάντησης - LOOP μέχρι να βρούμε το σωστό response
-        let stdout = child
-            .stdout
-            .as_mut()
-            .ok_or("Failed to get stdout".to_string())?;
-
-        let mut reader = BufReader::new(stdout);
-
-        // Loop για να διαβάσουμε πολλαπλά μηνύματα μέχρι να βρούμε το response με το σωστό id
-        loop {
-            let mut header_line = String::new();
-            let mut content_length: usize = 0;
-            let mut found_header = false;
-
-            // Διάβασμα headers μέχρι να βρούμε κενή γραμμή (end of headers)
-            let mut empty_count = 0;
-            loop {
-                header_line.clear();
-                let bytes_read = reader
-                    .read_line(&mut header_line)
-                    .await
-                    .map_err(|e| format!("Failed to read header: {}", e))?;
-
-                // EOF - stream closed
-                if bytes_read == 0 {
-                    return Err("LSP server closed connection unexpectedly".to_string());
-                }
-
-                let trimmed = header_line.trim();
-
-                // Κενή γραμμή σημαίνει τέλος headers (αλλά μόνο αν έχουμε ήδη βρει header)
-                if trimmed.is_empty() {
-                    if found_header {
-                        break; // End of headers section
-                    }
-                    // Skip leading empty lines (before any header) - but not too many
-                    empty_count += 1;
-                    if empty_count > 100 {
-                        return Err("Too many empty lines from LSP server".to_string());
-                    }
-                    continue;
-                }
+            .map_err(|e| format!("Failed to flush: {}", e))
+    }
 
-                found_header = true;
-
-                // Parse Content-Length header (case-insensitive)
-                if trimmed.to_lowercase().starts_with("content-length:") {
-                    content_length = trimmed
-                        .split(':')
-                        .nth(1)
-                        .ok_or("Invalid Content-Length format")?
-                        .trim()
-                        .parse()
-                        .map_err(|e| format!("Failed to parse Content-Length: {}", e))?;
-                }
-                // Αγνοούμε άλλα headers (π.χ. Content-Type)
-            }
+    /// Στέλνει LSP request στο texlab, κάνοντας μία επανεκκίνηση αν έχει crashάρει
+    pub async fn send_request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.ensure_alive().await?;
 
-            if content_length == 0 {
-                return Err("No Content-Length header found".to_string());
+        match self.request(method, params.clone()).await {
+            // Ο server πέθανε ενώ περιμέναμε: restart και μία ακόμα προσπάθεια
+            Err(_) if self.root_uri.is_some() && self.has_exited() => {
+                self.ensure_alive().await?;
+                self.request(method, params).await
             }
+            result => result,
+        }
+    }
 
-            // Διάβασμα του JSON message
-            let mut buffer = vec![0; content_length];
-            tokio::io::AsyncReadExt::read_exact(&mut reader, &mut buffer)
-                .await
-                .map_err(|e| format!("Failed to read message: {}", e))?;
+    /// Στέλνει notification (χωρίς response)
+    pub async fn send_notification(&mut self, method: &str, params: Value) -> Result<(), String> {
+        self.ensure_alive().await?;
+        self.notify(method, params).await
+    }
 
-            let message_str = String::from_utf8(buffer)
-                .map_err(|e| format!("Failed to decode message: {}", e))?;
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.request_with_timeout(method, params, self.request_timeout)
+            .await
+    }
 
-            let message: Value = serde_json::from_str(&message_str)
-                .map_err(|e| format!("Failed to parse message: {}", e))?;
+    async fn request_with_timeout(
+        &mut self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, String> {
+        if self.process.is_none() {
+            return Err("Texlab server is not running".to_string());
+        }
 
-            // Έλεγχος αν είναι notification (δεν έχει id)
-            if message.get("method").is_some() && message.get("id").is_none() {
-                // Συνέχισε να διαβάζεις - αυτό είναι notification, όχι response
-                continue;
-            }
+        let id = self.next_request_id();
 
-            // Έλεγχος αν είναι το response που περιμένουμε
-            if let Some(msg_id) = message.get("id") {
-                if msg_id.as_i64() == Some(id) {
-                    // Βρήκαμε το response!
+        // Register before writing so a fast response can't beat us to the map
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
 
-                    // Έλεγχος για errors
-                    if let Some(error) = message.get("error") {
-                        return Err(format!("LSP Error: {}", error));
-                    }
+        // Δημιουργία JSON-RPC 2.0 request
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+        if let Err(e) = self.write_message(&request).await {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
 
-                    // Επιστροφή του result
-                    let result = message.get("result").cloned().unwrap_or(Value::Null);
-                    return Ok(result);
-                } else {
-                    continue;
-                }
+        // Σε timeout ο server μένει ζωντανός· μια καθυστερημένη απάντηση απλά αγνοείται
+        let message = match tokio::time::timeout(timeout, rx).await {
+            Ok(response) => {
+                response.map_err(|_| "LSP server closed connection unexpectedly".to_string())?
             }
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                return Err(format!(
+                    "LSP request '{}' timed out after {}ms",
+                    method,
+                    timeout.as_millis()
+                ));
+            }
+        };
 
-            // Αν φτάσαμε εδώ, κάτι πήγε στραβά
-            return Err("Received unexpected message format".to_string());
+        // Έλεγχος για errors
+        if let Some(error) = message.get("error") {
+            return Err(format!("LSP Error: {}", error));
         }
-    }
-
-    /// Στέλνει notification (χωρίς response)
-    pub async fn send_notification(&mut self, method: &str, params: Value) -> Result<(), String> {
-        if let Some(ref mut child) = self.process {
-            // Δημιουργία JSON-RPC 2.0 notification (χωρίς id)
-            let notification = json!({
-                "jsonrpc": "2.0",
-                "method": method,
-                "params": params
-            });
-
-            let notification_str = serde_json::to_string(&notification)
-                .map_err(|e| format!("Failed to serialize notification: {}", e))?;
-
-            // Υπολογισμός Content-Length
-            let content_length = notification_str.len();
-            let message = format!(
-                "Content-Length: {}\r\n\r\n{}",
-                content_length, notification_str
-            );
 
-            // Αποστολή του LSP message
-            let stdin = child
-                .stdin
-                .as_mut()
-                .ok_or("Failed to get stdin".to_string())?;
-
-            stdin
-                .write_all(message.as_bytes())
-                .await
-                .map_err(|e| format!("Failed to write notification: {}", e))?;
-            stdin
-                .flush()
-                .await
-                .map_err(|e| format!("Failed to flush: {}", e))?;
+        // Επιστροφή του result
+        Ok(message.get("result").cloned().unwrap_or(Value::Null))
+    }
 
-            Ok(())
-        } else {
-            Err("Texlab server is not running".to_string())
+    async fn notify(&mut self, method: &str, params: Value) -> Result<(), String> {
+        if self.process.is_none() {
+            return Err("Texlab server is not running".to_string());
         }
+
+        // Δημιουργία JSON-RPC 2.0 notification (χωρίς id)
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        });
+        self.write_message(&notification).await
     }
 
     /// Στέλνει shutdown request
     async fn send_shutdown_request(&mut self) -> Result<(), String> {
-        let _ = self.send_request("shutdown", Value::Null).await?;
-        let _ = self.send_notification("exit", Value::Null).await?;
+        let _ = self.request("shutdown", Value::Null).await?;
+        let _ = self.notify("exit", Value::Null).await?;
         Ok(())
     }
 
@@ -294,11 +382,127 @@ impl TexlabManager {
 impl Drop for TexlabManager {
     fn drop(&mut self) {
         // Sync drop - just kill the process
-        if let Some(child) = self.process.take() {
+        // `id()` is None once the child has already been reaped
+        if let Some(pid) = self.process.take().and_then(|child| child.id()) {
             let _ = std::process::Command::new("kill")
                 .arg("-9")
-                .arg(child.id().unwrap().to_string())
+                .arg(pid.to_string())
                 .output();
         }
     }
 }
+
+/// Διαβάζει ένα LSP message (headers + JSON body). `None` σημαίνει EOF.
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>, String> {
+    let mut header_line = String::new();
+    let mut content_length: usize = 0;
+    let mut found_header = false;
+
+    // Διάβασμα headers μέχρι να βρούμε κενή γραμμή (end of headers)
+    let mut empty_count = 0;
+    loop {
+        header_line.clear();
+        let bytes_read = reader
+            .read_line(&mut header_line)
+            .await
+            .map_err(|e| format!("Failed to read header: {}", e))?;
+
+        // EOF - stream closed
+        if bytes_read == 0 {
+            return Ok(None);
+        }
+
+        let trimmed = header_line.trim();
+
+        // Κενή γραμμή σημαίνει τέλος headers (αλλά μόνο αν έχουμε ήδη βρει header)
+        if trimmed.is_empty() {
+            if found_header {
+                break; // End of headers section
+            }
+            // Skip leading empty lines (before any header) - but not too many
+            empty_count += 1;
+            if empty_count > 100 {
+                return Err("Too many empty lines from LSP server".to_string());
+            }
+            continue;
+        }
+
+        found_header = true;
+
+        // Parse Content-Length header (case-insensitive)
+        if trimmed.to_lowercase().starts_with("content-length:") {
+            content_length = trimmed
+                .split(':')
+                .nth(1)
+                .ok_or("Invalid Content-Length format")?
+                .trim()
+                .parse()
+                .map_err(|e| format!("Failed to parse Content-Length: {}", e))?;
+        }
+        // Αγνοούμε άλλα headers (π.χ. Content-Type)
+    }
+
+    if content_length == 0 {
+        return Err("No Content-Length header found".to_string());
+    }
+
+    // Διάβασμα του JSON message
+    let mut buffer = vec![0; content_length];
+    reader
+        .read_exact(&mut buffer)
+        .await
+        .map_err(|e| format!("Failed to read message: {}", e))?;
+
+    serde_json::from_slice(&buffer)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse message: {}", e))
+}
+
+/// Reader task: routes responses to their waiting request and records diagnostics,
+/// forwarding them to the frontend as `lsp-diagnostics`.
+async fn read_messages<R: AsyncBufRead + Unpin>(
+    mut reader: R,
+    pending: PendingRequests,
+    diagnostics: Arc<StdMutex<HashMap<String, Value>>>,
+    alive: Arc<AtomicBool>,
+    app_handle: AppHandle,
+) {
+    loop {
+        let message = match read_message(&mut reader).await {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                println!("[LSP] {}", e);
+                break;
+            }
+        };
+
+        match (message.get("id"), message.get("method")) {
+            // Response σε δικό μας request
+            (Some(id), None) => {
+                let sender = id
+                    .as_i64()
+                    .and_then(|id| pending.lock().unwrap().remove(&id));
+                if let Some(sender) = sender {
+                    let _ = sender.send(message);
+                }
+            }
+            (None, Some(method)) if method == "textDocument/publishDiagnostics" => {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                if let Some(uri) = params.get("uri").and_then(|u| u.as_str()) {
+                    diagnostics
+                        .lock()
+                        .unwrap()
+                        .insert(uri.to_string(), params.clone());
+                }
+                let _ = app_handle.emit("lsp-diagnostics", params);
+            }
+            // Άλλα notifications και server→client requests αγνοούνται
+            _ => {}
+        }
+    }
+
+    // Dropping the senders fails any request still waiting
+    alive.store(false, Ordering::SeqCst);
+    pending.lock().unwrap().clear();
+}
@@ -57,6 +57,17 @@ pub struct ReplaceResult {
 pub fn search_in_files(
     query: &SearchQuery,
     resources: Vec<Resource>,
+) -> Result<SearchResult, String> {
+    search_with_context(query, resources, (2, 2), query.max_results)
+}
+
+/// Like `search_in_files`, with `context` = (lines before, lines after) around each match
+/// and at most `max_per_file` matches taken from any single file
+pub fn search_with_context(
+    query: &SearchQuery,
+    resources: Vec<Resource>,
+    context: (usize, usize),
+    max_per_file: usize,
 ) -> Result<SearchResult, String> {
     let start_time = Instant::now();
 
@@ -82,7 +93,10 @@ pub fn search_in_files(
     // Collect all matches from all files, then flatten and limit
     let mut all_matches: Vec<SearchMatch> = filtered_resources
         .par_iter()
-        .map(|resource| search_single_file(&resource.path, &resource.id, query).unwrap_or_default())
+        .map(|resource| {
+            search_single_file(&resource.path, &resource.id, query, context, max_per_file)
+                .unwrap_or_default()
+        })
         .flatten()
         .collect();
 
@@ -103,6 +117,8 @@ fn search_single_file(
     file_path: &str,
     resource_id: &str,
     query: &SearchQuery,
+    (before, after): (usize, usize),
+    max_matches: usize,
 ) -> Result<Vec<SearchMatch>, String> {
     let file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
     let reader = BufReader::new(file);
@@ -144,22 +160,9 @@ fn search_single_file(
             println!("Found match at line {}: '{}'", line_idx + 1, line_content);
             println!("Match positions: start={}, end={}", mat.start(), mat.end());
 
-            // Get context lines (2 before and 2 after)
-            let context_before: Vec<String> = if line_idx >= 2 {
-                lines[line_idx - 2..line_idx].to_vec()
-            } else if line_idx >= 1 {
-                lines[line_idx - 1..line_idx].to_vec()
-            } else {
-                Vec::new()
-            };
-
-            let context_after: Vec<String> = if line_idx + 3 <= lines.len() {
-                lines[line_idx + 1..line_idx + 3].to_vec()
-            } else if line_idx + 2 <= lines.len() {
-                lines[line_idx + 1..line_idx + 2].to_vec()
-            } else {
-                Vec::new()
-            };
+            let context_before = lines[line_idx.saturating_sub(before)..line_idx].to_vec();
+            let context_after =
+                lines[line_idx + 1..(line_idx + 1 + after).min(lines.len())].to_vec();
 
             matches.push(SearchMatch {
                 resource_id: resource_id.to_string(),
@@ -173,8 +176,8 @@ fn search_single_file(
                 context_after,
             });
 
-            // Stop if we've reached max results
-            if matches.len() >= query.max_results {
+            // Stop if we've reached the per-file cap
+            if matches.len() >= max_matches {
                 break;
             }
         }
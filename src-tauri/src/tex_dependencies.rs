@@ -0,0 +1,254 @@
+// TeX Dependency Extraction
+// Finds \usepackage, \input, \include and \documentclass references in imported
+// sources and records them in the `dependencies` table.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use tokio_util::sync::CancellationToken;
+
+use crate::database::DatabaseManager;
+
+/// A reference from one TeX source to another file
+#[derive(Debug, Clone, PartialEq)]
+pub struct TexReference {
+    /// "package", "input" or "class"
+    pub relation_type: &'static str,
+    pub target: String,
+}
+
+/// An imported .tex/.sty/.cls resource whose references should be linked
+pub struct ImportedSource {
+    pub id: String,
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// Collect the references in `content`, ignoring commented-out lines
+pub fn extract_references(content: &str) -> Vec<TexReference> {
+    static COMMAND: OnceLock<Regex> = OnceLock::new();
+    let command = COMMAND.get_or_init(|| {
+        Regex::new(
+            r"\\(usepackage|RequirePackage|documentclass|LoadClass|input|include)\s*(?:\[[^\]]*\])?\s*\{([^}]*)\}",
+        )
+        .unwrap()
+    });
+
+    let mut references = Vec::new();
+    for line in content.lines() {
+        for caps in command.captures_iter(strip_comment(line)) {
+            let relation_type = match &caps[1] {
+                "usepackage" | "RequirePackage" => "package",
+                "documentclass" | "LoadClass" => "class",
+                _ => "input",
+            };
+            // \usepackage{a,b} loads several packages at once
+            for target in caps[2].split(',') {
+                let target = target.trim();
+                if !target.is_empty() {
+                    references.push(TexReference {
+                        relation_type,
+                        target: target.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    references
+}
+
+/// Drop everything from the first unescaped `%`
+fn strip_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'%' {
+            let backslashes = bytes[..i].iter().rev().take_while(|&&c| c == b'\\').count();
+            if backslashes % 2 == 0 {
+                return &line[..i];
+            }
+        }
+    }
+    line
+}
+
+/// Resolve a reference to an imported resource id. Inputs are looked up next to the
+/// source file and then at the import root (TeX resolves them from the main file's
+/// directory); packages and classes are matched by `<name>.sty` / `<name>.cls`.
+fn resolve<'a>(
+    reference: &TexReference,
+    source: &Path,
+    root: &Path,
+    by_path: &'a HashMap<PathBuf, String>,
+    by_name: &'a HashMap<String, String>,
+) -> Option<&'a String> {
+    match reference.relation_type {
+        "package" => by_name.get(&format!("{}.sty", reference.target)),
+        "class" => by_name.get(&format!("{}.cls", reference.target)),
+        _ => {
+            let target = Path::new(&reference.target);
+            let with_ext = target
+                .extension()
+                .is_none()
+                .then(|| target.with_extension("tex"));
+            let bases = [source.parent().unwrap_or(root), root];
+            bases.iter().find_map(|base| {
+                std::iter::once(target)
+                    .chain(with_ext.as_deref())
+                    .find_map(|t| by_path.get(&normalize(&base.join(t))))
+            })
+        }
+    }
+}
+
+/// Lexically resolve `.` and `..` so joined paths compare equal to walked ones
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Record dependencies between the given sources. References to files that weren't
+/// imported (e.g. packages from the TeX distribution) are skipped. Stops at the next
+/// source once `cancel` fires. All links are checked and stored in one batch. Returns
+/// how many links were added plus a message for each one that was rejected.
+pub async fn link_sources(
+    db: &DatabaseManager,
+    root: &Path,
+    sources: &[ImportedSource],
+    cancel: &CancellationToken,
+) -> (usize, Vec<String>) {
+    let by_path: HashMap<PathBuf, String> = sources
+        .iter()
+        .map(|s| (normalize(&s.path), s.id.clone()))
+        .collect();
+    let by_name: HashMap<String, String> = sources
+        .iter()
+        .filter_map(|s| {
+            let name = s.path.file_name()?.to_string_lossy().to_string();
+            Some((name, s.id.clone()))
+        })
+        .collect();
+
+    let mut links = Vec::new();
+    // The source each link came from, for error messages
+    let mut origins = Vec::new();
+    for source in sources {
+        if cancel.is_cancelled() {
+            break;
+        }
+        for reference in extract_references(&source.content) {
+            let Some(target_id) = resolve(&reference, &source.path, root, &by_path, &by_name)
+            else {
+                continue;
+            };
+            if *target_id == source.id {
+                continue;
+            }
+            links.push((
+                source.id.clone(),
+                target_id.clone(),
+                reference.relation_type,
+            ));
+            origins.push(&source.path);
+        }
+    }
+
+    let outcomes = match db.add_dependencies(&links).await {
+        Ok(outcomes) => outcomes,
+        Err(e) => return (0, vec![e]),
+    };
+    let mut linked = 0;
+    let mut errors = Vec::new();
+    for (outcome, origin) in outcomes.into_iter().zip(origins) {
+        match outcome {
+            Ok(()) => linked += 1,
+            Err(e) => errors.push(format!("{}: {}", origin.display(), e)),
+        }
+    }
+
+    (linked, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_packages_inputs_and_class() {
+        let refs = extract_references(
+            "\\documentclass[11pt]{article}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amsmath, mystyle}\n\\input{chapters/intro}\n",
+        );
+        let pairs: Vec<(&str, &str)> = refs
+            .iter()
+            .map(|r| (r.relation_type, r.target.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("class", "article"),
+                ("package", "inputenc"),
+                ("package", "amsmath"),
+                ("package", "mystyle"),
+                ("input", "chapters/intro"),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_comments_but_not_escaped_percent() {
+        let refs = extract_references("% \\input{old}\n50\\% done \\include{ch1} % \\input{x}\n");
+        assert_eq!(
+            refs,
+            vec![TexReference {
+                relation_type: "input",
+                target: "ch1".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn resolves_inputs_relative_to_source_then_root() {
+        let root = Path::new("/proj");
+        let by_path: HashMap<PathBuf, String> = [
+            (
+                PathBuf::from("/proj/chapters/intro.tex"),
+                "intro".to_string(),
+            ),
+            (PathBuf::from("/proj/macros.tex"), "macros".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let by_name = HashMap::new();
+        let source = Path::new("/proj/chapters/main.tex");
+
+        let input = |target: &str| TexReference {
+            relation_type: "input",
+            target: target.to_string(),
+        };
+        assert_eq!(
+            resolve(&input("intro"), source, root, &by_path, &by_name),
+            Some(&"intro".to_string())
+        );
+        assert_eq!(
+            resolve(&input("../macros.tex"), source, root, &by_path, &by_name),
+            Some(&"macros".to_string())
+        );
+        assert_eq!(
+            resolve(&input("macros"), source, root, &by_path, &by_name),
+            Some(&"macros".to_string())
+        );
+        assert_eq!(
+            resolve(&input("missing"), source, root, &by_path, &by_name),
+            None
+        );
+    }
+}
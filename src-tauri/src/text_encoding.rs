@@ -0,0 +1,94 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+
+/// Share of NUL bytes in one byte lane above which BOM-less text is taken to be UTF-16
+const UTF16_NUL_RATIO: f32 = 0.3;
+
+/// Decode a text file to UTF-8 without losing characters.
+///
+/// A BOM wins; then valid UTF-8; then BOM-less UTF-16 (recognized by its NUL bytes);
+/// anything else goes through charset detection, which covers legacy 8-bit encodings such as
+/// Windows-1253 (Greek) and Latin-1. Files that still contain NUL bytes are rejected as binary.
+pub fn decode_text(bytes: &[u8]) -> Result<String, String> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return decode_with(encoding, &bytes[bom_len..]);
+    }
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        if !text.contains('\0') {
+            return Ok(text.to_string());
+        }
+    }
+
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return decode_with(encoding, bytes);
+    }
+
+    if bytes.contains(&0) {
+        return Err("File appears to be binary, not text".to_string());
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    decode_with(detector.guess(None, true), bytes)
+}
+
+fn decode_with(encoding: &'static Encoding, bytes: &[u8]) -> Result<String, String> {
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        return Err(format!("File is not valid {}", encoding.name()));
+    }
+    Ok(text.into_owned())
+}
+
+/// ASCII-heavy UTF-16 has a NUL in every other byte: odd positions for LE, even for BE
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let pairs = (bytes.len() / 2) as f32;
+    let even_nuls = bytes.iter().step_by(2).filter(|b| **b == 0).count() as f32;
+    let odd_nuls = bytes.iter().skip(1).step_by(2).filter(|b| **b == 0).count() as f32;
+
+    if odd_nuls / pairs > UTF16_NUL_RATIO && even_nuls == 0.0 {
+        Some(UTF_16LE)
+    } else if even_nuls / pairs > UTF16_NUL_RATIO && odd_nuls == 0.0 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_utf8_and_bom() {
+        assert_eq!(decode_text("Καλημέρα".as_bytes()).unwrap(), "Καλημέρα");
+        assert_eq!(decode_text(b"\xEF\xBB\xBFabc").unwrap(), "abc");
+    }
+
+    #[test]
+    fn test_decode_legacy_greek() {
+        let (bytes, _, _) =
+            encoding_rs::WINDOWS_1253.encode("\\section{Εισαγωγή} Η συνάρτηση είναι συνεχής.");
+        assert_eq!(
+            decode_text(&bytes).unwrap(),
+            "\\section{Εισαγωγή} Η συνάρτηση είναι συνεχής."
+        );
+    }
+
+    #[test]
+    fn test_decode_utf16_without_bom() {
+        let bytes: Vec<u8> = "\\begin{document}"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        assert_eq!(decode_text(&bytes).unwrap(), "\\begin{document}");
+    }
+
+    #[test]
+    fn test_reject_binary() {
+        assert!(decode_text(b"%PDF-1.5\n\x00\x01\x02\xff\x00\x10").is_err());
+    }
+}
@@ -1,13 +1,13 @@
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
+use std::collections::HashMap;
 use std::fs;
 use std::future::Future;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 use std::pin::Pin;
-use std::process::Command;
 use std::sync::Arc;
-use tauri::Emitter;
-use tokio::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio::sync::{oneshot, Mutex};
 
 use crate::database::DatabaseManager;
 use crate::vectors::VectorStoreState;
@@ -25,12 +25,88 @@ pub trait Tool: Send + Sync {
         &self,
         args: serde_json::Value,
     ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>>;
+    /// Read-only tools may run concurrently with each other within one agent turn
+    fn parallelizable(&self) -> bool {
+        false
+    }
+}
+
+/// Directory the file tools are confined to; `None` when no project or collection is open
+pub struct Workspace {
+    root: Option<PathBuf>,
+}
+
+impl Workspace {
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, String> {
+        let root = root
+            .as_ref()
+            .canonicalize()
+            .map_err(|e| format!("Invalid workspace root {}: {}", root.as_ref().display(), e))?;
+        Ok(Self { root: Some(root) })
+    }
+
+    /// No workspace: every file tool call is refused
+    pub fn none() -> Self {
+        Self { root: None }
+    }
+
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Resolves `path` (relative paths are taken from the root) and rejects anything
+    /// that ends up outside the workspace once symlinks and `..` are resolved.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let root = self.root.as_ref().ok_or(
+            "No project or collection is open. Ask the user to open one to work with files.",
+        )?;
+        let requested = root.join(path);
+
+        // The target may not exist yet (write_file), so canonicalize the deepest
+        // existing ancestor and re-append the remaining components.
+        let mut existing = requested.as_path();
+        let mut rest = Vec::new();
+        while !existing.exists() {
+            match (existing.file_name(), existing.parent()) {
+                (Some(name), Some(parent)) => {
+                    rest.push(name.to_owned());
+                    existing = parent;
+                }
+                _ => return Err(format!("Invalid path: {}", path)),
+            }
+        }
+        let mut resolved = existing
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", path, e))?;
+        for name in rest.iter().rev() {
+            resolved.push(name);
+        }
+
+        // `..` after a missing component can't be canonicalized
+        if resolved.components().any(|c| c == Component::ParentDir) {
+            return Err(format!("Invalid path: {}", path));
+        }
+        if !resolved.starts_with(root) {
+            return Err(format!(
+                "Access denied: {} is outside the workspace {}",
+                path,
+                root.display()
+            ));
+        }
+        Ok(resolved)
+    }
 }
 
 // --- Tool Implementations ---
 
-pub struct ListFilesTool;
+pub struct ListFilesTool {
+    pub workspace: Arc<Workspace>,
+}
 impl Tool for ListFilesTool {
+    fn parallelizable(&self) -> bool {
+        true
+    }
+
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "list_files".to_string(),
@@ -41,7 +117,7 @@ impl Tool for ListFilesTool {
                 "properties": {
                     "path": {
                         "type": "string",
-                        "description": "Absolute path to directory to list"
+                        "description": "Directory to list, absolute or relative to the workspace root"
                     }
                 },
                 "required": ["path"]
@@ -55,13 +131,13 @@ impl Tool for ListFilesTool {
     ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
         Box::pin(async move {
             let path_str = args["path"].as_str().ok_or("Missing path argument")?;
-            let path = Path::new(path_str);
+            let path = self.workspace.resolve(path_str)?;
 
             if !path.exists() {
                 return Err(format!("Path does not exist: {}", path_str));
             }
 
-            let entries = fs::read_dir(path).map_err(|e| e.to_string())?;
+            let entries = fs::read_dir(&path).map_err(|e| e.to_string())?;
             let mut files = Vec::new();
 
             for entry in entries {
@@ -83,8 +159,14 @@ impl Tool for ListFilesTool {
     }
 }
 
-pub struct ReadFileTool;
+pub struct ReadFileTool {
+    pub workspace: Arc<Workspace>,
+}
 impl Tool for ReadFileTool {
+    fn parallelizable(&self) -> bool {
+        true
+    }
+
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "read_file".to_string(),
@@ -94,7 +176,7 @@ impl Tool for ReadFileTool {
                 "properties": {
                     "path": {
                         "type": "string",
-                        "description": "Absolute path to file to read"
+                        "description": "File to read, absolute or relative to the workspace root"
                     }
                 },
                 "required": ["path"]
@@ -108,13 +190,9 @@ impl Tool for ReadFileTool {
     ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
         Box::pin(async move {
             let path_str = args["path"].as_str().ok_or("Missing path argument")?;
+            let path = self.workspace.resolve(path_str)?;
 
-            // Basic security check (very simple for now)
-            if path_str.contains("..") {
-                return Err("Access denied: Traversal paths not allowed".to_string());
-            }
-
-            match fs::read_to_string(path_str) {
+            match fs::read_to_string(&path) {
                 Ok(content) => {
                     // Truncate if too huge
                     if content.len() > 10000 {
@@ -130,7 +208,9 @@ impl Tool for ReadFileTool {
     }
 }
 
-pub struct WriteFileTool;
+pub struct WriteFileTool {
+    pub workspace: Arc<Workspace>,
+}
 impl Tool for WriteFileTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
@@ -142,7 +222,7 @@ impl Tool for WriteFileTool {
                 "properties": {
                     "path": {
                         "type": "string",
-                        "description": "Absolute path to file"
+                        "description": "File path, absolute or relative to the workspace root"
                     },
                     "content": {
                         "type": "string",
@@ -162,13 +242,9 @@ impl Tool for WriteFileTool {
             let path_str = args["path"].as_str().ok_or("Missing path argument")?;
             let content = args["content"].as_str().ok_or("Missing content argument")?;
 
-            // Basic security checks
-            if path_str.contains("..") {
-                return Err("Access denied: Traversal paths not allowed".to_string());
-            }
+            let path = self.workspace.resolve(path_str)?;
 
             // Ensure parent directory exists
-            let path = Path::new(path_str);
             if let Some(parent) = path.parent() {
                 if !parent.exists() {
                     fs::create_dir_all(parent)
@@ -176,13 +252,383 @@ impl Tool for WriteFileTool {
                 }
             }
 
-            fs::write(path_str, content).map_err(|e| format!("Failed to write file: {}", e))?;
+            fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))?;
             Ok(format!("Successfully wrote to {}", path_str))
         })
     }
 }
 
-pub struct RunTerminalTool;
+pub struct MoveFileTool {
+    pub workspace: Arc<Workspace>,
+    pub db_manager: Arc<Mutex<Option<DatabaseManager>>>,
+    pub vector_store: Arc<VectorStoreState>,
+    pub app_handle: tauri::AppHandle,
+}
+impl Tool for MoveFileTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "move_file".to_string(),
+            description:
+                "Move or rename a file or directory. Library resources pointing at it are updated."
+                    .to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "from": {
+                        "type": "string",
+                        "description": "Current path, absolute or relative to the workspace root"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "New path, absolute or relative to the workspace root"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace an existing file at the destination (default false)"
+                    }
+                },
+                "required": ["from", "to"]
+            }),
+        }
+    }
+
+    fn execute(
+        &self,
+        args: serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
+        Box::pin(async move {
+            let from = self
+                .workspace
+                .resolve(args["from"].as_str().ok_or("Missing from argument")?)?;
+            let to = self
+                .workspace
+                .resolve(args["to"].as_str().ok_or("Missing to argument")?)?;
+            let overwrite = args["overwrite"].as_bool().unwrap_or(false);
+
+            if !from.exists() {
+                return Err(format!("Path does not exist: {}", from.display()));
+            }
+            if self.workspace.root() == Some(from.as_path()) {
+                return Err("Refusing to move the workspace root".to_string());
+            }
+            if to.exists() {
+                if !overwrite {
+                    return Err(format!(
+                        "Destination already exists: {}. Pass overwrite: true to replace it.",
+                        to.display()
+                    ));
+                }
+                if to.is_dir() {
+                    return Err(format!(
+                        "Destination is a directory and won't be overwritten: {}",
+                        to.display()
+                    ));
+                }
+            }
+
+            if let Some(parent) = to.parent() {
+                if !parent.exists() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create parent dir: {}", e))?;
+                }
+            }
+
+            fs::rename(&from, &to).map_err(|e| format!("Failed to move file: {}", e))?;
+
+            let from_str = from.to_string_lossy();
+            let to_str = to.to_string_lossy();
+            let guard = self.db_manager.lock().await;
+            let moves = match guard.as_ref() {
+                Some(db) => db.move_resource_paths(&from_str, &to_str).await?,
+                None => Vec::new(),
+            };
+
+            // Embeddings are keyed by path: both ends are stale until the next index build
+            let mut stale = vec![from_str.to_string(), to_str.to_string()];
+            for (old_path, new_path) in &moves {
+                stale.push(old_path.clone());
+                stale.push(new_path.clone());
+            }
+            crate::vectors::remove_embeddings(&self.vector_store, &self.app_handle, &stale).await?;
+
+            Ok(format!(
+                "Moved {} to {} ({} library resources updated)",
+                from_str,
+                to_str,
+                moves.len()
+            ))
+        })
+    }
+}
+
+pub struct DeleteFileTool {
+    pub workspace: Arc<Workspace>,
+    pub db_manager: Arc<Mutex<Option<DatabaseManager>>>,
+    pub vector_store: Arc<VectorStoreState>,
+    pub app_handle: tauri::AppHandle,
+}
+impl Tool for DeleteFileTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "delete_file".to_string(),
+            description: "Delete a file or directory. Moves it to the system trash unless trash is false. Library resources pointing at it are removed."
+                .to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to delete, absolute or relative to the workspace root"
+                    },
+                    "trash": {
+                        "type": "boolean",
+                        "description": "Move to the trash instead of deleting permanently (default true)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    fn execute(
+        &self,
+        args: serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
+        Box::pin(async move {
+            let path = self
+                .workspace
+                .resolve(args["path"].as_str().ok_or("Missing path argument")?)?;
+            let use_trash = args["trash"].as_bool().unwrap_or(true);
+
+            if !path.exists() {
+                return Err(format!("Path does not exist: {}", path.display()));
+            }
+            if self.workspace.root() == Some(path.as_path()) {
+                return Err("Refusing to delete the workspace root".to_string());
+            }
+
+            if use_trash {
+                trash::delete(&path).map_err(|e| format!("Failed to move to trash: {}", e))?;
+            } else if path.is_dir() {
+                fs::remove_dir_all(&path).map_err(|e| format!("Failed to delete: {}", e))?;
+            } else {
+                fs::remove_file(&path).map_err(|e| format!("Failed to delete: {}", e))?;
+            }
+
+            let path_str = path.to_string_lossy();
+            let guard = self.db_manager.lock().await;
+            let mut removed = match guard.as_ref() {
+                Some(db) => db.delete_resources_at_path(&path_str).await?,
+                None => Vec::new(),
+            };
+            let count = removed.len();
+
+            removed.push(path_str.to_string());
+            crate::vectors::remove_embeddings(&self.vector_store, &self.app_handle, &removed)
+                .await?;
+
+            Ok(format!(
+                "{} {} ({} library resources removed)",
+                if use_trash { "Trashed" } else { "Deleted" },
+                path_str,
+                count
+            ))
+        })
+    }
+}
+
+/// Diagnostics of each severity reported back to the model per compile
+const MAX_COMPILE_MESSAGES: usize = 20;
+/// Kill runaway compilations so the agent loop doesn't hang
+const COMPILE_TIMEOUT_SECS: u64 = 120;
+
+pub struct CompileTool {
+    pub workspace: Arc<Workspace>,
+    /// The user's shell-escape setting; every compile that would use it is approved first
+    pub shell_escape: crate::compiler::ShellEscape,
+    pub app_handle: tauri::AppHandle,
+}
+impl Tool for CompileTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "compile".to_string(),
+            description: "Compile a LaTeX document and get back the errors and warnings from its log. Use this after editing to check your changes."
+                .to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Main .tex file, absolute or relative to the workspace root"
+                    },
+                    "engine": {
+                        "type": "string",
+                        "description": "pdflatex, xelatex, lualatex or latexmk (default pdflatex)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    fn execute(
+        &self,
+        args: serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
+        Box::pin(async move {
+            let path = self
+                .workspace
+                .resolve(args["path"].as_str().ok_or("Missing path argument")?)?;
+            let path_str = path.to_string_lossy().to_string();
+            let engine = args["engine"].as_str().unwrap_or("pdflatex").to_string();
+
+            // \write18 lets the document run arbitrary commands, so the user decides each time
+            let mut shell_escape = self.shell_escape;
+            let mut note = "";
+            if shell_escape != crate::compiler::ShellEscape::Disabled {
+                let command = format!("{} -shell-escape {}", engine, path_str);
+                let cwd = path.parent().unwrap_or(&path);
+                if !request_approval(&self.app_handle, &command, &cwd.to_string_lossy()).await {
+                    shell_escape = crate::compiler::ShellEscape::Disabled;
+                    note = "The user denied shell escape; compiled without it.\n";
+                }
+            }
+
+            let result = tokio::task::spawn_blocking(move || {
+                crate::compiler::compile_with_result(
+                    &path_str,
+                    &engine,
+                    vec!["-interaction=nonstopmode".to_string()],
+                    "",
+                    Some(COMPILE_TIMEOUT_SECS),
+                    shell_escape,
+                    true,
+                )
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+
+            Ok(format!("{}{}", note, summarize_compile(&result)))
+        })
+    }
+}
+
+/// Condense a compile result into status plus file:line diagnostics instead of raw stdout
+fn summarize_compile(result: &crate::compiler::CompileResult) -> String {
+    let messages = result.messages.as_deref().unwrap_or_default();
+    let mut out = format!(
+        "Compilation {} (exit code {:?}).\n",
+        if result.success {
+            "succeeded"
+        } else {
+            "failed"
+        },
+        result.exit_code
+    );
+
+    for severity in ["error", "warning"] {
+        let matching: Vec<_> = messages.iter().filter(|m| m.severity == severity).collect();
+        if matching.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n{} {}(s):\n", matching.len(), severity));
+        for msg in matching.iter().take(MAX_COMPILE_MESSAGES) {
+            let location = match (&msg.file, msg.line) {
+                (Some(file), Some(line)) => format!("{}:{}: ", file, line),
+                (Some(file), None) => format!("{}: ", file),
+                (None, Some(line)) => format!("line {}: ", line),
+                (None, None) => String::new(),
+            };
+            out.push_str(&format!("- {}{}\n", location, msg.message));
+        }
+        if matching.len() > MAX_COMPILE_MESSAGES {
+            out.push_str(&format!(
+                "... ({} more)\n",
+                matching.len() - MAX_COMPILE_MESSAGES
+            ));
+        }
+    }
+
+    // Nothing parsed from the log (e.g. a fatal error before it was written): fall back to
+    // the tail of the engine output
+    if !result.success && messages.is_empty() {
+        let output = format!("{}{}", result.stdout, result.stderr);
+        let output = output.trim();
+        let tail_start = output.len().saturating_sub(2000);
+        let tail_start = (tail_start..output.len())
+            .find(|&i| output.is_char_boundary(i))
+            .unwrap_or(output.len());
+        out.push_str(&format!("\n{}\n", &output[tail_start..]));
+    }
+
+    out
+}
+
+/// Terminal commands awaiting a user decision, keyed by request id
+#[derive(Default)]
+pub struct ToolApprovals(pub std::sync::Mutex<HashMap<String, oneshot::Sender<bool>>>);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    pub command: String,
+    pub cwd: String,
+}
+
+#[tauri::command]
+pub fn approve_tool_cmd(
+    id: String,
+    approvals: tauri::State<'_, ToolApprovals>,
+) -> Result<(), String> {
+    resolve_approval(&approvals, &id, true)
+}
+
+#[tauri::command]
+pub fn deny_tool_cmd(id: String, approvals: tauri::State<'_, ToolApprovals>) -> Result<(), String> {
+    resolve_approval(&approvals, &id, false)
+}
+
+fn resolve_approval(approvals: &ToolApprovals, id: &str, approved: bool) -> Result<(), String> {
+    let sender = approvals
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(id)
+        .ok_or_else(|| format!("No pending approval with id {}", id))?;
+    // The tool may have been cancelled in the meantime; nothing to resolve then
+    let _ = sender.send(approved);
+    Ok(())
+}
+
+/// Ask the UI to approve a command; a dropped request counts as denied
+async fn request_approval(app_handle: &tauri::AppHandle, command: &str, cwd: &str) -> bool {
+    let request = ApprovalRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        command: command.to_string(),
+        cwd: cwd.to_string(),
+    };
+
+    let (tx, rx) = oneshot::channel();
+    match app_handle.state::<ToolApprovals>().0.lock() {
+        Ok(mut pending) => pending.insert(request.id.clone(), tx),
+        Err(_) => return false,
+    };
+
+    if app_handle.emit("agent-approval-request", &request).is_err() {
+        if let Ok(mut pending) = app_handle.state::<ToolApprovals>().0.lock() {
+            pending.remove(&request.id);
+        }
+        return false;
+    }
+
+    rx.await.unwrap_or(false)
+}
+
+pub struct RunTerminalTool {
+    /// When set, each command waits for `approve_tool_cmd`/`deny_tool_cmd` before running
+    pub approval: Option<tauri::AppHandle>,
+}
+
 impl Tool for RunTerminalTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
@@ -213,18 +659,27 @@ impl Tool for RunTerminalTool {
             let cmd_str = args["command"].as_str().ok_or("Missing command argument")?;
             let cwd_str = args["cwd"].as_str().unwrap_or(".");
 
-            let output = if cfg!(target_os = "windows") {
-                Command::new("cmd")
-                    .args(["/C", cmd_str])
-                    .current_dir(cwd_str)
-                    .output()
+            if let Some(app_handle) = &self.approval {
+                if !request_approval(app_handle, cmd_str, cwd_str).await {
+                    return Ok("User denied command execution".to_string());
+                }
+            }
+
+            let mut command = if cfg!(target_os = "windows") {
+                let mut command = tokio::process::Command::new("cmd");
+                command.args(["/C", cmd_str]);
+                command
             } else {
-                Command::new("sh")
-                    .arg("-c")
-                    .arg(cmd_str)
-                    .current_dir(cwd_str)
-                    .output()
+                let mut command = tokio::process::Command::new("sh");
+                command.arg("-c").arg(cmd_str);
+                command
             };
+            // Stopping the agent drops this future; don't leave the command running
+            let output = command
+                .current_dir(cwd_str)
+                .kill_on_drop(true)
+                .output()
+                .await;
 
             match output {
                 Ok(out) => {
@@ -243,6 +698,10 @@ pub struct DatabaseSearchTool {
 }
 
 impl Tool for DatabaseSearchTool {
+    fn parallelizable(&self) -> bool {
+        true
+    }
+
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "search_files".to_string(),
@@ -268,7 +727,7 @@ impl Tool for DatabaseSearchTool {
         Box::pin(async move {
             let query_text = args["query"].as_str().ok_or("Missing query")?.to_string();
             let use_regex = args["regex"].as_bool().unwrap_or(false);
-            let extensions = args["extensions"]
+            let extensions: Vec<String> = args["extensions"]
                 .as_array()
                 .map(|arr| {
                     arr.iter()
@@ -279,6 +738,30 @@ impl Tool for DatabaseSearchTool {
 
             let guard = db_manager.lock().await;
             if let Some(db) = guard.as_ref() {
+                // Plain-text queries go to the FTS index first; walk the files only if it has nothing
+                if !use_regex {
+                    let hits: Vec<_> = db
+                        .search_fts(&query_text, 20)
+                        .await
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|h| {
+                            extensions.is_empty()
+                                || extensions
+                                    .iter()
+                                    .any(|ext| h.path.ends_with(&format!(".{}", ext)))
+                        })
+                        .collect();
+
+                    if !hits.is_empty() {
+                        let mut out = format!("Found {} indexed files:\n", hits.len());
+                        for hit in hits {
+                            out.push_str(&format!("{} - {}\n", hit.path, hit.snippet.trim()));
+                        }
+                        return Ok(out);
+                    }
+                }
+
                 let collections = db.get_collections().await.unwrap_or_default();
                 let col_names: Vec<String> = collections.into_iter().map(|c| c.name).collect();
                 let resources = db
@@ -321,9 +804,104 @@ impl Tool for DatabaseSearchTool {
     }
 }
 
+/// Like `search_files`, but shows numbered context lines around each hit
+pub struct GrepTool {
+    pub db_manager: Arc<Mutex<Option<DatabaseManager>>>,
+}
+
+impl Tool for GrepTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "grep".to_string(),
+            description: "Search files in the database and show surrounding lines for each match, prefixed with line numbers.".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to search for" },
+                    "regex": { "type": "boolean", "description": "Use regex? Default false" },
+                    "extensions": { "type": "array", "items": { "type": "string" }, "description": "File extensions (tex, bib...)" },
+                    "context_before": { "type": "integer", "description": "Lines shown before each match (default 2)" },
+                    "context_after": { "type": "integer", "description": "Lines shown after each match (default 2)" },
+                    "max_matches_per_file": { "type": "integer", "description": "Maximum matches reported per file (default 5)" }
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    fn execute(
+        &self,
+        args: serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
+        let db_manager = self.db_manager.clone();
+        Box::pin(async move {
+            let query_text = args["query"].as_str().ok_or("Missing query")?.to_string();
+            let use_regex = args["regex"].as_bool().unwrap_or(false);
+            let extensions: Vec<String> = args["extensions"]
+                .as_array()
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let before = args["context_before"].as_u64().unwrap_or(2) as usize;
+            let after = args["context_after"].as_u64().unwrap_or(2) as usize;
+            let max_per_file = args["max_matches_per_file"].as_u64().unwrap_or(5).max(1) as usize;
+
+            let guard = db_manager.lock().await;
+            let db = guard.as_ref().ok_or("Database not initialized")?;
+            let collections = db.get_collections().await.unwrap_or_default();
+            let col_names: Vec<String> = collections.into_iter().map(|c| c.name).collect();
+            let resources = db
+                .get_resources_by_collections(&col_names)
+                .await
+                .unwrap_or_default();
+
+            let search_query = crate::search::SearchQuery {
+                text: query_text,
+                case_sensitive: false,
+                use_regex,
+                file_types: extensions,
+                max_results: 50,
+            };
+            let res = crate::search::search_with_context(
+                &search_query,
+                resources,
+                (before, after),
+                max_per_file,
+            )?;
+
+            let mut out = format!(
+                "Found {} matches in {} files:\n",
+                res.matches.len(),
+                res.total_files_searched
+            );
+            let mut current_file = "";
+            for m in &res.matches {
+                if m.file_path != current_file {
+                    current_file = &m.file_path;
+                    out.push_str(&format!("\n{}\n", m.file_path));
+                }
+                let first = m.line_number - m.context_before.len();
+                for (i, line) in m.context_before.iter().enumerate() {
+                    out.push_str(&format!("{}- {}\n", first + i, line));
+                }
+                out.push_str(&format!("{}: {}\n", m.line_number, m.line_content));
+                for (i, line) in m.context_after.iter().enumerate() {
+                    out.push_str(&format!("{}- {}\n", m.line_number + 1 + i, line));
+                }
+                out.push_str("--\n");
+            }
+            Ok(out)
+        })
+    }
+}
+
 // Registry to hold tools
 pub struct ToolRegistry {
     tools: std::collections::HashMap<String, Box<dyn Tool>>,
+    workspace_root: Option<PathBuf>,
 }
 
 impl ToolRegistry {
@@ -332,22 +910,61 @@ impl ToolRegistry {
         db_manager: Arc<Mutex<Option<DatabaseManager>>>,
         vector_store: Arc<VectorStoreState>,
         config: crate::ai::ProviderConfig,
+        require_approval: bool,
+        shell_escape: crate::compiler::ShellEscape,
+        workspace: Workspace,
     ) -> Self {
+        let workspace = Arc::new(workspace);
         let mut registry = ToolRegistry {
             tools: std::collections::HashMap::new(),
+            workspace_root: workspace.root().map(Path::to_path_buf),
         };
 
-        registry.register(Box::new(ListFilesTool));
-        registry.register(Box::new(ReadFileTool));
-        registry.register(Box::new(WriteFileTool));
+        registry.register(Box::new(ListFilesTool {
+            workspace: workspace.clone(),
+        }));
+        registry.register(Box::new(ReadFileTool {
+            workspace: workspace.clone(),
+        }));
+        registry.register(Box::new(WriteFileTool {
+            workspace: workspace.clone(),
+        }));
+        registry.register(Box::new(MoveFileTool {
+            workspace: workspace.clone(),
+            db_manager: db_manager.clone(),
+            vector_store: vector_store.clone(),
+            app_handle: app_handle.clone(),
+        }));
+        registry.register(Box::new(DeleteFileTool {
+            workspace: workspace.clone(),
+            db_manager: db_manager.clone(),
+            vector_store: vector_store.clone(),
+            app_handle: app_handle.clone(),
+        }));
+        registry.register(Box::new(CompileTool {
+            workspace: workspace.clone(),
+            shell_escape,
+            app_handle: app_handle.clone(),
+        }));
         registry.register(Box::new(ProposeEditTool {
             app_handle: app_handle.clone(),
+            workspace: workspace.clone(),
+        }));
+        registry.register(Box::new(ApplyPatchTool {
+            app_handle: app_handle.clone(),
+            workspace,
+        }));
+        // Opt-in: ask the user before running terminal commands
+        registry.register(Box::new(RunTerminalTool {
+            approval: require_approval.then(|| app_handle.clone()),
         }));
-        registry.register(Box::new(RunTerminalTool));
         registry.register(Box::new(FindResourceTool {
             db_manager: db_manager.clone(),
         }));
 
+        registry.register(Box::new(GrepTool {
+            db_manager: db_manager.clone(),
+        }));
         registry.register(Box::new(DatabaseSearchTool { db_manager }));
 
         // For Semantic Search, we need to handle the embedding generation.
@@ -370,6 +987,10 @@ impl ToolRegistry {
     pub fn get_definitions(&self) -> Vec<ToolDefinition> {
         self.tools.values().map(|t| t.definition()).collect()
     }
+
+    pub fn workspace_root(&self) -> Option<&Path> {
+        self.workspace_root.as_deref()
+    }
 }
 
 // ... (ProposeEditTool impl was NOT missing, but the Struct was?)
@@ -380,6 +1001,7 @@ impl ToolRegistry {
 
 pub struct ProposeEditTool {
     pub app_handle: tauri::AppHandle,
+    pub workspace: Arc<Workspace>,
 }
 
 pub struct FindResourceTool {
@@ -387,6 +1009,10 @@ pub struct FindResourceTool {
 }
 
 impl Tool for FindResourceTool {
+    fn parallelizable(&self) -> bool {
+        true
+    }
+
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "find_resource".to_string(),
@@ -469,7 +1095,7 @@ impl Tool for ProposeEditTool {
                 "properties": {
                     "path": {
                         "type": "string",
-                        "description": "Absolute path to file"
+                        "description": "File path, absolute or relative to the workspace root"
                     },
                     "new_content": {
                         "type": "string",
@@ -487,16 +1113,16 @@ impl Tool for ProposeEditTool {
     ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
         let app = self.app_handle.clone();
         Box::pin(async move {
-            let path_str = args["path"]
-                .as_str()
-                .ok_or("Missing path argument")?
-                .to_string();
+            let path = self
+                .workspace
+                .resolve(args["path"].as_str().ok_or("Missing path argument")?)?;
+            let path_str = path.to_string_lossy().to_string();
             let new_content = args["new_content"]
                 .as_str()
                 .ok_or("Missing new_content argument")?
                 .to_string();
 
-            if !Path::new(&path_str).exists() {
+            if !path.exists() {
                 return Err(format!(
                     "File does not exist: {}. Use write_file to create new files.",
                     path_str
@@ -521,6 +1147,166 @@ impl Tool for ProposeEditTool {
     }
 }
 
+/// Proposes an edit from targeted replacements or a unified diff instead of a full rewrite
+pub struct ApplyPatchTool {
+    pub app_handle: tauri::AppHandle,
+    pub workspace: Arc<Workspace>,
+}
+
+impl Tool for ApplyPatchTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "apply_patch".to_string(),
+            description: "Propose changes to an existing file without resending all of it. Pass either `edits` (each old_str must appear exactly once) or a unified `diff`. The user will review the result in a Diff View.".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "File path, absolute or relative to the workspace root"
+                    },
+                    "edits": {
+                        "type": "array",
+                        "description": "Replacements applied in order",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "old_str": { "type": "string", "description": "Exact text to replace, with enough context to be unique" },
+                                "new_str": { "type": "string", "description": "Replacement text" }
+                            },
+                            "required": ["old_str", "new_str"]
+                        }
+                    },
+                    "diff": {
+                        "type": "string",
+                        "description": "Unified diff against the current file"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    fn execute(
+        &self,
+        args: serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + '_>> {
+        let app = self.app_handle.clone();
+        Box::pin(async move {
+            let path = self
+                .workspace
+                .resolve(args["path"].as_str().ok_or("Missing path argument")?)?;
+            let path_str = path.to_string_lossy().to_string();
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path_str, e))?;
+
+            let edits = if let Some(edits) = args["edits"].as_array() {
+                edits
+                    .iter()
+                    .map(|e| {
+                        let old_str = e["old_str"].as_str().ok_or("Edit missing old_str")?;
+                        let new_str = e["new_str"].as_str().ok_or("Edit missing new_str")?;
+                        Ok((old_str.to_string(), new_str.to_string()))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?
+            } else if let Some(diff) = args["diff"].as_str() {
+                diff_to_replacements(diff)?
+            } else {
+                return Err("Provide either edits or diff".to_string());
+            };
+
+            let new_content = apply_replacements(&content, &edits)?;
+
+            // Same event as propose_edit, so the diff review UI handles both
+            app.emit(
+                "agent-proposal",
+                serde_json::json!({
+                    "path": path_str,
+                    "new_content": new_content
+                }),
+            )
+            .map_err(|e| e.to_string())?;
+
+            Ok(format!(
+                "Proposed {} change(s) for {}. User is reviewing changes...",
+                edits.len(),
+                path_str
+            ))
+        })
+    }
+}
+
+/// Apply replacements in order; each `old` must occur exactly once in the text at that point
+fn apply_replacements(content: &str, edits: &[(String, String)]) -> Result<String, String> {
+    let mut result = content.to_string();
+    for (i, (old, new)) in edits.iter().enumerate() {
+        if old.is_empty() {
+            return Err(format!("Edit {}: old_str is empty", i + 1));
+        }
+        match result.matches(old.as_str()).count() {
+            0 => return Err(format!("Edit {}: old_str not found:\n{}", i + 1, old)),
+            1 => result = result.replacen(old.as_str(), new, 1),
+            n => {
+                return Err(format!(
+                    "Edit {}: old_str matches {} times; include more surrounding context:\n{}",
+                    i + 1,
+                    n,
+                    old
+                ))
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Turn each hunk of a unified diff into an (old, new) replacement of its lines
+fn diff_to_replacements(diff: &str) -> Result<Vec<(String, String)>, String> {
+    let mut replacements = Vec::new();
+    let mut hunk: Option<(Vec<&str>, Vec<&str>)> = None;
+
+    for line in diff.lines() {
+        if line.starts_with("@@") {
+            if let Some((old, new)) = hunk.take() {
+                replacements.push(hunk_replacement(&old, &new)?);
+            }
+            hunk = Some((Vec::new(), Vec::new()));
+            continue;
+        }
+        let Some((old, new)) = hunk.as_mut() else {
+            // File headers (---/+++) and anything else before the first hunk
+            continue;
+        };
+        if let Some(rest) = line.strip_prefix('-') {
+            old.push(rest);
+        } else if let Some(rest) = line.strip_prefix('+') {
+            new.push(rest);
+        } else if line.starts_with('\\') {
+            // "\ No newline at end of file"
+        } else {
+            let rest = line.strip_prefix(' ').unwrap_or(line);
+            old.push(rest);
+            new.push(rest);
+        }
+    }
+    if let Some((old, new)) = hunk {
+        replacements.push(hunk_replacement(&old, &new)?);
+    }
+
+    if replacements.is_empty() {
+        return Err("Diff contains no hunks".to_string());
+    }
+    Ok(replacements)
+}
+
+fn hunk_replacement(old: &[&str], new: &[&str]) -> Result<(String, String), String> {
+    if old.is_empty() {
+        return Err(
+            "Hunk has no context lines to anchor it; include surrounding lines".to_string(),
+        );
+    }
+    Ok((old.join("\n"), new.join("\n")))
+}
+
 // Actual Impl of SemanticSearchTool with Config
 struct SemanticSearchTool {
     vector_store: Arc<VectorStoreState>,
@@ -528,6 +1314,10 @@ struct SemanticSearchTool {
 }
 
 impl Tool for SemanticSearchTool {
+    fn parallelizable(&self) -> bool {
+        true
+    }
+
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "semantic_search".to_string(),
@@ -536,7 +1326,9 @@ impl Tool for SemanticSearchTool {
                 "type": "object",
                 "properties": {
                     "query": { "type": "string", "description": "Concept or text to find" },
-                    "k": { "type": "integer", "description": "Number of results (default 5)" }
+                    "k": { "type": "integer", "description": "Number of results (default 5)" },
+                    "collection": { "type": "string", "description": "Only search resources in this collection" },
+                    "type": { "type": "string", "description": "Only search resources of this type (e.g. file, table, figure)" }
                 },
                 "required": ["query"]
             }),
@@ -558,13 +1350,29 @@ impl Tool for SemanticSearchTool {
                 .await
                 .map_err(|e| e.to_string())?;
 
+            let filter: HashMap<String, String> = ["collection", "type"]
+                .into_iter()
+                .filter_map(|key| Some((key.to_string(), args[key].as_str()?.to_string())))
+                .collect();
+
             let store = store_state.0.lock().await;
-            let results = store.search(&embedding, top_k);
+            let results = store.search_filtered(&embedding, top_k, &filter);
 
             let mut out = String::new();
             out.push_str(&format!("Found {} semantic matches:\n", results.len()));
             for (id, score) in results {
-                out.push_str(&format!("- {} (Score: {:.4})\n", id, score));
+                let meta = store
+                    .vectors
+                    .get(&id)
+                    .and_then(|item| item.metadata.as_ref());
+                let path = meta.and_then(|m| m.get("path")).unwrap_or(&id);
+                match meta.and_then(|m| Some((m.get("start_line")?, m.get("end_line")?))) {
+                    Some((start, end)) => out.push_str(&format!(
+                        "- {} (lines {}-{}, Score: {:.4})\n",
+                        path, start, end, score
+                    )),
+                    None => out.push_str(&format!("- {} (Score: {:.4})\n", path, score)),
+                }
             }
             Ok(out)
         })
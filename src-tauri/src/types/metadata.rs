@@ -240,3 +240,85 @@ impl Default for DocumentMetadata {
         Self::new()
     }
 }
+
+// ============================================================================
+// Storage Layout
+// ============================================================================
+
+/// Tables holding typed metadata for a resource type, as `(table, id column)`.
+/// Junction and history tables come first so the main row is deleted last.
+pub fn typed_metadata_tables(resource_type: &str) -> &'static [(&'static str, &'static str)] {
+    match resource_type {
+        "file" => &[
+            ("resource_file_chapters", "resource_id"),
+            ("resource_file_sections", "resource_id"),
+            ("resource_file_subsections", "resource_id"),
+            ("resource_file_exercise_types", "resource_id"),
+            ("resource_file_packages", "resource_id"),
+            ("resource_file_tags", "resource_id"),
+            ("resource_file_bib_entries", "resource_id"),
+            ("resource_file_solutions", "file_id"),
+            ("resource_file_history", "resource_id"),
+            // Documents that include this file
+            ("resource_document_files", "file_id"),
+            ("resource_files", "resource_id"),
+        ],
+        "document" => &[
+            ("resource_document_chapters", "resource_id"),
+            ("resource_document_sections", "resource_id"),
+            ("resource_document_subsections", "resource_id"),
+            ("resource_document_files", "document_id"),
+            ("resource_document_tags", "resource_id"),
+            ("resource_document_bib_entries", "resource_id"),
+            ("resource_document_history", "resource_id"),
+            ("resource_documents", "resource_id"),
+        ],
+        "table" => &[
+            ("resource_table_packages", "resource_id"),
+            ("resource_table_tags", "resource_id"),
+            ("resource_table_history", "resource_id"),
+            ("resource_tables", "resource_id"),
+        ],
+        "figure" => &[
+            ("resource_figure_packages", "resource_id"),
+            ("resource_figure_tags", "resource_id"),
+            ("resource_figure_history", "resource_id"),
+            ("resource_figures", "resource_id"),
+        ],
+        "command" => &[
+            ("resource_command_packages", "resource_id"),
+            ("resource_command_tags", "resource_id"),
+            ("resource_command_history", "resource_id"),
+            ("resource_commands", "resource_id"),
+        ],
+        "package" => &[
+            ("resource_package_dependencies", "resource_id"),
+            ("resource_package_topics", "resource_id"),
+            ("resource_package_provided_commands", "resource_id"),
+            ("resource_package_tags", "resource_id"),
+            ("resource_package_history", "resource_id"),
+            ("resource_packages", "resource_id"),
+        ],
+        "preamble" => &[
+            ("resource_preamble_command_types", "resource_id"),
+            ("resource_preamble_packages", "resource_id"),
+            ("resource_preamble_provided_commands", "resource_id"),
+            ("resource_preambles", "resource_id"),
+        ],
+        "class" => &[
+            ("resource_class_packages", "resource_id"),
+            ("resource_class_provided_commands", "resource_id"),
+            ("resource_class_tags", "resource_id"),
+            ("resource_class_history", "resource_id"),
+            ("resource_classes", "resource_id"),
+        ],
+        "bibliography" => &[
+            ("resource_bibliography_persons", "resource_id"),
+            ("resource_bibliography_extras", "resource_id"),
+            ("resource_bibliographies", "resource_id"),
+        ],
+        "dtx" => &[("resource_dtx", "resource_id")],
+        "ins" => &[("resource_ins", "resource_id")],
+        _ => &[],
+    }
+}
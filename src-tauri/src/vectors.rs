@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::{Emitter, Manager, State};
 use tokio::sync::Mutex;
@@ -31,10 +31,86 @@ impl VectorStore {
         self.vectors.insert(item.id.clone(), item);
     }
 
+    /// Drop the vector stored under `id_prefix` and every chunk of it (`<id_prefix>#<n>`).
+    /// Returns how many vectors were removed.
+    pub fn remove(&mut self, id_prefix: &str) -> usize {
+        let chunk_prefix = format!("{}#", id_prefix);
+        let before = self.vectors.len();
+        self.vectors
+            .retain(|id, _| id != id_prefix && !id.starts_with(&chunk_prefix));
+        before - self.vectors.len()
+    }
+
+    /// Set `key` to `value` in the metadata of the vector stored under `id_prefix` and
+    /// its chunks. Returns how many vectors changed.
+    pub fn set_metadata(&mut self, id_prefix: &str, key: &str, value: &str) -> usize {
+        let chunk_prefix = format!("{}#", id_prefix);
+        let mut changed = 0;
+        for item in self.vectors.values_mut() {
+            if item.id != id_prefix && !item.id.starts_with(&chunk_prefix) {
+                continue;
+            }
+            let meta = item.metadata.get_or_insert_with(HashMap::new);
+            if meta.get(key).map(String::as_str) != Some(value) {
+                meta.insert(key.to_string(), value.to_string());
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Replace `old` with `new` wherever it is the value of `key`. Returns how many
+    /// vectors changed.
+    pub fn replace_metadata_value(&mut self, key: &str, old: &str, new: &str) -> usize {
+        let mut changed = 0;
+        for meta in self
+            .vectors
+            .values_mut()
+            .filter_map(|item| item.metadata.as_mut())
+        {
+            if let Some(value) = meta.get_mut(key).filter(|value| value.as_str() == old) {
+                *value = new.to_string();
+                changed += 1;
+            }
+        }
+        changed
+    }
+
     pub fn search(&self, query_vector: &[f32], top_k: usize) -> Vec<(String, f32)> {
+        self.search_filtered(query_vector, top_k, &HashMap::new())
+    }
+
+    /// Like `search`, but only scores items whose metadata contains every key/value in `filter`
+    pub fn search_filtered(
+        &self,
+        query_vector: &[f32],
+        top_k: usize,
+        filter: &HashMap<String, String>,
+    ) -> Vec<(String, f32)> {
+        // Vectors from a different embedding model can't be compared with the query
+        let mismatched = self
+            .vectors
+            .values()
+            .filter(|item| item.vector.len() != query_vector.len())
+            .count();
+        if mismatched > 0 {
+            println!(
+                "[VECTORS] Warning: skipping {} of {} vectors whose dimension differs from the query ({}). Clear the index after changing embedding models.",
+                mismatched,
+                self.vectors.len(),
+                query_vector.len()
+            );
+        }
+
         let mut scores: Vec<(String, f32)> = self
             .vectors
             .values()
+            .filter(|item| item.vector.len() == query_vector.len())
+            .filter(|item| {
+                filter.iter().all(|(key, value)| {
+                    item.metadata.as_ref().and_then(|m| m.get(key)) == Some(value)
+                })
+            })
             .map(|item| {
                 let score = cosine_similarity(query_vector, &item.vector);
                 (item.id.clone(), score)
@@ -49,6 +125,9 @@ impl VectorStore {
 }
 
 fn cosine_similarity(v1: &[f32], v2: &[f32]) -> f32 {
+    if v1.len() != v2.len() {
+        return 0.0;
+    }
     let dot_product: f32 = v1.iter().zip(v2.iter()).map(|(a, b)| a * b).sum();
     let magnitude1: f32 = v1.iter().map(|x| x * x).sum::<f32>().sqrt();
     let magnitude2: f32 = v2.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -60,6 +139,57 @@ fn cosine_similarity(v1: &[f32], v2: &[f32]) -> f32 {
     dot_product / (magnitude1 * magnitude2)
 }
 
+/// Roughly 1000 tokens per chunk at ~4 bytes per token
+const CHUNK_BYTES: usize = 4000;
+/// Roughly 100 tokens repeated between neighbouring chunks so boundaries don't lose context
+const CHUNK_OVERLAP_BYTES: usize = 400;
+
+/// Split `content` into overlapping byte ranges, preferring to cut before a sectioning
+/// command, then at a blank line, then at a line break.
+pub fn chunk_text(content: &str) -> Vec<(usize, usize)> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < content.len() {
+        let mut end = floor_char_boundary(content, start + CHUNK_BYTES);
+        if end < content.len() {
+            // Only look at the back half of the window so chunks don't get tiny
+            let min_end = floor_char_boundary(content, start + CHUNK_BYTES / 2);
+            let window = &content[min_end..end];
+            let cut = ["\n\\section", "\n\\subsection", "\n\\chapter", "\n\n", "\n"]
+                .iter()
+                .find_map(|sep| window.rfind(sep));
+            if let Some(pos) = cut {
+                end = min_end + pos + 1;
+            }
+        }
+        chunks.push((start, end));
+
+        if end >= content.len() {
+            break;
+        }
+        // Start the next chunk at a line break inside the overlap when there is one
+        let overlap_start = floor_char_boundary(content, end.saturating_sub(CHUNK_OVERLAP_BYTES));
+        let next = match content[overlap_start..end].find('\n') {
+            Some(pos) => overlap_start + pos + 1,
+            None => overlap_start,
+        };
+        start = if next > start { next } else { end };
+    }
+
+    chunks
+}
+
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    if index >= s.len() {
+        return s.len();
+    }
+    (0..=index)
+        .rev()
+        .find(|&i| s.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
 // Global State
 #[derive(Clone)]
 pub struct VectorStoreState(pub Arc<Mutex<VectorStore>>);
@@ -67,25 +197,116 @@ pub struct VectorStoreState(pub Arc<Mutex<VectorStore>>);
 // App Data Path helper (placeholder, actual path logic handles mostly in main or passed from frontend)
 pub fn get_vectors_path(app_handle: &tauri::AppHandle) -> PathBuf {
     let mut path = app_handle.path().app_data_dir().unwrap();
-    path.push("vectors.json");
+    path.push("vectors.bin");
     path
 }
 
+/// Bumped whenever the binary layout below changes
+const STORE_FORMAT_VERSION: u8 = 1;
+
+/// Metadata lives in a small JSON file next to the vector blob
+fn metadata_path(path: &Path) -> PathBuf {
+    path.with_extension("meta.json")
+}
+
+/// Binary layout (little endian): version byte, item count (u32), then per item
+/// id length (u32), id bytes, dimension (u32) and the raw f32 components.
 pub fn save_store(store: &VectorStore, path: &PathBuf) -> Result<(), String> {
-    let json = serde_json::to_string(store).map_err(|e| e.to_string())?;
-    fs::write(path, json).map_err(|e| e.to_string())?;
+    let dims: usize = store.vectors.values().map(|v| v.vector.len()).sum();
+    let mut buf = Vec::with_capacity(5 + store.vectors.len() * 64 + dims * 4);
+    buf.push(STORE_FORMAT_VERSION);
+    buf.extend_from_slice(&(store.vectors.len() as u32).to_le_bytes());
+
+    let mut metadata = HashMap::new();
+    for item in store.vectors.values() {
+        buf.extend_from_slice(&(item.id.len() as u32).to_le_bytes());
+        buf.extend_from_slice(item.id.as_bytes());
+        buf.extend_from_slice(&(item.vector.len() as u32).to_le_bytes());
+        for x in &item.vector {
+            buf.extend_from_slice(&x.to_le_bytes());
+        }
+        if let Some(meta) = &item.metadata {
+            metadata.insert(item.id.as_str(), meta);
+        }
+    }
+
+    fs::write(path, buf).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(&metadata).map_err(|e| e.to_string())?;
+    fs::write(metadata_path(path), json).map_err(|e| e.to_string())?;
     Ok(())
 }
 
 pub fn load_store(path: &PathBuf) -> Result<VectorStore, String> {
     if !path.exists() {
+        // Stores written before the binary format; re-saved as binary on the next write
+        let legacy = path.with_extension("json");
+        if legacy.exists() {
+            let content = fs::read_to_string(&legacy).map_err(|e| e.to_string())?;
+            return serde_json::from_str(&content).map_err(|e| e.to_string());
+        }
         return Ok(VectorStore::new());
     }
-    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    let store: VectorStore = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    let mut reader = BlobReader {
+        bytes: &bytes,
+        pos: 0,
+    };
+    let version = reader.take(1)?[0];
+    if version != STORE_FORMAT_VERSION {
+        return Err(format!("Unsupported vector store version {}", version));
+    }
+
+    let mut metadata: HashMap<String, HashMap<String, String>> =
+        match fs::read_to_string(metadata_path(path)) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| e.to_string())?,
+            Err(_) => HashMap::new(),
+        };
+
+    let count = reader.u32()?;
+    let mut store = VectorStore::new();
+    for _ in 0..count {
+        let id_len = reader.u32()? as usize;
+        let id = String::from_utf8(reader.take(id_len)?.to_vec()).map_err(|e| e.to_string())?;
+        let dim = reader.u32()? as usize;
+        let vector = reader
+            .take(dim * 4)?
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        let metadata = metadata.remove(&id);
+        store.insert(VectorItem {
+            id,
+            vector,
+            metadata,
+        });
+    }
     Ok(store)
 }
 
+struct BlobReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BlobReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("Vector store file is truncated")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
 // --- Commands ---
 
 #[tauri::command]
@@ -117,69 +338,343 @@ pub async fn search_similar(
     Ok(results.into_iter().map(|(id, _score)| id).collect())
 }
 
+/// Drop the embeddings of `paths` and persist the store if anything changed. Call this
+/// whenever resources are deleted, or their stale embeddings keep surfacing in semantic search.
+pub async fn remove_embeddings(
+    state: &VectorStoreState,
+    app_handle: &tauri::AppHandle,
+    paths: &[String],
+) -> Result<usize, String> {
+    let mut store = state.0.lock().await;
+    let removed: usize = paths.iter().map(|path| store.remove(path)).sum();
+    if removed > 0 {
+        save_store(&store, &get_vectors_path(app_handle))?;
+    }
+    Ok(removed)
+}
+
+/// Record the new collection of moved resources on their embeddings, so semantic search
+/// filtered by collection keeps finding them
+pub async fn set_embeddings_collection(
+    state: &VectorStoreState,
+    app_handle: &tauri::AppHandle,
+    paths: &[String],
+    collection: &str,
+) -> Result<usize, String> {
+    let mut store = state.0.lock().await;
+    let changed: usize = paths
+        .iter()
+        .map(|path| store.set_metadata(path, COLLECTION_KEY, collection))
+        .sum();
+    if changed > 0 {
+        save_store(&store, &get_vectors_path(app_handle))?;
+    }
+    Ok(changed)
+}
+
+/// Follow a collection rename in embedding metadata
+pub async fn rename_embeddings_collection(
+    state: &VectorStoreState,
+    app_handle: &tauri::AppHandle,
+    old_name: &str,
+    new_name: &str,
+) -> Result<usize, String> {
+    let mut store = state.0.lock().await;
+    let changed = store.replace_metadata_value(COLLECTION_KEY, old_name, new_name);
+    if changed > 0 {
+        save_store(&store, &get_vectors_path(app_handle))?;
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+pub async fn remove_embeddings_cmd(
+    path: String,
+    state: State<'_, VectorStoreState>,
+    app_handle: tauri::AppHandle,
+) -> Result<usize, String> {
+    remove_embeddings(&state, &app_handle, &[path]).await
+}
+
+/// Wipe every stored vector, e.g. after switching to an embedding model with a different dimension
+#[tauri::command]
+pub async fn clear_index_cmd(
+    state: State<'_, VectorStoreState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let mut store = state.0.lock().await;
+    store.vectors.clear();
+    save_store(&store, &get_vectors_path(&app_handle))
+}
+
+/// Metadata key holding the hash of the content a vector was embedded from
+const CONTENT_HASH_KEY: &str = "content_hash";
+/// Metadata keys recording which model produced a vector and its dimension
+const MODEL_KEY: &str = "model";
+const DIMENSION_KEY: &str = "dim";
+/// Metadata keys with the library collection and resource type, for filtered search
+const COLLECTION_KEY: &str = "collection";
+const TYPE_KEY: &str = "type";
+
+/// Whether a stored vector was embedded from `content_hash` by `model`, with the
+/// dimension that model currently returns (when known)
+fn is_current(
+    item: &VectorItem,
+    content_hash: &str,
+    model: &str,
+    dimension: Option<usize>,
+) -> bool {
+    let Some(meta) = item.metadata.as_ref() else {
+        return false;
+    };
+    meta.get(CONTENT_HASH_KEY).map(String::as_str) == Some(content_hash)
+        && meta.get(MODEL_KEY).map(String::as_str) == Some(model)
+        && dimension.is_none_or(|dim| dim == item.vector.len())
+}
+
+/// Vector ids for chunks are `<path>#<chunk_index>`
+fn chunk_id(file_path: &str, index: usize) -> String {
+    format!("{}#{}", file_path, index)
+}
+
+/// Chunks sent per embedding request
+const EMBEDDING_BATCH_SIZE: usize = 50;
+
+/// A chunk waiting to be embedded, with the location recorded in its metadata
+struct PendingChunk {
+    index: usize,
+    start: usize,
+    end: usize,
+    start_line: usize,
+    end_line: usize,
+    text: String,
+}
+
+/// A changed file whose chunks are queued for the next embedding batch
+struct PendingFile {
+    path: String,
+    content_hash: String,
+    resource: Option<crate::database::entities::Resource>,
+    chunks: Vec<PendingChunk>,
+}
+
+/// Shared state of one `build_index_cmd` run
+struct IndexRun<'a> {
+    config: &'a ProviderConfig,
+    /// Recorded per vector so mixed-model stores can be diagnosed
+    model_name: String,
+    state: &'a VectorStoreState,
+    app_handle: &'a tauri::AppHandle,
+    total: usize,
+    processed: usize,
+}
+
+impl IndexRun<'_> {
+    fn report(&mut self, file_path: &str, status: &str) {
+        self.processed += 1;
+        self.app_handle
+            .emit(
+                "indexing-progress",
+                serde_json::json!({
+                    "current": self.processed,
+                    "total": self.total,
+                    "file": file_path,
+                    "status": status
+                }),
+            )
+            .unwrap_or(());
+    }
+
+    /// Embed all queued chunks in batches and store them. Returns how many files were indexed.
+    async fn flush(&mut self, pending: Vec<PendingFile>) -> usize {
+        let texts: Vec<String> = pending
+            .iter()
+            .flat_map(|f| f.chunks.iter().map(|c| c.text.clone()))
+            .collect();
+
+        let mut vectors = Vec::with_capacity(texts.len());
+        let mut failed = false;
+        for batch in texts.chunks(EMBEDDING_BATCH_SIZE) {
+            match ai::get_embeddings_batch(batch, self.config).await {
+                Ok(batch_vectors) => vectors.extend(batch_vectors),
+                Err(e) => {
+                    // Old vectors stay in place for every file in the failed run
+                    println!("Failed to embed batch of {} chunks: {}", batch.len(), e);
+                    failed = true;
+                    break;
+                }
+            }
+        }
+
+        let mut vectors = vectors.into_iter();
+        let mut indexed = 0;
+        for file in pending {
+            if failed {
+                self.report(&file.path, "failed");
+                continue;
+            }
+
+            let items: Vec<VectorItem> = file
+                .chunks
+                .iter()
+                .zip(&mut vectors)
+                .map(|(chunk, vector)| {
+                    let mut metadata = HashMap::from([
+                        ("path".to_string(), file.path.clone()),
+                        (CONTENT_HASH_KEY.to_string(), file.content_hash.clone()),
+                        ("chunk".to_string(), chunk.index.to_string()),
+                        ("start".to_string(), chunk.start.to_string()),
+                        ("end".to_string(), chunk.end.to_string()),
+                        ("start_line".to_string(), chunk.start_line.to_string()),
+                        ("end_line".to_string(), chunk.end_line.to_string()),
+                        (MODEL_KEY.to_string(), self.model_name.clone()),
+                        (DIMENSION_KEY.to_string(), vector.len().to_string()),
+                    ]);
+                    if let Some(resource) = &file.resource {
+                        metadata.insert(COLLECTION_KEY.to_string(), resource.collection.clone());
+                        metadata.insert(TYPE_KEY.to_string(), resource.kind.clone());
+                    }
+                    VectorItem {
+                        id: chunk_id(&file.path, chunk.index),
+                        vector,
+                        metadata: Some(metadata),
+                    }
+                })
+                .collect();
+
+            // Replace the file's previous vectors (including pre-chunking whole-file ones)
+            {
+                let mut store = self.state.0.lock().await;
+                store.remove(&file.path);
+                for item in items {
+                    store.insert(item);
+                }
+            }
+            indexed += 1;
+            self.report(&file.path, "indexed");
+        }
+        indexed
+    }
+}
+
 #[tauri::command]
 pub async fn build_index_cmd(
     files: Vec<String>,
     config: ProviderConfig,
+    force: bool,
     state: State<'_, VectorStoreState>,
+    app_state: State<'_, crate::AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let total = files.len();
     let mut indexed_count = 0;
+    let mut skipped_count = 0;
+    let mut run = IndexRun {
+        config: &config,
+        model_name: match &config.model {
+            Some(model) => format!("{}/{}", config.provider, model),
+            None => config.provider.clone(),
+        },
+        state: &state,
+        app_handle: &app_handle,
+        total,
+        processed: 0,
+    };
 
-    for (index, file_path) in files.iter().enumerate() {
-        // 1. Read file with fallback encoding support (lossy) to fix Greek file crashes
-        if let Ok(bytes) = fs::read(file_path) {
-            let content = String::from_utf8_lossy(&bytes);
+    // The model's current output size, so vectors from an older configuration of the same
+    // model name are re-embedded too. Left unchecked when the probe fails.
+    let dimension = if force {
+        None
+    } else {
+        ai::get_embedding("dimension probe", &config)
+            .await
+            .ok()
+            .map(|vector| vector.len())
+    };
 
-            // Simple truncation/chunking for now
-            let truncated = if content.len() > 8000 {
-                &content[0..8000]
-            } else {
-                &content
-            };
+    // Changed files are queued until there are enough chunks for a full batch
+    let mut pending: Vec<PendingFile> = Vec::new();
+    let mut pending_chunks = 0;
 
-            if truncated.trim().is_empty() {
-                continue;
+    for file_path in &files {
+        // 1. Read file with fallback encoding support (lossy) to fix Greek file crashes
+        let Ok(bytes) = fs::read(file_path) else {
+            run.report(file_path, "failed");
+            continue;
+        };
+        let content = String::from_utf8_lossy(&bytes);
+        let content_hash = crate::history::hash_content(&content);
+
+        // Library info for filtered search; files outside the library just lack these keys
+        let resource = {
+            let db_guard = app_state.db_manager.lock().await;
+            match db_guard.as_ref() {
+                Some(db) => db.get_resource_by_path(file_path).await.unwrap_or(None),
+                None => None,
             }
+        };
 
-            // 2. Get Embedding (async await)
-            match ai::get_embedding(truncated, &config)
-                .await
-                .map_err(|e| e.to_string())
-            {
-                Ok(vector) => {
-                    // 3. Store (Scoped lock to allow other reads if needed, though we are blocking mostly)
-                    {
-                        let mut store = state.0.lock().await;
-                        store.insert(VectorItem {
-                            id: file_path.clone(),
-                            vector,
-                            metadata: Some(HashMap::from([(
-                                "path".to_string(),
-                                file_path.clone(),
-                            )])),
-                        });
-                    } // Lock released
-
-                    indexed_count += 1;
-                }
-                Err(e) => {
-                    println!("Failed to embed {}: {}", file_path, e);
-                }
+        // Skip files embedded from the same content by the same model
+        let unchanged = !force && {
+            let store = state.0.lock().await;
+            store
+                .vectors
+                .get(&chunk_id(file_path, 0))
+                .is_some_and(|item| is_current(item, &content_hash, &run.model_name, dimension))
+        };
+
+        if unchanged {
+            // Vectors from before filtered search, or from before a move, still need
+            // the current library info
+            if let Some(resource) = &resource {
+                let mut store = state.0.lock().await;
+                store.set_metadata(file_path, COLLECTION_KEY, &resource.collection);
+                store.set_metadata(file_path, TYPE_KEY, &resource.kind);
             }
+            skipped_count += 1;
+            run.report(file_path, "skipped");
+            continue;
+        }
+        if content.trim().is_empty() {
+            run.report(file_path, "empty");
+            continue;
         }
 
-        // Emit Progress
-        app_handle
-            .emit(
-                "indexing-progress",
-                serde_json::json!({
-                    "current": index + 1,
-                    "total": total
-                }),
-            )
-            .unwrap_or(());
+        let chunks: Vec<PendingChunk> = chunk_text(&content)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (start, end))| !content[*start..*end].trim().is_empty())
+            .map(|(index, (start, end))| {
+                let text = content[start..end].to_string();
+                let start_line = content[..start].matches('\n').count() + 1;
+                let end_line = start_line + text.trim_end().matches('\n').count();
+                PendingChunk {
+                    index,
+                    start,
+                    end,
+                    start_line,
+                    end_line,
+                    text,
+                }
+            })
+            .collect();
+
+        pending_chunks += chunks.len();
+        pending.push(PendingFile {
+            path: file_path.clone(),
+            content_hash,
+            resource,
+            chunks,
+        });
+
+        // 2. Embed and store once a batch is full
+        if pending_chunks >= EMBEDDING_BATCH_SIZE {
+            indexed_count += run.flush(std::mem::take(&mut pending)).await;
+            pending_chunks = 0;
+        }
+    }
+    if !pending.is_empty() {
+        indexed_count += run.flush(pending).await;
     }
 
     // Save at the end
@@ -190,8 +685,8 @@ pub async fn build_index_cmd(
     }
 
     println!(
-        "Indexing finished. Processed {} files out of {}.",
-        indexed_count, total
+        "Indexing finished. Embedded {} files, skipped {} unchanged, out of {}.",
+        indexed_count, skipped_count, total
     );
     Ok(())
 }
@@ -1,24 +1,111 @@
-use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
-use std::sync::mpsc::channel;
+use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{AppHandle, Emitter};
 
+/// Quiet period before a burst of filesystem events is reported
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// LaTeX build output that changes on every compile; unless committed, it never matters
+/// to git status
+const BUILD_ARTIFACT_SUFFIXES: &[&str] = &[
+    ".aux",
+    ".log",
+    ".pdf",
+    ".synctex.gz",
+    ".synctex",
+    ".fls",
+    ".fdb_latexmk",
+    ".out",
+    ".toc",
+    ".lof",
+    ".lot",
+    ".bbl",
+    ".blg",
+    ".bcf",
+    ".run.xml",
+    ".nav",
+    ".snm",
+    ".vrb",
+    ".xdv",
+    ".dvi",
+];
+
+fn is_build_artifact(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    BUILD_ARTIFACT_SUFFIXES
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+/// Whether a change to `path` can affect what git reports: paths matched by the repo's
+/// ignore rules are skipped, and so are build artifacts unless git tracks them (a
+/// committed PDF is as much a change as any source file).
+fn is_relevant(repo: Option<&git2::Repository>, path: &Path) -> bool {
+    let Some(repo) = repo else {
+        return !is_build_artifact(path);
+    };
+    // Changes inside .git (index, HEAD, refs) are what commits and checkouts look like
+    if path.components().any(|c| c.as_os_str() == ".git") {
+        return true;
+    }
+    let Some(relative) = repo.workdir().and_then(|w| path.strip_prefix(w).ok()) else {
+        return !is_build_artifact(path);
+    };
+    if is_build_artifact(path) {
+        return is_tracked(repo, relative);
+    }
+    !repo.is_path_ignored(relative).unwrap_or(false)
+}
+
+/// Whether `relative` is in the index or HEAD, including tracked files just deleted.
+fn is_tracked(repo: &git2::Repository, relative: &Path) -> bool {
+    repo.status_file(relative)
+        .map(|status| !status.intersects(git2::Status::WT_NEW | git2::Status::IGNORED))
+        .unwrap_or(false)
+}
+
+/// Payload of `git-refresh`: the relevant paths changed during one debounce window
+#[derive(Debug, Clone, Serialize)]
+pub struct GitRefreshEvent {
+    pub root: String,
+    pub paths: Vec<String>,
+    /// "create", "modify", "remove", "other", or "mixed" when a window saw several kinds
+    pub kind: String,
+}
+
+fn event_kind(kind: &EventKind) -> &'static str {
+    match kind {
+        EventKind::Create(_) => "create",
+        EventKind::Modify(_) => "modify",
+        EventKind::Remove(_) => "remove",
+        _ => "other",
+    }
+}
+
 pub struct GitWatcher {
-    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    /// One watcher per watched root; dropping a watcher stops it
+    watchers: Arc<Mutex<HashMap<String, RecommendedWatcher>>>,
 }
 
 impl GitWatcher {
     pub fn new() -> Self {
         Self {
-            watcher: Arc::new(Mutex::new(None)),
+            watchers: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
     pub fn watch(&self, path: &str, app: AppHandle) -> Result<(), String> {
         let (tx, rx) = channel();
 
-        // Create a watcher object, delivering debounced events.
+        // Create a watcher object, delivering raw events.
         // The notification back-end is selected based on the platform.
         let mut watcher =
             RecommendedWatcher::new(tx, Config::default()).map_err(|e| e.to_string())?;
@@ -29,19 +116,148 @@ impl GitWatcher {
             .watch(Path::new(path), RecursiveMode::Recursive)
             .map_err(|e| e.to_string())?;
 
-        // Store the watcher so it stays alive
+        // Store the watcher so it stays alive; re-watching a root replaces its old watcher
+        self.watchers
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), watcher);
+
+        // Spawn a thread to handle events. Saving a single file can produce dozens of raw
+        // events (temp files, renames), so they are coalesced into one `git-refresh`
+        // emitted once the tree has been quiet for DEBOUNCE.
+        let root = path.to_string();
+        std::thread::spawn(move || {
+            // Not every watched folder is a repository; then all build artifacts are filtered
+            let repo = git2::Repository::open(&root).ok();
+            let mut paths: BTreeSet<PathBuf> = BTreeSet::new();
+            let mut kind: Option<&'static str> = None;
+            loop {
+                let res = if !paths.is_empty() {
+                    match rx.recv_timeout(DEBOUNCE) {
+                        Ok(res) => res,
+                        Err(RecvTimeoutError::Timeout) => {
+                            let _ = app.emit(
+                                "git-refresh",
+                                GitRefreshEvent {
+                                    root: root.clone(),
+                                    paths: std::mem::take(&mut paths)
+                                        .into_iter()
+                                        .map(|p| p.to_string_lossy().to_string())
+                                        .collect(),
+                                    kind: kind.take().unwrap_or("other").to_string(),
+                                },
+                            );
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                } else {
+                    // Nothing buffered: block until the next event or until the watcher is dropped
+                    match rx.recv() {
+                        Ok(res) => res,
+                        Err(_) => break,
+                    }
+                };
+
+                match res {
+                    Ok(event) => {
+                        let before = paths.len();
+                        paths.extend(
+                            event
+                                .paths
+                                .into_iter()
+                                .filter(|p| is_relevant(repo.as_ref(), p)),
+                        );
+                        if paths.len() > before {
+                            let this_kind = event_kind(&event.kind);
+                            kind = match kind {
+                                Some(k) if k != this_kind => Some("mixed"),
+                                _ => Some(this_kind),
+                            };
+                        }
+                    }
+                    Err(e) => println!("watch error: {:?}", e),
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Stop watching a single root
+    pub fn unwatch_path(&self, path: &str) {
+        self.watchers.lock().unwrap().remove(path);
+    }
+
+    /// Stop watching every root
+    pub fn unwatch(&self) {
+        self.watchers.lock().unwrap().clear();
+    }
+}
+
+/// Watches the directories holding database resources and reports files that were
+/// written, so stored hashes and history can follow edits made outside the editor.
+pub struct ResourceWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl ResourceWatcher {
+    pub fn new() -> Self {
+        Self {
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// Watch `dirs` (non-recursively), replacing any previous set. `on_change` runs on the
+    /// watcher thread with the files created or modified during one debounce window. These
+    /// are unfiltered: a PDF or figure may be a resource itself, so only the caller can tell
+    /// resources from build output by checking the resource table.
+    pub fn watch<F>(&self, dirs: &BTreeSet<PathBuf>, on_change: F) -> Result<(), String>
+    where
+        F: Fn(Vec<PathBuf>) + Send + 'static,
+    {
+        let (tx, rx) = channel();
+        let mut watcher =
+            RecommendedWatcher::new(tx, Config::default()).map_err(|e| e.to_string())?;
+
+        for dir in dirs {
+            // A collection folder may have been removed since it was imported
+            if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                println!("resource watch {:?}: {}", dir, e);
+            }
+        }
+
+        // Dropping the previous watcher ends its thread
         *self.watcher.lock().unwrap() = Some(watcher);
 
-        // Spawn a thread to handle events
+        // Compilation rewrites many files in quick succession, so changes are coalesced
+        // the same way as for `git-refresh`
         std::thread::spawn(move || {
-            for res in rx {
+            let mut paths: BTreeSet<PathBuf> = BTreeSet::new();
+            loop {
+                let res = if !paths.is_empty() {
+                    match rx.recv_timeout(DEBOUNCE) {
+                        Ok(res) => res,
+                        Err(RecvTimeoutError::Timeout) => {
+                            on_change(std::mem::take(&mut paths).into_iter().collect());
+                            continue;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                } else {
+                    match rx.recv() {
+                        Ok(res) => res,
+                        Err(_) => break,
+                    }
+                };
+
                 match res {
-                    Ok(_) => {
-                        // Debounce logic could be here, or frontend can debounce.
-                        // For simply telling frontend "something changed", we emit event.
-                        // Filter for relevant git events if needed, but monitoring whole repo is safer.
-                        let _ = app.emit("git-refresh", ());
+                    Ok(event)
+                        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) =>
+                    {
+                        paths.extend(event.paths);
                     }
+                    Ok(_) => {}
                     Err(e) => println!("watch error: {:?}", e),
                 }
             }
@@ -51,7 +267,6 @@ impl GitWatcher {
     }
 
     pub fn unwatch(&self) {
-        // Dropping the watcher stops it
         *self.watcher.lock().unwrap() = None;
     }
 }
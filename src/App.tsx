@@ -333,11 +333,9 @@ export default function App() {
         }
 
         // Save local history snapshot (fire and forget)
-        invoke("save_history_snapshot_cmd", {
+        invoke("autosnapshot_on_save", {
           filePath: tab.id,
           content: contentToSave,
-          summary: null,
-          isManual: false,
         }).catch((err) =>
           console.warn("Failed to save history snapshot:", err),
         );
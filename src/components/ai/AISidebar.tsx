@@ -14,10 +14,12 @@ import {
   Box,
   Divider,
   Progress,
+  NumberInput,
 } from "@mantine/core";
 import { indexingService } from "../../services/indexingService";
+import { invoke } from "@tauri-apps/api/core";
 import { IconTrash } from "@tabler/icons-react";
-import { currentProvider } from "../../services/aiService";
+import { currentProvider, providerConfig } from "../../services/aiService";
 import { ChatPanel } from "./ChatPanel";
 import { AgentEditor } from "./AgentEditor";
 import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
@@ -25,6 +27,13 @@ import { faCog, faArrowLeft } from "@fortawesome/free-solid-svg-icons";
 import { useAIStore, AIProviderId } from "../../stores/aiStore";
 import { Agent } from "../../services/agentService";
 
+interface ProviderStatus {
+  ok: boolean;
+  latency_ms: number;
+  model_count: number;
+  message: string;
+}
+
 interface AISidebarProps {
   onInsertCode: (code: string) => void;
   onClose: () => void;
@@ -51,6 +60,8 @@ export const AISidebar: React.FC<AISidebarProps> = ({
     setOpenAIKey,
     openaiModel,
     setOpenAIModel,
+    openaiBaseUrl,
+    setOpenAIBaseUrl,
     geminiKey,
     setGeminiKey,
     geminiModel,
@@ -59,6 +70,12 @@ export const AISidebar: React.FC<AISidebarProps> = ({
     setOllamaUrl,
     ollamaModel,
     setOllamaModel,
+    temperature,
+    setTemperature,
+    topP,
+    setTopP,
+    maxTokens,
+    setMaxTokens,
     agents,
     builtInAgents,
     deleteAgent,
@@ -68,6 +85,30 @@ export const AISidebar: React.FC<AISidebarProps> = ({
 
   const [availableModels, setAvailableModels] = useState<string[]>([]);
   const [loadingModels, setLoadingModels] = useState(false);
+  const [providerStatus, setProviderStatus] = useState<ProviderStatus | null>(
+    null,
+  );
+  const [testingProvider, setTestingProvider] = useState(false);
+
+  const handleTestProvider = async () => {
+    setTestingProvider(true);
+    setProviderStatus(null);
+    try {
+      const status = await invoke<ProviderStatus>("test_provider_cmd", {
+        config: providerConfig(),
+      });
+      setProviderStatus(status);
+    } catch (e) {
+      setProviderStatus({
+        ok: false,
+        latency_ms: 0,
+        model_count: 0,
+        message: String(e),
+      });
+    } finally {
+      setTestingProvider(false);
+    }
+  };
 
   const handleFetchModels = async () => {
     setLoadingModels(true);
@@ -143,6 +184,7 @@ export const AISidebar: React.FC<AISidebarProps> = ({
             onChange={(val) => {
               setProvider(val as AIProviderId);
               setAvailableModels([]); // Reset models on provider change
+              setProviderStatus(null);
             }}
           />
 
@@ -154,6 +196,12 @@ export const AISidebar: React.FC<AISidebarProps> = ({
                 value={openaiKey}
                 onChange={(e) => setOpenAIKey(e.currentTarget.value)}
               />
+              <TextInput
+                label={t("ai.settings.openaiBaseUrl")}
+                placeholder="https://api.openai.com/v1"
+                value={openaiBaseUrl}
+                onChange={(e) => setOpenAIBaseUrl(e.currentTarget.value)}
+              />
               {renderModelSelection(openaiModel, setOpenAIModel, "gpt-4o")}
             </>
           )}
@@ -198,6 +246,57 @@ export const AISidebar: React.FC<AISidebarProps> = ({
             </>
           )}
 
+          {provider !== "mock" && (
+            <Group grow align="flex-start">
+              <NumberInput
+                label={t("ai.settings.temperature")}
+                placeholder="default"
+                min={0}
+                max={2}
+                step={0.1}
+                decimalScale={2}
+                value={temperature ?? ""}
+                onChange={(v) =>
+                  setTemperature(typeof v === "number" ? v : null)
+                }
+              />
+              <NumberInput
+                label={t("ai.settings.topP")}
+                placeholder="default"
+                min={0}
+                max={1}
+                step={0.05}
+                decimalScale={2}
+                value={topP ?? ""}
+                onChange={(v) => setTopP(typeof v === "number" ? v : null)}
+              />
+              <NumberInput
+                label={t("ai.settings.maxTokens")}
+                placeholder="default"
+                min={1}
+                allowDecimal={false}
+                value={maxTokens ?? ""}
+                onChange={(v) => setMaxTokens(typeof v === "number" ? v : null)}
+              />
+            </Group>
+          )}
+
+          <Group gap="xs" wrap="nowrap">
+            <Button
+              variant="default"
+              size="xs"
+              onClick={handleTestProvider}
+              loading={testingProvider}
+            >
+              {t("ai.settings.testConnection")}
+            </Button>
+            {providerStatus && (
+              <Text size="xs" c={providerStatus.ok ? "green" : "red"}>
+                {providerStatus.message}
+              </Text>
+            )}
+          </Group>
+
           <Divider my="md" labelPosition="center" />
 
           <Title order={6} size="sm" c="dimmed" mb="xs">
@@ -226,6 +325,20 @@ export const AISidebar: React.FC<AISidebarProps> = ({
                 {isIndexing ? "Stop" : "Build Index"}
               </Button>
             </Group>
+            <Button
+              size="xs"
+              variant="subtle"
+              color="red"
+              mb="xs"
+              disabled={isIndexing}
+              onClick={async () => {
+                // Needed after switching embedding models: old vectors can't be compared
+                await invoke("clear_index_cmd");
+                setIndexProgress(0);
+              }}
+            >
+              Clear Index
+            </Button>
             {indexProgress > 0 && (
               <Progress value={indexProgress} size="sm" animated={isIndexing} />
             )}
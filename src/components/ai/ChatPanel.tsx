@@ -22,7 +22,8 @@ import remarkMath from "remark-math";
 import rehypeKatex from "rehype-katex";
 import "katex/dist/katex.min.css";
 import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
-import { faPaperPlane, faUser } from "@fortawesome/free-solid-svg-icons";
+import { faPaperPlane, faStop, faUser } from "@fortawesome/free-solid-svg-icons";
+import { invoke } from "@tauri-apps/api/core";
 import {
   IconSparkles2,
   IconTrash,
@@ -123,6 +124,7 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({ onInsertCode }) => {
   useEffect(() => {
     let unlistenThought: () => void;
     let unlistenObservation: () => void;
+    let unlistenUsage: () => void;
 
     import("@tauri-apps/api/event").then(async ({ listen }) => {
       unlistenThought = await listen("agent-thought", (event: any) => {
@@ -134,11 +136,21 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({ onInsertCode }) => {
         if (obs.length > 200) obs = obs.substring(0, 200) + "...";
         setThoughts((prev) => [...prev, `👁️ ${obs}`]);
       });
+      unlistenUsage = await listen("agent-usage", (event: any) => {
+        const { prompt_tokens, completion_tokens, total_tokens } =
+          event.payload;
+        if (!total_tokens) return;
+        setThoughts((prev) => [
+          ...prev,
+          `📊 Tokens: ${prompt_tokens} in / ${completion_tokens} out (${total_tokens} total)`,
+        ]);
+      });
     });
 
     return () => {
       if (unlistenThought) unlistenThought();
       if (unlistenObservation) unlistenObservation();
+      if (unlistenUsage) unlistenUsage();
     };
   }, []);
 
@@ -493,16 +505,29 @@ export const ChatPanel: React.FC<ChatPanelProps> = ({ onInsertCode }) => {
             style={{ flex: 1, maxWidth: 200 }}
           />
 
-          <Button
-            size="xs"
-            variant="light"
-            radius="md"
-            onClick={handleSend}
-            disabled={loading || !input.trim()}
-            rightSection={<FontAwesomeIcon icon={faPaperPlane} />}
-          >
-            Send
-          </Button>
+          {loading ? (
+            <Button
+              size="xs"
+              variant="light"
+              color="red"
+              radius="md"
+              onClick={() => invoke("stop_agent_cmd")}
+              rightSection={<FontAwesomeIcon icon={faStop} />}
+            >
+              Stop
+            </Button>
+          ) : (
+            <Button
+              size="xs"
+              variant="light"
+              radius="md"
+              onClick={handleSend}
+              disabled={!input.trim()}
+              rightSection={<FontAwesomeIcon icon={faPaperPlane} />}
+            >
+              Send
+            </Button>
+          )}
         </Group>
 
         <Text
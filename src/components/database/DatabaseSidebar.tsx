@@ -17,6 +17,7 @@ import {
   MultiSelect,
   Checkbox,
   Menu,
+  Progress,
 } from "@mantine/core";
 import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
 import {
@@ -96,6 +97,8 @@ export const DatabaseSidebar = ({
   );
 
   const importFolder = useDatabaseStore((state) => state.importFolder);
+  const importProgress = useDatabaseStore((state) => state.importProgress);
+  const cancelImport = useDatabaseStore((state) => state.cancelImport);
 
   const importFile = useDatabaseStore((state) => state.importFile);
   const deleteCollection = useDatabaseStore((state) => state.deleteCollection);
@@ -1497,6 +1500,37 @@ export const DatabaseSidebar = ({
         />
       </Stack>
 
+      {/* Folder import progress */}
+      {importProgress && (
+        <Stack gap={4}>
+          <Group justify="space-between" gap="xs" wrap="nowrap">
+            <Text size="xs" c="dimmed" truncate title={importProgress.path}>
+              {t("database.importProgress", {
+                current: importProgress.current,
+                total: importProgress.total,
+              })}
+            </Text>
+            <Button
+              size="compact-xs"
+              variant="subtle"
+              color="red"
+              onClick={() => cancelImport()}
+            >
+              {t("common.cancel")}
+            </Button>
+          </Group>
+          <Progress
+            value={
+              importProgress.total > 0
+                ? (importProgress.current / importProgress.total) * 100
+                : 0
+            }
+            size="sm"
+            animated
+          />
+        </Stack>
+      )}
+
       {/* Main Content Area */}
       <Box style={{ flex: 1, overflow: "hidden", position: "relative" }}>
         {activeView === "collections" ? (
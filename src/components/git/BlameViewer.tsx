@@ -2,13 +2,15 @@ import React, { useEffect, useState } from "react";
 import { Box, Group, Text, ScrollArea, Tooltip, Loader } from "@mantine/core";
 import { invoke } from "@tauri-apps/api/core";
 
-export interface BlameInfo {
-  line_number: number;
+export interface BlameLine {
+  line_no: number;
   commit_id: string;
   short_id: string;
-  author: string;
+  author_name: string;
+  author_email: string;
   timestamp: number;
-  line_content: string;
+  summary: string;
+  content: string;
 }
 
 interface BlameViewerProps {
@@ -20,7 +22,7 @@ export const BlameViewer: React.FC<BlameViewerProps> = ({
   repoPath,
   filePath,
 }) => {
-  const [blameData, setBlameData] = useState<BlameInfo[]>([]);
+  const [blameData, setBlameData] = useState<BlameLine[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
 
@@ -29,7 +31,7 @@ export const BlameViewer: React.FC<BlameViewerProps> = ({
       setLoading(true);
       setError(null);
       try {
-        const data = await invoke<BlameInfo[]>("git_blame_cmd", {
+        const data = await invoke<BlameLine[]>("git_blame_cmd", {
           repoPath,
           filePath,
         });
@@ -114,14 +116,14 @@ export const BlameViewer: React.FC<BlameViewerProps> = ({
                 >
                   {!isSameCommit && (
                     <Tooltip
-                      label={`${line.author} - ${new Date(line.timestamp * 1000).toLocaleString()} \n ${line.commit_id}`}
+                      label={`${line.author_name} - ${new Date(line.timestamp * 1000).toLocaleString()} \n ${line.summary} \n ${line.commit_id}`}
                     >
                       <Group gap={4} wrap="nowrap">
                         <Text size="xs" c="blue" fw={500} style={{ width: 50 }}>
                           {line.short_id}
                         </Text>
                         <Text size="xs" c="dimmed" truncate style={{ flex: 1 }}>
-                          {line.author}
+                          {line.author_name}
                         </Text>
                         <Text
                           size="xs"
@@ -144,12 +146,12 @@ export const BlameViewer: React.FC<BlameViewerProps> = ({
                     ta="right"
                     style={{ userSelect: "none" }}
                   >
-                    {line.line_number}
+                    {line.line_no}
                   </Text>
                   <Text
                     style={{ whiteSpace: "pre-wrap", wordBreak: "break-all" }}
                   >
-                    {line.line_content}
+                    {line.content}
                   </Text>
                 </Group>
               </Box>
@@ -24,6 +24,8 @@ interface DiffLine {
   old_line_no: number | null;
   new_line_no: number | null;
   content: string;
+  // Changed character ranges [start, end) on modified lines
+  highlights?: [number, number][];
 }
 
 interface DiffStats {
@@ -37,6 +39,8 @@ export interface StructuredDiff {
   new_content: string;
   lines: DiffLine[];
   stats: DiffStats;
+  is_binary: boolean;
+  too_large: boolean;
 }
 
 interface DiffViewerProps {
@@ -128,7 +132,13 @@ export const DiffViewer: React.FC<DiffViewerProps> = ({
 
       {/* Diff Content */}
       <ScrollArea style={{ flex: 1 }}>
-        {viewMode === "unified" ? (
+        {diff.is_binary || diff.too_large ? (
+          <Text size="xs" c="dimmed" p="sm">
+            {diff.is_binary
+              ? "Binary file changed"
+              : "File too large to display a diff"}
+          </Text>
+        ) : viewMode === "unified" ? (
           <UnifiedDiffView lines={diff.lines} />
         ) : (
           <SideBySideDiff repoPath={repoPath} filePath={diff.file_path} />
@@ -201,7 +211,7 @@ const UnifiedDiffView: React.FC<{ lines: DiffLine[] }> = ({ lines }) => {
             }}
           >
             {getLinePrefix(line.line_type)}
-            {line.content}
+            {renderHighlighted(line)}
           </Text>
         </Group>
       ))}
@@ -210,6 +220,35 @@ const UnifiedDiffView: React.FC<{ lines: DiffLine[] }> = ({ lines }) => {
 };
 
 // Helper functions
+function renderHighlighted(line: DiffLine): React.ReactNode {
+  if (!line.highlights || line.highlights.length === 0) return line.content;
+
+  // Offsets are in characters, so slice by code points rather than UTF-16 units
+  const chars = Array.from(line.content);
+  const parts: React.ReactNode[] = [];
+  let pos = 0;
+  line.highlights.forEach(([start, end], i) => {
+    if (start > pos) parts.push(chars.slice(pos, start).join(""));
+    parts.push(
+      <span
+        key={i}
+        style={{
+          backgroundColor:
+            line.line_type === "add"
+              ? "rgba(0, 255, 0, 0.25)"
+              : "rgba(255, 0, 0, 0.25)",
+          borderRadius: 2,
+        }}
+      >
+        {chars.slice(start, end).join("")}
+      </span>,
+    );
+    pos = end;
+  });
+  if (pos < chars.length) parts.push(chars.slice(pos).join(""));
+  return parts;
+}
+
 function getLineBackground(type: string): string | undefined {
   switch (type) {
     case "add":
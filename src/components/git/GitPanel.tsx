@@ -57,6 +57,8 @@ interface GitRepoInfo {
   remote_url: string | null;
   is_dirty: boolean;
   head_commit: string | null;
+  ahead: number;
+  behind: number;
 }
 
 interface GitFileStatus {
@@ -309,9 +311,14 @@ export const GitPanel: React.FC<GitPanelProps> = ({
       }
 
       // Listen for event
-      unlisten = await listen("git-refresh", () => {
-        debouncedRefresh(repoInfo.path);
-      });
+      unlisten = await listen<{ root: string; paths: string[]; kind: string }>(
+        "git-refresh",
+        (event) => {
+          // Other projects may be watched too
+          if (event.payload.root !== repoInfo.path) return;
+          debouncedRefresh(repoInfo.path);
+        },
+      );
     };
 
     startWatching();
@@ -319,7 +326,7 @@ export const GitPanel: React.FC<GitPanelProps> = ({
     return () => {
       // Cleanup
       if (unlisten) unlisten();
-      invoke("git_unwatch_repo_cmd").catch((e) =>
+      invoke("git_unwatch_repo_cmd", { repoPath: repoInfo.path }).catch((e) =>
         console.warn("Failed to stop git watcher:", e),
       );
     };
@@ -338,7 +345,11 @@ export const GitPanel: React.FC<GitPanelProps> = ({
       const branchList = await invoke<BranchInfo[]>("git_list_branches_cmd", {
         repoPath,
       });
-      setBranches(branchList.filter((b) => !b.is_remote)); // Filter out remote branches for main list if desired, or keep all. Backend sorts them.
+      // Remote branches stay listed: switching to one creates a local tracking branch.
+      // The symbolic "<remote>/HEAD" is not a branch anyone wants to check out.
+      setBranches(
+        branchList.filter((b) => !(b.is_remote && b.name.endsWith("/HEAD"))),
+      );
 
       // Fetch remotes
       try {
@@ -680,12 +691,15 @@ export const GitPanel: React.FC<GitPanelProps> = ({
     if (!repoInfo) return;
 
     try {
-      const log = await invoke<GitCommitInfo[]>("git_log_cmd", {
-        repoPath: repoInfo.path,
-        limit: 200,
-        all: true,
-      });
-      setCommits(log);
+      const log = await invoke<{ commits: GitCommitInfo[]; has_more: boolean }>(
+        "git_log_cmd",
+        {
+          repoPath: repoInfo.path,
+          limit: 200,
+          all: true,
+        },
+      );
+      setCommits(log.commits);
     } catch (err) {
       console.error("Failed to load commits:", err);
     }
@@ -33,6 +33,8 @@ interface HistoryEntry {
   created_at: string;
   summary: string | null;
   is_manual_snapshot: boolean;
+  // Changes relative to the previous snapshot
+  stats: DiffStats;
 }
 
 interface DiffChange {
@@ -86,10 +88,10 @@ export const HistoryPanel: React.FC<HistoryPanelProps> = ({
     setError(null);
 
     try {
-      const entries = await invoke<HistoryEntry[]>("get_file_history_cmd", {
-        filePath: activeFilePath,
-        limit: 50,
-      });
+      const entries = await invoke<HistoryEntry[]>(
+        "get_history_timeline_cmd",
+        { filePath: activeFilePath, limit: 50 },
+      );
       setHistory(entries);
     } catch (err) {
       console.error("Failed to load history:", err);
@@ -153,7 +155,12 @@ export const HistoryPanel: React.FC<HistoryPanelProps> = ({
           snapshotId: selectedEntry.id,
         },
       );
+      // Writes the file and keeps the current version as a manual snapshot
+      await invoke("restore_snapshot_to_file_cmd", {
+        snapshotId: selectedEntry.id,
+      });
       onRestoreContent(content);
+      loadHistory();
       setSelectedEntry(null);
       setDiffResult(null);
     } catch (err) {
@@ -295,6 +302,12 @@ export const HistoryPanel: React.FC<HistoryPanelProps> = ({
                           Manual
                         </Badge>
                       )}
+                      <Text size="xs" c="green">
+                        +{entry.stats.additions}
+                      </Text>
+                      <Text size="xs" c="red">
+                        −{entry.stats.deletions}
+                      </Text>
                     </Group>
                     <Text size="xs" c="dimmed" lineClamp={1}>
                       {entry.summary || entry.content_hash.slice(0, 8)}
@@ -1,6 +1,7 @@
 import { useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useDatabaseStore } from "../stores/databaseStore";
+import { useSettingsStore } from "../stores/settingsStore";
 import { parseLatexLog, LogEntry } from "../utils/logParser";
 
 interface UseCompilationOptions {
@@ -51,27 +52,21 @@ export function useCompilation({
         let selectedEngine = engine || "pdflatex";
         let args = ["-interaction=nonstopmode", "-synctex=1"];
         const outputDir = "";
+        const config = useSettingsStore.getState().settings.texEngine;
+        // Passed as a flag rather than an argument so the backend decides how to apply it
+        const shellEscape = config.shellEscape;
 
-        // If no engine explicitly passed, check config
+        // If no engine explicitly passed, use the TeX engine settings
         if (!engine) {
-          const savedConfig = localStorage.getItem("tex-engine-config");
-          if (savedConfig) {
-            try {
-              const config = JSON.parse(savedConfig);
-              const engineKey = config.defaultEngine || "pdflatex";
-              if (engineKey === "xelatex")
-                selectedEngine = config.xelatexPath || "xelatex";
-              else if (engineKey === "lualatex")
-                selectedEngine = config.lualatexPath || "lualatex";
-              else selectedEngine = config.pdflatexPath || "pdflatex";
-
-              args = ["-interaction=nonstopmode"];
-              if (config.synctex) args.push("-synctex=1");
-              if (config.shellEscape) args.push("-shell-escape");
-            } catch (e) {
-              // Failed to parse config, using defaults
-            }
-          }
+          const engineKey = config.defaultEngine || "pdflatex";
+          if (engineKey === "xelatex")
+            selectedEngine = config.xelatexPath || "xelatex";
+          else if (engineKey === "lualatex")
+            selectedEngine = config.lualatexPath || "lualatex";
+          else selectedEngine = config.pdflatexPath || "pdflatex";
+
+          args = ["-interaction=nonstopmode"];
+          if (config.synctex) args.push("-synctex=1");
         }
 
         // --- DYNAMIC COMPILATION CHECK ---
@@ -79,11 +74,25 @@ export function useCompilation({
         // Normalize paths for comparison if needed (though usually identical)
         const resource = allResources.find((r) => r.path === filePath);
 
-        if (resource && resource.metadata && resource.metadata.preamble) {
-          // Modular resource detected, using compile_resource_cmd
-          // Use the specific command that handles wrapping
-          // We can ignore the returned path since we forced it to be standard filename.pdf
-          await invoke("compile_resource_cmd", { id: resource.id });
+        if (resource) {
+          // Library resource: compile_resource_cmd applies its stored build command
+          // (falling back to the settings above) and wraps it in its preamble if it has one
+          const result = await invoke<{
+            success: boolean;
+            exit_code: number | null;
+            stdout: string;
+            stderr: string;
+          }>("compile_resource_cmd", {
+            id: resource.id,
+            engine: selectedEngine,
+            args,
+            shellEscape,
+          });
+          if (!result.success) {
+            throw new Error(
+              `Compilation failed with status code: ${result.exit_code}\n\nSTDOUT:\n${result.stdout}\n\nSTDERR:\n${result.stderr}`,
+            );
+          }
         } else {
           // Standard Compilation
           await invoke("compile_tex", {
@@ -91,6 +100,7 @@ export function useCompilation({
             engine: selectedEngine,
             args,
             outputDir,
+            shellEscape,
           });
         }
 
@@ -88,10 +88,6 @@ export function usePdfState({
       try {
         const texPath = activeTab.id;
         const pdfPath = texPath.replace(/\.tex$/i, ".pdf");
-        const lastSlash = texPath.lastIndexOf(
-          texPath.includes("\\") ? "\\" : "/",
-        );
-        const cwd = texPath.substring(0, lastSlash);
 
         // Check if PDF file actually exists on disk
         const { exists } = await import("@tauri-apps/plugin-fs");
@@ -104,41 +100,24 @@ export function usePdfState({
           return;
         }
 
-        const args = [
-          "view",
-          "-i",
-          `${line}:${column}:${texPath}`,
-          "-o",
-          pdfPath,
-        ];
-
-        const result = await invoke<string>("run_synctex_command", {
-          args,
-          cwd,
+        const { page, x, y } = await invoke<{
+          page: number;
+          x: number;
+          y: number;
+        }>("synctex_forward_cmd", {
+          file: texPath,
+          line,
+          column,
+          pdf: pdfPath,
         });
 
-        // Validate regex matches
-        const pageMatch = result.match(/Page:(\d+)/);
-        const xMatch = result.match(/x:([\d\.]+)/);
-        const yMatch = result.match(/y:([\d\.]+)/);
-
-        if (pageMatch) {
-          const page = parseInt(pageMatch[1], 10);
-          const x = xMatch ? parseFloat(xMatch[1]) : 0;
-          const y = yMatch ? parseFloat(yMatch[1]) : 0;
-
-          if (isNaN(page) || page < 1) {
-            setCompileError("SyncTeX returned invalid page number.");
-            return;
-          }
-
-          setSyncTexCoords({ page, x, y });
-          onRequirePanelOpen?.();
-        } else {
-          setCompileError(
-            "SyncTeX forward sync failed. Make sure you compiled with -synctex=1 flag.",
-          );
+        if (page < 1) {
+          setCompileError("SyncTeX returned invalid page number.");
+          return;
         }
+
+        setSyncTexCoords({ page, x, y });
+        onRequirePanelOpen?.();
       } catch (e) {
         console.error("SyncTeX Forward Failed:", e);
         const errorMsg = String(e);
@@ -165,32 +144,11 @@ export function usePdfState({
       try {
         const texPath = activeTab.id;
         const pdfPath = texPath.replace(/\.tex$/i, ".pdf");
-        const lastSlash = texPath.lastIndexOf(
-          texPath.includes("\\") ? "\\" : "/",
-        );
-        const cwd = texPath.substring(0, lastSlash);
-
-        // synctex edit -o page:x:y:file.pdf
-        const args = ["edit", "-o", `${page}:${x}:${y}:${pdfPath}`];
-
-        const result = await invoke<string>("run_synctex_command", {
-          args,
-          cwd,
-        });
 
-        // Output format:
-        // Line:10
-        // File:/path/to/file.tex
-        const lineMatch = result.match(/Line:(\d+)/);
-        const fileMatch = result.match(/File:(.+)/);
-
-        if (lineMatch && fileMatch) {
-          return {
-            line: parseInt(lineMatch[1], 10),
-            file: fileMatch[1].trim(),
-          };
-        }
-        return null;
+        return await invoke<{ file: string; line: number }>(
+          "synctex_inverse_cmd",
+          { pdf: pdfPath, page, x, y },
+        );
       } catch (e) {
         console.error("SyncTeX Inverse Failed:", e);
         return null;
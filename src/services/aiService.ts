@@ -1,4 +1,7 @@
 import { useAIStore, Message } from "../stores/aiStore";
+import { useProjectStore } from "../stores/projectStore";
+import { useDatabaseStore } from "../stores/databaseStore";
+import { useSettingsStore } from "../stores/settingsStore";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { notifications } from "@mantine/notifications";
@@ -12,31 +15,44 @@ export interface AIProvider {
   getModels?(): Promise<string[]>;
 }
 
+/** Backend `ProviderConfig` for the currently selected provider */
+export function providerConfig() {
+  const store = useAIStore.getState();
+  return {
+    provider: store.provider,
+    api_key:
+      store.provider === "openai"
+        ? store.openaiKey
+        : store.provider === "gemini"
+          ? store.geminiKey
+          : undefined,
+    model:
+      store.provider === "openai"
+        ? store.openaiModel
+        : store.provider === "gemini"
+          ? store.geminiModel
+          : store.provider === "ollama"
+            ? store.ollamaModel
+            : undefined,
+    url:
+      store.provider === "ollama"
+        ? store.ollamaUrl
+        : store.provider === "openai"
+          ? store.openaiBaseUrl || undefined
+          : undefined,
+    temperature: store.temperature ?? undefined,
+    top_p: store.topP ?? undefined,
+    max_tokens: store.maxTokens ?? undefined,
+  };
+}
+
 export const aiProxy = {
   // Deprecated client-side chat, now effectively a wrapper for backend agent
   async chat(
     history: Message[],
     onStream: (chunk: string) => void,
   ): Promise<string> {
-    const store = useAIStore.getState();
-    const config = {
-      provider: store.provider,
-      api_key:
-        store.provider === "openai"
-          ? store.openaiKey
-          : store.provider === "gemini"
-            ? store.geminiKey
-            : undefined,
-      model:
-        store.provider === "openai"
-          ? store.openaiModel
-          : store.provider === "gemini"
-            ? store.geminiModel
-            : store.provider === "ollama"
-              ? store.ollamaModel
-              : undefined,
-      url: store.provider === "ollama" ? store.ollamaUrl : undefined,
-    };
+    const config = providerConfig();
 
     let fullResponse = "";
     const listeners: (() => void)[] = [];
@@ -71,11 +87,45 @@ export const aiProxy = {
           }),
         );
 
+        listeners.push(
+          await listen("agent-context-trimmed", (event: any) => {
+            const { removed_messages } = event.payload;
+            console.log("[Event] agent-context-trimmed:", event.payload);
+            notifications.show({
+              title: "Context Trimmed",
+              message: `Removed ${removed_messages} older messages to fit the model's context window.`,
+              color: "yellow",
+            });
+          }),
+        );
+
+        // Tokens arrive as they are generated; agent-response carries the full answer
+        let streamed = false;
+        listeners.push(
+          await listen("agent-token", (event: any) => {
+            streamed = true;
+            onStream(event.payload);
+          }),
+        );
+
         listeners.push(
           await listen("agent-response", (event: any) => {
             console.log("[Event] agent-response chunk received");
             fullResponse += event.payload;
-            onStream(event.payload);
+            if (!streamed) onStream(event.payload);
+          }),
+        );
+
+        listeners.push(
+          await listen("agent-approval-request", async (event: any) => {
+            const { id, command, cwd } = event.payload;
+            const approved = window.confirm(
+              `The agent wants to run:\n\n${command}\n\nin ${cwd}. Allow?`,
+            );
+            // Stopping the agent withdraws the request while the dialog is open
+            await invoke(approved ? "approve_tool_cmd" : "deny_tool_cmd", {
+              id,
+            }).catch(() => {});
           }),
         );
 
@@ -152,6 +202,14 @@ export const aiProxy = {
         await invoke("start_agent_cmd", {
           chatHistory: chatHistory,
           config: config,
+          // File tools are confined to the open project, or else the active collection
+          options: {
+            workspace_root: useProjectStore.getState().rootPath,
+            collection: useDatabaseStore.getState().activeCollection,
+            // Compiles with shell escape still ask the user first
+            shell_escape:
+              useSettingsStore.getState().settings.texEngine.shellEscape,
+          },
         });
         console.log("[AIProxy] Invoke success");
       } catch (e: any) {
@@ -170,8 +228,9 @@ export const aiProxy = {
 // Compatibility Shim for legacy components
 export const currentProvider = {
   getModels: async (): Promise<string[]> => {
-    // Return standard models for now until backend command is ready
-    return ["gpt-4o", "gpt-4-turbo", "gemini-1.5-flash", "llama3"];
+    return await invoke<string[]>("list_models_cmd", {
+      config: providerConfig(),
+    });
   },
   chat: async (messages: Message[]): Promise<string> => {
     // Call aiProxy.chat with the FULL history.
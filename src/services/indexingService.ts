@@ -12,15 +12,23 @@ export const indexingService = {
 
   async buildIndex(
     onProgress: (current: number, total: number) => void,
+    force = false,
   ): Promise<void> {
     if (this.isIndexing) return;
     this.isIndexing = true;
     this.stopRequested = false;
     this.progress = 0;
+    let skipped = 0;
 
     // Listen for progress events from Rust
     const unlisten = await listen("indexing-progress", (event: any) => {
-      const payload = event.payload as { current: number; total: number };
+      const payload = event.payload as {
+        current: number;
+        total: number;
+        status?: string;
+      };
+      // Unchanged files keep their existing embedding
+      if (payload.status === "skipped") skipped++;
       this.progress = payload.current;
       this.total = payload.total;
       onProgress(this.progress, this.total);
@@ -76,7 +84,12 @@ export const indexingService = {
               : aiState.provider === "ollama"
                 ? aiState.ollamaModel
                 : undefined,
-        url: aiState.provider === "ollama" ? aiState.ollamaUrl : undefined,
+        url:
+          aiState.provider === "ollama"
+            ? aiState.ollamaUrl
+            : aiState.provider === "openai"
+              ? aiState.openaiBaseUrl || undefined
+              : undefined,
       };
 
       // 3. Invoke Rust Command
@@ -85,11 +98,15 @@ export const indexingService = {
       await invoke("build_index_cmd", {
         files: filePaths,
         config: config,
+        force,
       });
 
       notifications.show({
         title: "Indexing Complete",
-        message: `Successfully indexed ${this.total} files via Rust backend.`,
+        message:
+          skipped > 0
+            ? `Indexed ${this.total - skipped} files, skipped ${skipped} unchanged.`
+            : `Successfully indexed ${this.total} files via Rust backend.`,
         color: "green",
       });
     } catch (e: any) {
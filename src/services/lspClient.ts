@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 
 /**
  * Convert a file path to a properly encoded file:// URI
@@ -87,6 +88,40 @@ export interface Location {
   };
 }
 
+/**
+ * LSP Document Symbol (hierarchical)
+ */
+export interface DocumentSymbol {
+  name: string;
+  detail?: string;
+  kind: number;
+  range: Location["range"];
+  selectionRange: Location["range"];
+  children?: DocumentSymbol[];
+}
+
+/**
+ * LSP Text Edit (returned by formatting requests)
+ */
+export interface TextEdit {
+  range: Location["range"];
+  newText: string;
+}
+
+/**
+ * LSP Diagnostic
+ */
+export interface Diagnostic {
+  range: {
+    start: { line: number; character: number };
+    end: { line: number; character: number };
+  };
+  severity?: number;
+  code?: string | number;
+  source?: string;
+  message: string;
+}
+
 /**
  * Texlab LSP Client
  * Provides intelligent autocomplete and language features for LaTeX
@@ -94,15 +129,28 @@ export interface Location {
 export class TexlabLspClient {
   private initialized = false;
   private documentVersion = new Map<string, number>();
+  // Last known text per document, re-sent if texlab is restarted after a crash
+  private documentText = new Map<string, string>();
+  private unlistenRestart: UnlistenFn | null = null;
 
   /**
-   * Initialize the LSP server with workspace root
+   * Initialize the LSP server with workspace root.
+   * `requestTimeoutMs` overrides the backend's 5s per-request timeout.
    */
-  async initialize(rootUri: string): Promise<void> {
+  async initialize(rootUri: string, requestTimeoutMs?: number): Promise<void> {
     try {
       const encodedUri = pathToUri(rootUri);
-      await invoke("lsp_initialize", { rootUri: encodedUri });
+      await invoke("lsp_initialize", {
+        rootUri: encodedUri,
+        requestTimeoutMs: requestTimeoutMs ?? null,
+      });
       this.initialized = true;
+
+      if (!this.unlistenRestart) {
+        this.unlistenRestart = await listen("lsp-restarted", () => {
+          this.reopenDocuments();
+        });
+      }
     } catch (error) {
       console.error("❌ Failed to initialize Texlab LSP:", error);
       throw error;
@@ -216,6 +264,124 @@ export class TexlabLspClient {
     }
   }
 
+  /**
+   * Get the document outline (sections, environments, labels)
+   */
+  async documentSymbols(uri: string): Promise<DocumentSymbol[]> {
+    if (!this.initialized) return [];
+
+    try {
+      const encodedUri = pathToUri(uri);
+      const result = await invoke<DocumentSymbol[] | null>(
+        "lsp_document_symbol",
+        { uri: encodedUri },
+      );
+      return result || [];
+    } catch (error) {
+      console.error("Document symbol error:", error);
+      return [];
+    }
+  }
+
+  /**
+   * Find all references to the symbol at a position, including its declaration
+   */
+  async references(
+    uri: string,
+    line: number,
+    character: number,
+  ): Promise<Location[]> {
+    if (!this.initialized) return [];
+
+    try {
+      const encodedUri = pathToUri(uri);
+      const result = await invoke<Location[] | null>("lsp_references", {
+        uri: encodedUri,
+        line: line - 1,
+        character,
+      });
+      return result || [];
+    } catch (error) {
+      console.error("References error:", error);
+      return [];
+    }
+  }
+
+  /**
+   * Format the whole document with latexindent
+   */
+  async formatting(
+    uri: string,
+    tabSize = 2,
+    insertSpaces = true,
+  ): Promise<TextEdit[]> {
+    if (!this.initialized) return [];
+
+    try {
+      const encodedUri = pathToUri(uri);
+      const result = await invoke<TextEdit[] | null>("lsp_formatting", {
+        uri: encodedUri,
+        tabSize,
+        insertSpaces,
+      });
+      return result || [];
+    } catch (error) {
+      console.error("Formatting error:", error);
+      return [];
+    }
+  }
+
+  /**
+   * Format a selection with latexindent (1-based lines, like the other methods)
+   */
+  async rangeFormatting(
+    uri: string,
+    startLine: number,
+    startCharacter: number,
+    endLine: number,
+    endCharacter: number,
+    tabSize = 2,
+    insertSpaces = true,
+  ): Promise<TextEdit[]> {
+    if (!this.initialized) return [];
+
+    try {
+      const encodedUri = pathToUri(uri);
+      const result = await invoke<TextEdit[] | null>("lsp_range_formatting", {
+        uri: encodedUri,
+        range: {
+          start: { line: startLine - 1, character: startCharacter },
+          end: { line: endLine - 1, character: endCharacter },
+        },
+        tabSize,
+        insertSpaces,
+      });
+      return result || [];
+    } catch (error) {
+      console.error("Range formatting error:", error);
+      return [];
+    }
+  }
+
+  /**
+   * Get the latest diagnostics texlab published for a document.
+   * Live updates are emitted as `lsp-diagnostics` events.
+   */
+  async diagnostics(uri: string): Promise<Diagnostic[]> {
+    if (!this.initialized) return [];
+
+    try {
+      const encodedUri = pathToUri(uri);
+      const result = await invoke<Diagnostic[]>("lsp_diagnostics", {
+        uri: encodedUri,
+      });
+      return result || [];
+    } catch (error) {
+      console.error("Diagnostics error:", error);
+      return [];
+    }
+  }
+
   /**
    * Notify LSP that a document was opened
    */
@@ -229,6 +395,7 @@ export class TexlabLspClient {
       const version = 1;
       const encodedUri = pathToUri(uri);
       this.documentVersion.set(uri, version);
+      this.documentText.set(uri, text);
 
       await invoke("lsp_did_open", {
         uri: encodedUri,
@@ -254,6 +421,7 @@ export class TexlabLspClient {
       const version = (this.documentVersion.get(uri) || 0) + 1;
       const encodedUri = pathToUri(uri);
       this.documentVersion.set(uri, version);
+      this.documentText.set(uri, text);
 
       await invoke("lsp_did_change", {
         uri: encodedUri,
@@ -275,11 +443,24 @@ export class TexlabLspClient {
       await invoke("lsp_shutdown");
       this.initialized = false;
       this.documentVersion.clear();
+      this.documentText.clear();
+      this.unlistenRestart?.();
+      this.unlistenRestart = null;
     } catch (error) {
       console.error("Shutdown error:", error);
     }
   }
 
+  /**
+   * Re-open every tracked document after texlab was restarted
+   */
+  private async reopenDocuments(): Promise<void> {
+    const documents = Array.from(this.documentText.entries());
+    for (const [uri, text] of documents) {
+      await this.didOpen(uri, "latex", text);
+    }
+  }
+
   /**
    * Check if LSP is initialized and ready
    */
@@ -141,6 +141,7 @@ interface PackageListItem {
   version: string;
   home: string | null;
   ctan: string | null;
+  score: number | null;
 }
 
 interface PackageResponse {
@@ -159,6 +160,8 @@ export interface ListPackage {
   hasWizard: boolean;
   home: string | null;
   ctan: string | null;
+  // Search relevance, null when browsing without a query
+  score: number | null;
 }
 
 export async function getAllPackages(
@@ -183,6 +186,7 @@ export async function getAllPackages(
       hasWizard: hasWizard(item.id),
       home: item.home,
       ctan: item.ctan,
+      score: item.score,
     }));
 
     return { packages, total: response.total };
@@ -266,6 +270,28 @@ export function getWizardPackageCount(): number {
   return Object.keys(WIZARD_REGISTRY).length;
 }
 
+/**
+ * Install a package through tlmgr. Output lines arrive as `tlmgr-progress` events.
+ */
+export async function installPackage(name: string): Promise<string> {
+  return invoke<string>("install_package", { name });
+}
+
+/**
+ * Check whether tlmgr reports the package as installed
+ */
+export async function isPackageInstalled(name: string): Promise<boolean> {
+  return invoke<boolean>("is_package_installed", { name });
+}
+
+/**
+ * Download the current package list from CTAN. Returns the package count;
+ * rejects (keeping the existing list) when CTAN is unreachable.
+ */
+export async function refreshCtanDatabase(): Promise<number> {
+  return invoke<number>("refresh_ctan_db");
+}
+
 // Re-export types and functions from wizard registry
 export { hasWizard, getWizardConfig, WIZARD_REGISTRY };
 export type { WizardConfig, WizardCategory };
@@ -19,10 +19,16 @@ interface AIState {
   provider: AIProviderId;
   openaiKey: string;
   openaiModel: string;
+  /** OpenAI-compatible gateway; empty means the OpenAI API itself */
+  openaiBaseUrl: string;
   geminiKey: string;
   geminiModel: string;
   ollamaUrl: string;
   ollamaModel: string;
+  // Sampling overrides; null leaves the provider default
+  temperature: number | null;
+  topP: number | null;
+  maxTokens: number | null;
 
   // Agents
   builtInAgents: Agent[];
@@ -36,10 +42,14 @@ interface AIState {
   setProvider: (provider: AIProviderId) => void;
   setOpenAIKey: (key: string) => void;
   setOpenAIModel: (model: string) => void;
+  setOpenAIBaseUrl: (url: string) => void;
   setGeminiKey: (key: string) => void;
   setGeminiModel: (model: string) => void;
   setOllamaUrl: (url: string) => void;
   setOllamaModel: (model: string) => void;
+  setTemperature: (value: number | null) => void;
+  setTopP: (value: number | null) => void;
+  setMaxTokens: (value: number | null) => void;
 
   createAgent: (agent: Agent) => void;
   updateAgent: (agent: Agent) => void;
@@ -59,10 +69,14 @@ export const useAIStore = create<AIState>()(
       provider: "mock",
       openaiKey: "",
       openaiModel: "gpt-4o",
+      openaiBaseUrl: "",
       geminiKey: "",
       geminiModel: "gemini-1.5-flash",
       ollamaUrl: "http://localhost:11434",
       ollamaModel: "llama3",
+      temperature: null,
+      topP: null,
+      maxTokens: null,
       messages: [],
 
       builtInAgents: getBuiltInAgents(),
@@ -73,10 +87,14 @@ export const useAIStore = create<AIState>()(
       setProvider: (provider) => set({ provider }),
       setOpenAIKey: (openaiKey) => set({ openaiKey }),
       setOpenAIModel: (openaiModel) => set({ openaiModel }),
+      setOpenAIBaseUrl: (openaiBaseUrl) => set({ openaiBaseUrl }),
       setGeminiKey: (geminiKey) => set({ geminiKey }),
       setGeminiModel: (geminiModel) => set({ geminiModel }),
       setOllamaUrl: (ollamaUrl) => set({ ollamaUrl }),
       setOllamaModel: (ollamaModel) => set({ ollamaModel }),
+      setTemperature: (temperature) => set({ temperature }),
+      setTopP: (topP) => set({ topP }),
+      setMaxTokens: (maxTokens) => set({ maxTokens }),
 
       createAgent: (agent) =>
         set((state) => ({ agents: [...state.agents, agent] })),
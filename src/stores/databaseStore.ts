@@ -1,5 +1,6 @@
 import { create } from "zustand";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 
 export interface Collection {
   name: string;
@@ -38,6 +39,13 @@ export interface Resource {
   updated_at?: string;
 }
 
+// Payload of the backend's `import-progress` event
+export interface ImportProgress {
+  current: number;
+  total: number;
+  path: string;
+}
+
 export interface LatexFileResource extends Resource {
   kind: "file";
   metadata: LatexFileMetadata;
@@ -53,12 +61,15 @@ interface DatabaseState {
   loadedCollections: string[];
 
   allLoadedResources: Resource[];
+  // Set while a folder import runs
+  importProgress: ImportProgress | null;
   isWizardOpen: boolean;
   setWizardOpen: (open: boolean) => void;
 
   fetchCollections: () => Promise<void>;
   selectCollection: (name: string) => Promise<void>;
   importFolder: (path: string, name: string) => Promise<void>;
+  cancelImport: () => Promise<void>;
   addFolderToCollection: (
     collectionName: string,
     path: string,
@@ -69,7 +80,9 @@ interface DatabaseState {
   setLoadedCollections: (collections: string[]) => Promise<void>;
   fetchResourcesForLoadedCollections: () => Promise<void>;
   deleteCollection: (name: string) => Promise<void>;
-  deleteResource: (id: string) => Promise<void>;
+  renameCollection: (oldName: string, newName: string) => Promise<void>;
+  moveResources: (ids: string[], targetCollection: string) => Promise<void>;
+  deleteResource: (id: string, force?: boolean) => Promise<void>;
   createResource: (
     path: string,
     collection: string,
@@ -120,6 +133,7 @@ export const useDatabaseStore = create<DatabaseState>((set, get) => ({
   error: null,
   loadedCollections: [],
   allLoadedResources: [],
+  importProgress: null,
 
   fetchCollections: async () => {
     set({ isLoading: true, error: null });
@@ -219,22 +233,44 @@ export const useDatabaseStore = create<DatabaseState>((set, get) => ({
   },
 
   importFolder: async (path: string, name: string) => {
-    set({ isLoading: true });
+    set({ isLoading: true, importProgress: { current: 0, total: 0, path } });
+    const unlisten = await listen<ImportProgress>("import-progress", (event) =>
+      set({ importProgress: event.payload }),
+    );
     try {
-      await invoke("import_folder_cmd", { path, collectionName: name });
+      await invoke("import_folder_cmd", {
+        path,
+        collectionName: name,
+        excludeGlobs: [],
+      });
       // Refresh collections
       await get().fetchCollections();
       // Auto-load the newly imported collection
       await get().toggleCollectionLoaded(name);
     } catch (err: any) {
       set({ error: err.toString(), isLoading: false });
+    } finally {
+      unlisten();
+      set({ importProgress: null });
     }
   },
 
+  // The running import stops after its current file and keeps what it imported
+  cancelImport: async () => {
+    await invoke("cancel_import_cmd");
+  },
+
   addFolderToCollection: async (collectionName: string, path: string) => {
-    set({ isLoading: true });
+    set({ isLoading: true, importProgress: { current: 0, total: 0, path } });
+    const unlisten = await listen<ImportProgress>("import-progress", (event) =>
+      set({ importProgress: event.payload }),
+    );
     try {
-      await invoke("import_folder_cmd", { path, collectionName });
+      await invoke("import_folder_cmd", {
+        path,
+        collectionName,
+        excludeGlobs: [],
+      });
       // Refresh if currently loaded
       if (get().loadedCollections.includes(collectionName)) {
         await get().fetchResourcesForLoadedCollections();
@@ -242,6 +278,9 @@ export const useDatabaseStore = create<DatabaseState>((set, get) => ({
       set({ isLoading: false });
     } catch (err: any) {
       set({ error: err.toString(), isLoading: false });
+    } finally {
+      unlisten();
+      set({ importProgress: null });
     }
   },
 
@@ -283,13 +322,77 @@ export const useDatabaseStore = create<DatabaseState>((set, get) => ({
     }
   },
 
+  renameCollection: async (oldName: string, newName: string) => {
+    set({ isLoading: true, error: null });
+    try {
+      await invoke("rename_collection_cmd", { oldName, newName });
+
+      // Keep the active and loaded sets pointing at the new name
+      const { loadedCollections, activeCollection } = get();
+      if (activeCollection === oldName) {
+        set({ activeCollection: newName });
+      }
+      if (loadedCollections.includes(oldName)) {
+        set({
+          loadedCollections: loadedCollections.map((c) =>
+            c === oldName ? newName : c,
+          ),
+        });
+        await get().fetchResourcesForLoadedCollections();
+      }
+
+      await get().fetchCollections();
+
+      set({ isLoading: false });
+    } catch (err: any) {
+      set({ error: err.toString(), isLoading: false });
+      throw err;
+    }
+  },
+
+  moveResources: async (ids: string[], targetCollection: string) => {
+    set({ isLoading: true, error: null });
+    try {
+      await invoke("move_resources_cmd", { ids, targetCollection });
+      await get().fetchResourcesForLoadedCollections();
+
+      const { activeCollection } = get();
+      if (activeCollection) {
+        await get().selectCollection(activeCollection);
+      }
+      set({ isLoading: false });
+    } catch (err: any) {
+      set({ error: err.toString(), isLoading: false });
+      throw err;
+    }
+  },
+
   activeResourceId: null as string | null,
   selectResource: (id: string | null) => set({ activeResourceId: id }),
 
-  deleteResource: async (id: string) => {
+  deleteResource: async (id: string, force = false) => {
     set({ isLoading: true, error: null });
     try {
-      await invoke("delete_resource_cmd", { id });
+      const dependents = await invoke<Resource[]>("delete_resource_cmd", {
+        id,
+        force,
+      });
+
+      // Other resources still use this one; nothing was deleted yet
+      if (dependents.length > 0) {
+        set({ isLoading: false });
+        const names = dependents
+          .map((r) => r.title || r.path.split(/[\\/]/).pop())
+          .join(", ");
+        if (
+          window.confirm(
+            `${dependents.length} resource(s) depend on this one: ${names}. Delete anyway?`,
+          )
+        ) {
+          await get().deleteResource(id, true);
+        }
+        return;
+      }
 
       // Remove from local state to avoid full re-fetch
       const { resources, allLoadedResources, activeResourceId } = get();
@@ -347,6 +450,8 @@ export const useDatabaseStore = create<DatabaseState>((set, get) => ({
     set({ isLoading: true, error: null });
     try {
       await invoke("import_file_cmd", { path, collectionName: collection });
+      // Pick up the new folder in the backend's resource file watcher
+      await invoke("watch_resources_cmd");
       // Refresh to show new file
       await get().fetchResourcesForLoadedCollections();
       set({ isLoading: false });